@@ -0,0 +1,11 @@
+//! The color-cycle example's drawing code, shared by the binary and the tests.
+
+use gr_support::{gl, gl_check};
+
+/// Fades from red at `progress` 0 to green at 1. The frame is left in the back buffer, to be
+/// captured before it is swapped.
+pub fn draw(progress: f32) -> gr_support::Result<()> {
+  gl::clear_color(1.0_f32 - progress, progress, 0.0, 1.0);
+  gl::clear(gl::GL_COLOR_BUFFER_BIT);
+  gl_check()
+}
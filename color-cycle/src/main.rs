@@ -1,33 +1,91 @@
-use gr_context::Context;
-use opengles::glesv2 as gl;
-use std::thread;
-use std::time::{Duration, Instant};
+use clap::Parser;
+use color_cycle::draw;
+use gr_support::args::seconds;
+use gr_support::capture::Capture;
+use gr_support::clock::{FrameClock, VirtualClock};
+use gr_support::gpu::Gpu;
+use gr_support::record::Recorder;
+use gr_support::shutdown;
+use gr_support::GlContext;
+use std::process;
+use std::time::Duration;
 
-const STEPS: u32 = 180;
-const MILLIS_PER_FRAME: Duration = Duration::from_millis((1000_f64 / 60_f64) as u64);
+const FPS: u32 = 60;
 
-pub fn draw(context: &mut Context, progress: f32) {
-  gl::clear_color(1.0_f32 - progress, progress, 0.0, 1.0);
-  gl::clear(gl::GL_COLOR_BUFFER_BIT);
-  context.swap_buffers();
+/// Fades the screen from red to green.
+#[derive(Parser)]
+#[clap(after_help = gr_support::ENV_HELP)]
+struct Args {
+  /// How long the fade takes.
+  #[clap(long, value_name = "SECONDS", value_parser = seconds, default_value = "3")]
+  duration: Duration,
+  /// Stop after N frames, even if the fade is not over.
+  #[clap(long, value_name = "N")]
+  frames: Option<u64>,
+  /// Save screenshots to PATH instead of color-cycle-{frame}.png.
+  #[clap(long, value_name = "PATH")]
+  capture: Option<String>,
+  /// Print how many frames were drawn and missed.
+  #[clap(short, long, action = clap::ArgAction::Count)]
+  verbose: u8,
 }
 
-fn main() {
-  let mut context = Context::new();
+fn run(context: &GlContext, args: &Args) -> gr_support::Result<()> {
+  let mut capture = Capture::from_env("color-cycle")?;
+  if let Some(template) = &args.capture {
+    capture = capture.template(template);
+  }
+  let mut recorder = Recorder::from_env(context)?;
+  // A recording gets as many frames as its rate needs, as fast as they can be read back
+  let mut clock = match &recorder {
+    Some(recorder) => FrameClock::with_clock(recorder.fps(), VirtualClock::new()),
+    None => FrameClock::new(FPS),
+  };
+  let mut frames = 0;
+  loop {
+    let time = clock.tick().time;
+    if time >= args.duration || args.frames == Some(frames) || shutdown::requested() {
+      break;
+    }
+    draw(time.as_secs_f32() / args.duration.as_secs_f32())?;
+    if let Some(path) = capture.frame(context)? {
+      println!("Saved {}", path.display());
+    }
+    if let Some(recorder) = &mut recorder {
+      recorder.frame(context)?;
+    }
+    context.swap_buffers();
+    frames += 1;
+    clock.wait();
+  }
 
-  for i in 0..STEPS {
-    let start = Instant::now();
-    draw(&mut context, i as f32 / STEPS as f32);
+  if args.verbose > 0 {
+    println!(
+      "{} frames, {} missed, {:.2} ms on average",
+      frames,
+      clock.missed(),
+      clock.average_frame_time().as_secs_f64() * 1000.0
+    );
+  }
+  Ok(())
+}
 
-    let end = Instant::now();
+fn main() {
+  let args = Args::parse();
+  shutdown::install();
+
+  // Before creating the context, which fails when the wrong driver is linked
+  if let Some(mismatch) = Gpu::detect().driver_mismatch() {
+    eprintln!("warning: {}", mismatch);
+  }
 
-    match start
-      .checked_add(MILLIS_PER_FRAME)
-      .expect("Can always add 16ms")
-      .checked_duration_since(end)
-    {
-      Some(sleep) => thread::sleep(sleep),
-      None => {}
-    };
+  // The context is dropped by the time this returns, restoring the display
+  let result = GlContext::from_env().and_then(|context| run(&context, &args));
+  if let Err(err) = result {
+    eprintln!("{}", err);
+    process::exit(1);
+  }
+  if let Some(code) = shutdown::exit_code() {
+    process::exit(code);
   }
 }
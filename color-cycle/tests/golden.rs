@@ -0,0 +1,30 @@
+//! Frames of the fade on the software rasterizer, against the PNGs in `tests/golden`.
+//!
+//! `GR_UPDATE_GOLDEN=1 cargo test` replaces the references with what is drawn now.
+
+use gr_support::golden::Golden;
+use gr_support::image::Image;
+use gr_support::soft::Soft;
+use gr_support::GlContext;
+
+fn golden() -> Golden {
+  Golden::new(
+    concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"),
+    concat!(env!("CARGO_TARGET_TMPDIR"), "/golden"),
+  )
+}
+
+#[test]
+fn fades_from_red_to_green() {
+  let context = GlContext::with_target(Soft::new(32, 24));
+  for (name, progress) in [
+    ("progress-0", 0.0),
+    ("progress-25", 0.25),
+    ("progress-50", 0.5),
+    ("progress-75", 0.75),
+    ("progress-100", 1.0),
+  ] {
+    color_cycle::draw(progress).unwrap();
+    golden().assert(name, &Image::read(&context).unwrap());
+  }
+}
@@ -0,0 +1,160 @@
+//! `#[derive(ShaderBindings)]`, re-exported by `gr-support`.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
+
+/// Fills a struct of `Attribute<T>` / `Uniform<T>` fields from a linked `ShaderProgram`.
+///
+/// Each field is looked up by its own name, or by `#[shader(name = "...")]`. A
+/// `#[shader(rename_all = "camelCase")]` on the struct turns `model_view_matrix` into
+/// `modelViewMatrix` for every field without an explicit name.
+#[proc_macro_derive(ShaderBindings, attributes(shader))]
+pub fn derive_shader_bindings(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  match expand(input) {
+    Ok(tokens) => tokens.into(),
+    Err(err) => err.to_compile_error().into(),
+  }
+}
+
+#[derive(Default)]
+struct ShaderAttr {
+  name: Option<String>,
+  rename_all: Option<String>,
+}
+
+fn shader_attr(attrs: &[syn::Attribute]) -> syn::Result<ShaderAttr> {
+  let mut result = ShaderAttr::default();
+
+  for attr in attrs.iter().filter(|attr| attr.path.is_ident("shader")) {
+    let list = match attr.parse_meta()? {
+      Meta::List(list) => list,
+      meta => return Err(syn::Error::new(meta.span(), "expected #[shader(...)]")),
+    };
+
+    for nested in list.nested {
+      let pair = match nested {
+        NestedMeta::Meta(Meta::NameValue(pair)) => pair,
+        nested => return Err(syn::Error::new(nested.span(), "expected `key = \"value\"`")),
+      };
+      let value = match &pair.lit {
+        Lit::Str(value) => value.value(),
+        lit => return Err(syn::Error::new(lit.span(), "expected a string")),
+      };
+
+      if pair.path.is_ident("name") {
+        result.name = Some(value);
+      } else if pair.path.is_ident("rename_all") {
+        if value != "camelCase" {
+          return Err(syn::Error::new(
+            pair.lit.span(),
+            "only `rename_all = \"camelCase\"` is supported",
+          ));
+        }
+        result.rename_all = Some(value);
+      } else {
+        return Err(syn::Error::new(pair.path.span(), "unknown shader attribute"));
+      }
+    }
+  }
+
+  Ok(result)
+}
+
+fn camel_case(name: &str) -> String {
+  let mut result = String::with_capacity(name.len());
+  let mut upper = false;
+  for c in name.chars() {
+    if c == '_' {
+      upper = !result.is_empty();
+    } else if upper {
+      result.extend(c.to_uppercase());
+      upper = false;
+    } else {
+      result.push(c);
+    }
+  }
+  result
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+  let container = shader_attr(&input.attrs)?;
+  if container.name.is_some() {
+    return Err(syn::Error::new(
+      input.ident.span(),
+      "`name` only applies to fields",
+    ));
+  }
+
+  let fields = match &input.data {
+    Data::Struct(data) => match &data.fields {
+      Fields::Named(fields) => &fields.named,
+      _ => {
+        return Err(syn::Error::new(
+          input.ident.span(),
+          "ShaderBindings needs a struct with named fields",
+        ))
+      }
+    },
+    _ => {
+      return Err(syn::Error::new(
+        Span::call_site(),
+        "ShaderBindings can only be derived for structs",
+      ))
+    }
+  };
+
+  let mut binds = Vec::new();
+  let mut inits = Vec::new();
+  for field in fields {
+    let ident = field.ident.as_ref().expect("named field");
+    let ty = &field.ty;
+    let attr = shader_attr(&field.attrs)?;
+    if attr.rename_all.is_some() {
+      return Err(syn::Error::new(
+        field.span(),
+        "`rename_all` only applies to the struct",
+      ));
+    }
+
+    let field_name = ident.to_string();
+    let field_name = field_name.trim_start_matches("r#");
+    let name = match (attr.name, &container.rename_all) {
+      (Some(name), _) => name,
+      (None, Some(_)) => camel_case(field_name),
+      (None, None) => field_name.to_string(),
+    };
+
+    binds.push(quote! {
+      let #ident = ::gr_support::bindings::bind_field::<#ty>(program, #name, &mut errors);
+    });
+    inits.push(quote! {
+      #ident: #ident.expect("bound without errors"),
+    });
+  }
+
+  let name = &input.ident;
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+  Ok(quote! {
+    impl #impl_generics ::gr_support::ShaderBindings for #name #ty_generics #where_clause {
+      fn bind(program: &::gr_support::ShaderProgram<'_>) -> ::gr_support::Result<Self> {
+        let mut errors = ::std::vec::Vec::new();
+        #(#binds)*
+
+        if !errors.is_empty() {
+          return ::std::result::Result::Err(::gr_support::Error::Bindings(errors));
+        }
+
+        ::std::result::Result::Ok(#name {
+          #(#inits)*
+        })
+      }
+    }
+  })
+}
@@ -0,0 +1,37 @@
+//! Arbitrary bytes through the PS/2 decoder, whole and split where the first byte says.
+
+#![no_main]
+
+use gr_support::input::ps2::{Decoder, Protocol};
+use gr_support::input::Event;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  let (split, bytes) = match data.split_first() {
+    Some((&split, bytes)) => (split as usize % 7 + 1, bytes),
+    None => return,
+  };
+
+  for &protocol in &[Protocol::Standard, Protocol::IntelliMouse] {
+    let mut whole = Vec::new();
+    Decoder::new(protocol).feed(bytes, &mut whole);
+
+    let mut chunked = Vec::new();
+    let mut decoder = Decoder::new(protocol);
+    for chunk in bytes.chunks(split) {
+      decoder.feed(chunk, &mut chunked);
+    }
+    assert_eq!(whole, chunked);
+
+    for event in whole {
+      match event {
+        Event::Motion { dx, dy } => {
+          assert!((-256..=255).contains(&dx) && (-255..=256).contains(&dy))
+        }
+        Event::Wheel { vertical, .. } => assert!((-127..=128).contains(&vertical)),
+        Event::Button { .. } => {}
+        other => panic!("{:?}", other),
+      }
+    }
+  }
+});
@@ -0,0 +1,32 @@
+//! Parsers for the command line values the examples share, to pass to `clap` as
+//! `value_parser`s.
+
+use std::time::Duration;
+
+/// A non-negative number of seconds, e.g. `2.5`.
+pub fn seconds(value: &str) -> Result<Duration, String> {
+  match value.parse::<f64>() {
+    Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(Duration::from_secs_f64(seconds)),
+    _ => Err(format!("expected a number of seconds, not {:?}", value)),
+  }
+}
+
+/// Two numbers separated by a comma, e.g. `800,400`.
+pub fn point(value: &str) -> Result<(f32, f32), String> {
+  let error = || format!("expected X,Y, not {:?}", value);
+  let (x, y) = value.split_once(',').ok_or_else(error)?;
+  match (x.trim().parse::<f32>(), y.trim().parse::<f32>()) {
+    (Ok(x), Ok(y)) if x.is_finite() && y.is_finite() => Ok((x, y)),
+    _ => Err(error()),
+  }
+}
+
+/// Two positive whole numbers separated by an `x`, e.g. `640x480`.
+pub fn size(value: &str) -> Result<(u32, u32), String> {
+  let error = || format!("expected WIDTHxHEIGHT, not {:?}", value);
+  let (width, height) = value.split_once('x').ok_or_else(error)?;
+  match (width.parse::<u32>(), height.parse::<u32>()) {
+    (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+    _ => Err(error()),
+  }
+}
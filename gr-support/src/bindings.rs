@@ -0,0 +1,277 @@
+//! Typed attribute and uniform locations, filled in by `#[derive(ShaderBindings)]`.
+//!
+//! ```ignore
+//! #[derive(ShaderBindings)]
+//! #[shader(rename_all = "camelCase")]
+//! struct Locations {
+//!   vertex_position: Attribute<Vec3>,
+//!   projection_matrix: Uniform<Mat4>,
+//!   // Declared by the shader but unused, so the driver is free to drop it
+//!   color: Option<Uniform<Vec4>>,
+//! }
+//!
+//! let locations: Locations = program.bind()?;
+//! ```
+
+use crate::gl;
+use crate::{Result, ShaderProgram, Variable};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A struct whose fields are bound to a program's attributes and uniforms.
+pub trait ShaderBindings: Sized {
+  /// Binds every field, or reports every field that is missing or has the wrong type.
+  fn bind(program: &ShaderProgram<'_>) -> Result<Self>;
+}
+
+/// A GLSL type, named after the GLSL keyword.
+pub trait GlslType {
+  /// The `GL_FLOAT_VEC4`-style enum reported by `glGetActiveAttrib` and `glGetActiveUniform`.
+  const GL_TYPE: gl::GLenum;
+  const NAME: &'static str;
+}
+
+macro_rules! glsl_types {
+  ($($name:ident => $gl_type:ident, $keyword:literal;)*) => {
+    $(
+      #[derive(Clone, Copy, Debug)]
+      pub struct $name;
+
+      impl GlslType for $name {
+        const GL_TYPE: gl::GLenum = gl::$gl_type;
+        const NAME: &'static str = $keyword;
+      }
+    )*
+
+    /// GLSL spelling of a `glGetActive*` type, e.g. `vec4` for `GL_FLOAT_VEC4`.
+    pub fn glsl_type_name(type_: gl::GLenum) -> Option<&'static str> {
+      match type_ {
+        $(gl::$gl_type => Some($keyword),)*
+        _ => None,
+      }
+    }
+
+    /// The `glGetActive*` type of a GLSL type keyword, e.g. `GL_FLOAT_VEC4` for `vec4`.
+    pub fn glsl_type(keyword: &str) -> Option<gl::GLenum> {
+      match keyword {
+        $($keyword => Some(gl::$gl_type),)*
+        _ => None,
+      }
+    }
+  };
+}
+
+glsl_types! {
+  Float => GL_FLOAT, "float";
+  Vec2 => GL_FLOAT_VEC2, "vec2";
+  Vec3 => GL_FLOAT_VEC3, "vec3";
+  Vec4 => GL_FLOAT_VEC4, "vec4";
+  Int => GL_INT, "int";
+  IVec2 => GL_INT_VEC2, "ivec2";
+  IVec3 => GL_INT_VEC3, "ivec3";
+  IVec4 => GL_INT_VEC4, "ivec4";
+  Bool => GL_BOOL, "bool";
+  Mat2 => GL_FLOAT_MAT2, "mat2";
+  Mat3 => GL_FLOAT_MAT3, "mat3";
+  Mat4 => GL_FLOAT_MAT4, "mat4";
+  Sampler2D => GL_SAMPLER_2D, "sampler2D";
+  SamplerCube => GL_SAMPLER_CUBE, "samplerCube";
+}
+
+// ----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingKind {
+  Attribute,
+  Uniform,
+}
+
+impl fmt::Display for BindingKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BindingKind::Attribute => write!(f, "attribute"),
+      BindingKind::Uniform => write!(f, "uniform"),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BindingError {
+  /// The program has no active variable of this name.
+  Missing { kind: BindingKind, name: String },
+  /// The variable exists but its GLSL type is not the one the field asks for.
+  Mistyped {
+    kind: BindingKind,
+    name: String,
+    expected: gl::GLenum,
+    found: gl::GLenum,
+  },
+}
+
+impl fmt::Display for BindingError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let type_name = |type_: gl::GLenum| match glsl_type_name(type_) {
+      Some(name) => name.to_string(),
+      None => format!("{:#06x}", type_),
+    };
+
+    match self {
+      BindingError::Missing { kind, name } => write!(f, "{} {} is not active", kind, name),
+      BindingError::Mistyped {
+        kind,
+        name,
+        expected,
+        found,
+      } => write!(
+        f,
+        "{} {} is a {}, expected a {}",
+        kind,
+        name,
+        type_name(*found),
+        type_name(*expected)
+      ),
+    }
+  }
+}
+
+impl std::error::Error for BindingError {}
+
+/// A field of a [`ShaderBindings`] struct.
+pub trait Binding: Sized {
+  fn bind(program: &ShaderProgram<'_>, name: &str) -> std::result::Result<Self, BindingError>;
+}
+
+fn lookup(
+  kind: BindingKind,
+  variable: Option<&Variable>,
+  name: &str,
+  expected: gl::GLenum,
+) -> std::result::Result<gl::GLint, BindingError> {
+  let variable = variable.ok_or_else(|| BindingError::Missing {
+    kind,
+    name: name.to_string(),
+  })?;
+
+  if variable.type_ != expected {
+    return Err(BindingError::Mistyped {
+      kind,
+      name: name.to_string(),
+      expected,
+      found: variable.type_,
+    });
+  }
+
+  Ok(variable.location)
+}
+
+/// Location of an active attribute of GLSL type `T`.
+#[derive(Debug)]
+pub struct Attribute<T> {
+  pub location: gl::GLuint,
+  type_: PhantomData<T>,
+}
+
+impl<T> Clone for Attribute<T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T> Copy for Attribute<T> {}
+
+impl<T: GlslType> Binding for Attribute<T> {
+  fn bind(program: &ShaderProgram<'_>, name: &str) -> std::result::Result<Self, BindingError> {
+    let location = lookup(
+      BindingKind::Attribute,
+      program.attribute(name),
+      name,
+      T::GL_TYPE,
+    )?;
+    Ok(Attribute {
+      location: location as gl::GLuint,
+      type_: PhantomData,
+    })
+  }
+}
+
+/// Location of an active uniform of GLSL type `T`.
+#[derive(Debug)]
+pub struct Uniform<T> {
+  pub location: gl::GLint,
+  type_: PhantomData<T>,
+}
+
+impl<T> Clone for Uniform<T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T> Copy for Uniform<T> {}
+
+impl<T: GlslType> Binding for Uniform<T> {
+  fn bind(program: &ShaderProgram<'_>, name: &str) -> std::result::Result<Self, BindingError> {
+    let location = lookup(
+      BindingKind::Uniform,
+      program.uniform(name),
+      name,
+      T::GL_TYPE,
+    )?;
+    Ok(Uniform {
+      location,
+      type_: PhantomData,
+    })
+  }
+}
+
+impl Uniform<Int> {
+  pub fn set(&self, x: gl::GLint) {
+    gl::uniform1i(self.location, x);
+  }
+}
+
+impl Uniform<Vec2> {
+  pub fn set(&self, x: gl::GLfloat, y: gl::GLfloat) {
+    gl::uniform2f(self.location, x, y);
+  }
+}
+
+impl Uniform<Vec4> {
+  pub fn set(&self, x: gl::GLfloat, y: gl::GLfloat, z: gl::GLfloat, w: gl::GLfloat) {
+    gl::uniform4f(self.location, x, y, z, w);
+  }
+}
+
+impl Uniform<Mat4> {
+  pub fn set(&self, value: &[gl::GLfloat; 16]) {
+    gl::uniform_matrix4fv(self.location, false, value);
+  }
+}
+
+impl Uniform<Sampler2D> {
+  /// Points the sampler at a texture unit (0 for `GL_TEXTURE0`).
+  pub fn set(&self, unit: gl::GLint) {
+    gl::uniform1i(self.location, unit);
+  }
+}
+
+/// Drivers drop variables the shader never reads, so fields that may legitimately vanish can
+/// be optional. A variable that exists with the wrong type is still an error.
+impl<B: Binding> Binding for Option<B> {
+  fn bind(program: &ShaderProgram<'_>, name: &str) -> std::result::Result<Self, BindingError> {
+    match B::bind(program, name) {
+      Ok(binding) => Ok(Some(binding)),
+      Err(BindingError::Missing { .. }) => Ok(None),
+      Err(err) => Err(err),
+    }
+  }
+}
+
+#[doc(hidden)]
+pub fn bind_field<B: Binding>(
+  program: &ShaderProgram<'_>,
+  name: &str,
+  errors: &mut Vec<BindingError>,
+) -> Option<B> {
+  B::bind(program, name).map_err(|err| errors.push(err)).ok()
+}
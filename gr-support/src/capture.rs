@@ -0,0 +1,144 @@
+//! Screenshots of the frames a demo draws, taken when asked for.
+//!
+//! ```ignore
+//! let mut capture = Capture::from_env("triangle")?;
+//! loop {
+//!   draw(&context)?;
+//!   if let Some(path) = capture.frame(&context)? {
+//!     println!("Saved {}", path.display());
+//!   }
+//!   context.swap_buffers();
+//! }
+//! ```
+//!
+//! A frame is saved when Enter is pressed, on `SIGUSR1` (`pkill -USR1 triangle`), or every N
+//! frames.
+
+use crate::image::Image;
+use crate::{Error, GlContext, Result};
+use std::env;
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::thread;
+
+/// Set by the `SIGUSR1` handler and the thread reading stdin.
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+static LISTEN: Once = Once::new();
+
+#[cfg(feature = "png")]
+const DEFAULT_EXTENSION: &str = "png";
+#[cfg(not(feature = "png"))]
+const DEFAULT_EXTENSION: &str = "ppm";
+
+/// Saves frames to numbered files.
+#[derive(Clone, Debug)]
+pub struct Capture {
+  template: String,
+  every: Option<u64>,
+  on_request: bool,
+  frames: u64,
+}
+
+impl Capture {
+  /// Saves frames to `template`, with `{frame}` replaced by the frame number, counting from 1.
+  /// The extension picks the format, see [`Image::save`]. Nothing is saved until asked for with
+  /// [`every`](Capture::every) or [`on_request`](Capture::on_request).
+  pub fn new<S: Into<String>>(template: S) -> Capture {
+    Capture {
+      template: template.into(),
+      every: None,
+      on_request: false,
+      frames: 0,
+    }
+  }
+
+  /// Saves [`new`](Capture::new) frames to `GR_CAPTURE`, `NAME-{frame}.png` by default (`.ppm`
+  /// without the `png` feature), on request and every `GR_CAPTURE_EVERY` frames if set.
+  pub fn from_env(name: &str) -> Result<Capture> {
+    let template = env::var("GR_CAPTURE")
+      .unwrap_or_else(|_| format!("{}-{{frame}}.{}", name, DEFAULT_EXTENSION));
+    let capture = Capture::new(template).on_request();
+    match env::var("GR_CAPTURE_EVERY") {
+      Ok(every) => match every.parse() {
+        Ok(every) if every > 0 => Ok(capture.every(every)),
+        _ => Err(Error::CaptureEvery(every)),
+      },
+      Err(_) => Ok(capture),
+    }
+  }
+
+  /// Saves to `template` instead, e.g. for a path given on the command line.
+  pub fn template<S: Into<String>>(self, template: S) -> Capture {
+    Capture {
+      template: template.into(),
+      ..self
+    }
+  }
+
+  /// Saves every `frames`th frame.
+  pub fn every(self, frames: u64) -> Capture {
+    Capture {
+      every: Some(frames),
+      ..self
+    }
+  }
+
+  /// Saves the next frame after Enter is pressed or the process gets `SIGUSR1`. The first call
+  /// installs the signal handler and starts a thread reading stdin.
+  pub fn on_request(self) -> Capture {
+    LISTEN.call_once(listen);
+    Capture {
+      on_request: true,
+      ..self
+    }
+  }
+
+  /// Where frame number `frame` is saved.
+  pub fn path(&self, frame: u64) -> PathBuf {
+    PathBuf::from(self.template.replace("{frame}", &format!("{:06}", frame)))
+  }
+
+  /// Counts a frame, and saves it if asked to. Call it once the frame is drawn, before swapping
+  /// buffers, which leaves the back buffer undefined. Returns where the frame was saved.
+  pub fn frame(&mut self, context: &GlContext) -> Result<Option<PathBuf>> {
+    self.frames += 1;
+    let every = self
+      .every
+      .is_some_and(|every| self.frames.is_multiple_of(every));
+    let requested = self.on_request && REQUESTED.swap(false, Ordering::SeqCst);
+    if !every && !requested {
+      return Ok(None);
+    }
+
+    let path = self.path(self.frames);
+    Image::read(context)?.save(&path)?;
+    Ok(Some(path))
+  }
+}
+
+/// Saves the next frame, as Enter and `SIGUSR1` do, for a key bound to it.
+pub fn request() {
+  REQUESTED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn on_signal(_signal: libc::c_int) {
+  request();
+}
+
+fn listen() {
+  unsafe {
+    libc::signal(
+      libc::SIGUSR1,
+      on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t,
+    )
+  };
+
+  // Stops at the end of stdin, which is right away for /dev/null and a raw `input::tty::Terminal`
+  thread::spawn(|| {
+    for _ in io::stdin().lock().lines() {
+      REQUESTED.store(true, Ordering::SeqCst);
+    }
+  });
+}
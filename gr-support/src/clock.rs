@@ -0,0 +1,257 @@
+//! Paces a render loop at a target frame rate.
+//!
+//! ```ignore
+//! let mut clock = FrameClock::new(60).fixed_timestep(Duration::from_millis(10));
+//! loop {
+//!   let tick = clock.tick();
+//!   for _ in 0..tick.updates {
+//!     world.update(Duration::from_millis(10));
+//!   }
+//!   draw(&world, tick.alpha)?;
+//!   context.swap_buffers();
+//!   clock.wait();
+//! }
+//! ```
+//!
+//! Frames are due at fixed points from the start, `n / fps` seconds in, rather than a period
+//! after the previous frame ended, so rounding and oversleeping do not add up over time.
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Where a [`FrameClock`] gets the time from, and how it waits.
+pub trait Clock {
+  /// Time since some fixed point, which never goes back.
+  fn now(&self) -> Duration;
+  fn sleep(&self, duration: Duration);
+}
+
+/// The monotonic system clock, `Instant`.
+pub struct SystemClock {
+  start: Instant,
+}
+
+impl SystemClock {
+  pub fn new() -> SystemClock {
+    SystemClock {
+      start: Instant::now(),
+    }
+  }
+}
+
+impl Default for SystemClock {
+  fn default() -> Self {
+    SystemClock::new()
+  }
+}
+
+impl Clock for SystemClock {
+  fn now(&self) -> Duration {
+    self.start.elapsed()
+  }
+
+  fn sleep(&self, duration: Duration) {
+    thread::sleep(duration);
+  }
+}
+
+/// A clock that only moves when slept on, and then right away. Clones share the time.
+///
+/// For tests, and for recordings, where every frame should take exactly its share of the video
+/// however long it took to draw and read back.
+#[derive(Clone, Debug, Default)]
+pub struct VirtualClock {
+  now: Rc<Cell<Duration>>,
+}
+
+impl VirtualClock {
+  pub fn new() -> VirtualClock {
+    VirtualClock::default()
+  }
+
+  /// Moves the time on, as if `duration` passed while working.
+  pub fn advance(&self, duration: Duration) {
+    self.now.set(self.now.get() + duration);
+  }
+}
+
+impl Clock for VirtualClock {
+  fn now(&self) -> Duration {
+    self.now.get()
+  }
+
+  fn sleep(&self, duration: Duration) {
+    self.advance(duration);
+  }
+}
+
+/// What happened since the previous [`tick`](FrameClock::tick).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tick {
+  /// Counting from 0.
+  pub frame: u64,
+  /// Since the clock was created.
+  pub time: Duration,
+  /// Since the previous tick, zero on the first.
+  pub delta: Duration,
+  /// How many fixed timesteps to run, always 1 without a fixed timestep.
+  pub updates: u32,
+  /// How far into the next fixed timestep the frame is, from 0 to 1, to interpolate between the
+  /// previous state and the current one. Always 1 without a fixed timestep.
+  pub alpha: f32,
+}
+
+/// The fixed timestep, and the time not yet consumed by it.
+struct FixedStep {
+  step: Duration,
+  max_updates: u32,
+  accumulator: Duration,
+}
+
+/// How many frame times [`FrameClock`] keeps.
+const FRAME_TIMES: usize = 120;
+
+/// Paces frames at a target rate, see the [module documentation](self).
+pub struct FrameClock {
+  clock: Box<dyn Clock>,
+  fps: u32,
+  start: Duration,
+  frame: u64,
+  last_tick: Option<Duration>,
+  fixed: Option<FixedStep>,
+  frame_times: VecDeque<Duration>,
+  missed: u64,
+}
+
+impl FrameClock {
+  /// Paces frames at `fps` frames a second, by the system clock.
+  pub fn new(fps: u32) -> FrameClock {
+    FrameClock::with_clock(fps, SystemClock::new())
+  }
+
+  /// Paces frames at `fps` frames a second, by `clock`.
+  pub fn with_clock<C: Clock + 'static>(fps: u32, clock: C) -> FrameClock {
+    assert!(fps > 0, "a frame clock needs a rate");
+    let start = clock.now();
+    FrameClock {
+      clock: Box::new(clock),
+      fps,
+      start,
+      frame: 0,
+      last_tick: None,
+      fixed: None,
+      frame_times: VecDeque::with_capacity(FRAME_TIMES),
+      missed: 0,
+    }
+  }
+
+  /// Runs updates in steps of `step`, as many as the time since the previous tick holds, but no
+  /// more than 5 a frame so a slow frame cannot snowball.
+  pub fn fixed_timestep(self, step: Duration) -> FrameClock {
+    assert!(step > Duration::ZERO, "a fixed timestep cannot be zero");
+    FrameClock {
+      fixed: Some(FixedStep {
+        step,
+        max_updates: 5,
+        accumulator: Duration::ZERO,
+      }),
+      ..self
+    }
+  }
+
+  pub fn fps(&self) -> u32 {
+    self.fps
+  }
+
+  /// The time between frames, rounded down to the nanosecond.
+  pub fn period(&self) -> Duration {
+    Duration::from_secs(1) / self.fps
+  }
+
+  /// When frame `frame` is due, from the start.
+  fn due(&self, frame: u64) -> Duration {
+    Duration::from_nanos(frame * 1_000_000_000 / self.fps as u64)
+  }
+
+  /// Starts a frame.
+  pub fn tick(&mut self) -> Tick {
+    let now = self.clock.now();
+    let delta = match self.last_tick {
+      Some(last) => now.saturating_sub(last),
+      None => Duration::ZERO,
+    };
+    if self.last_tick.is_some() {
+      if self.frame_times.len() == FRAME_TIMES {
+        self.frame_times.pop_front();
+      }
+      self.frame_times.push_back(delta);
+    }
+    self.last_tick = Some(now);
+
+    let (updates, alpha) = match &mut self.fixed {
+      Some(fixed) => {
+        fixed.accumulator += delta;
+        let mut updates = 0;
+        while fixed.accumulator >= fixed.step && updates < fixed.max_updates {
+          fixed.accumulator -= fixed.step;
+          updates += 1;
+        }
+        if updates == fixed.max_updates {
+          // Whatever is left is dropped rather than caught up on
+          fixed.accumulator = fixed.accumulator.min(fixed.step);
+        }
+        let alpha = fixed.accumulator.as_secs_f32() / fixed.step.as_secs_f32();
+        (updates, alpha.min(1.0))
+      }
+      None => (1, 1.0),
+    };
+
+    Tick {
+      frame: self.frame,
+      time: now - self.start,
+      delta,
+      updates,
+      alpha,
+    }
+  }
+
+  /// Ends a frame, sleeping until the next one is due. A frame that ran past the next deadline
+  /// counts as missed, and the schedule moves on to the first deadline still ahead instead of
+  /// rushing to catch up.
+  pub fn wait(&mut self) {
+    self.frame += 1;
+    let elapsed = self.clock.now() - self.start;
+    let due = self.due(self.frame);
+    if elapsed <= due {
+      self.clock.sleep(due - elapsed);
+      return;
+    }
+
+    self.missed += 1;
+    let period = self.period().as_nanos().max(1);
+    let behind = ((elapsed - due).as_nanos() / period) as u64 + 1;
+    self.frame += behind;
+    self.clock.sleep(self.due(self.frame) - elapsed);
+  }
+
+  /// Frames that ran past the deadline of the next one.
+  pub fn missed(&self) -> u64 {
+    self.missed
+  }
+
+  /// The times between the last ticks, up to 120 of them, oldest first.
+  pub fn frame_times(&self) -> impl Iterator<Item = Duration> + '_ {
+    self.frame_times.iter().copied()
+  }
+
+  /// The average of [`frame_times`](FrameClock::frame_times), zero before the second tick.
+  pub fn average_frame_time(&self) -> Duration {
+    if self.frame_times.is_empty() {
+      return Duration::ZERO;
+    }
+    self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32
+  }
+}
@@ -0,0 +1,78 @@
+use crate::{args, Error, Headless, RenderTarget, Result};
+use gr_context::Context;
+use std::cell::RefCell;
+use std::env;
+
+/// A [`RenderTarget`] that GL objects can borrow.
+///
+/// `Buffer`, `Texture`, `Shader` and friends hold a `&'ctx GlContext` lifetime so they cannot
+/// outlive the context they were created in. `RenderTarget::swap_buffers` takes `&mut self`,
+/// which those borrows would forbid, so this wrapper swaps through a shared reference instead.
+pub struct GlContext {
+  target: RefCell<Box<dyn RenderTarget>>,
+}
+
+impl GlContext {
+  /// Draws to the display, through `gr_context`.
+  pub fn new() -> GlContext {
+    GlContext::from(Context::new())
+  }
+
+  /// Draws to an offscreen `width` x `height` surface, see [`Headless`].
+  pub fn headless(width: u32, height: u32) -> Result<GlContext> {
+    Ok(GlContext::with_target(Headless::new(width, height)?))
+  }
+
+  /// [`headless`](GlContext::headless) when `GR_HEADLESS` is set to `WIDTHxHEIGHT` (or to
+  /// nothing, for 640x480), [`new`](GlContext::new) otherwise.
+  pub fn from_env() -> Result<GlContext> {
+    match env::var("GR_HEADLESS") {
+      Ok(size) => {
+        let (width, height) = match size.as_str() {
+          "" => (640, 480),
+          _ => args::size(&size).map_err(|_| Error::HeadlessSize(size))?,
+        };
+        GlContext::headless(width, height)
+      }
+      Err(_) => Ok(GlContext::new()),
+    }
+  }
+
+  pub fn with_target<T: RenderTarget + 'static>(target: T) -> GlContext {
+    GlContext {
+      target: RefCell::new(Box::new(target)),
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.target.borrow().width()
+  }
+
+  pub fn height(&self) -> u32 {
+    self.target.borrow().height()
+  }
+
+  pub fn egl_version(&self) -> (i32, i32) {
+    self.target.borrow().egl_version()
+  }
+
+  pub fn swap_buffers(&self) {
+    self.target.borrow_mut().swap_buffers();
+  }
+
+  pub fn into_inner(self) -> Box<dyn RenderTarget> {
+    self.target.into_inner()
+  }
+}
+
+impl Default for GlContext {
+  fn default() -> Self {
+    GlContext::new()
+  }
+}
+
+impl From<Context> for GlContext {
+  fn from(context: Context) -> Self {
+    GlContext::with_target(context)
+  }
+}
@@ -0,0 +1,238 @@
+use crate::bindings::BindingError;
+use crate::headless::EglError;
+use crate::preprocess::PreprocessError;
+use crate::{gl, ShaderError};
+use std::fmt;
+use std::io;
+use std::panic::Location;
+
+/// An error code reported by `glGetError`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlError {
+  InvalidEnum,
+  InvalidValue,
+  InvalidOperation,
+  InvalidFramebufferOperation,
+  OutOfMemory,
+  /// A code GLES2 does not define, which some drivers report anyway.
+  Unknown(gl::GLenum),
+}
+
+impl GlError {
+  /// Maps a `glGetError` code, returning `None` for `GL_NO_ERROR`.
+  pub fn from_code(code: gl::GLenum) -> Option<GlError> {
+    match code {
+      gl::GL_NO_ERROR => None,
+      gl::GL_INVALID_ENUM => Some(GlError::InvalidEnum),
+      gl::GL_INVALID_VALUE => Some(GlError::InvalidValue),
+      gl::GL_INVALID_OPERATION => Some(GlError::InvalidOperation),
+      gl::GL_INVALID_FRAMEBUFFER_OPERATION => Some(GlError::InvalidFramebufferOperation),
+      gl::GL_OUT_OF_MEMORY => Some(GlError::OutOfMemory),
+      code => Some(GlError::Unknown(code)),
+    }
+  }
+
+  pub fn code(self) -> gl::GLenum {
+    match self {
+      GlError::InvalidEnum => gl::GL_INVALID_ENUM,
+      GlError::InvalidValue => gl::GL_INVALID_VALUE,
+      GlError::InvalidOperation => gl::GL_INVALID_OPERATION,
+      GlError::InvalidFramebufferOperation => gl::GL_INVALID_FRAMEBUFFER_OPERATION,
+      GlError::OutOfMemory => gl::GL_OUT_OF_MEMORY,
+      GlError::Unknown(code) => code,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      GlError::InvalidEnum => "GL_INVALID_ENUM",
+      GlError::InvalidValue => "GL_INVALID_VALUE",
+      GlError::InvalidOperation => "GL_INVALID_OPERATION",
+      GlError::InvalidFramebufferOperation => "GL_INVALID_FRAMEBUFFER_OPERATION",
+      GlError::OutOfMemory => "GL_OUT_OF_MEMORY",
+      GlError::Unknown(_) => "unknown GL error",
+    }
+  }
+
+  /// Explanation of the error, taken from https://www.khronos.org/opengl/wiki/OpenGL_Error
+  pub fn description(self) -> &'static str {
+    match self {
+      GlError::InvalidEnum => {
+        "Given when an enumeration parameter is not a legal enumeration for that function. \
+        This is given only for local problems; if the spec allows the enumeration in certain \
+        circumstances, where other parameters or state dictate those circumstances, then \
+        GL_INVALID_OPERATION is the result instead."
+      }
+      GlError::InvalidValue => {
+        "Given when a value parameter is not a legal value for that function. This is only \
+        given for local problems; if the spec allows the value in certain circumstances, where \
+        other parameters or state dictate those circumstances, then GL_INVALID_OPERATION is \
+        the result instead."
+      }
+      GlError::InvalidOperation => {
+        "Given when the set of state for a command is not legal for the parameters given to \
+        that command. It is also given for commands where combinations of parameters define \
+        what the legal parameters are."
+      }
+      GlError::InvalidFramebufferOperation => {
+        "Given when doing anything that would attempt to read from or write/render to a \
+        framebuffer that is not complete."
+      }
+      GlError::OutOfMemory => {
+        "Given when performing an operation that can allocate memory, and the memory cannot be \
+        allocated. The results of OpenGL functions that return this error are undefined; it is \
+        allowable for partial execution of an operation to happen in this circumstance."
+      }
+      GlError::Unknown(_) => "The driver reported an error code that GLES2 does not define.",
+    }
+  }
+}
+
+impl fmt::Display for GlError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} ({:#06x})", self.name(), self.code())
+  }
+}
+
+impl std::error::Error for GlError {}
+
+/// Errors returned by the helpers in this crate.
+#[derive(Debug)]
+pub enum Error {
+  /// `glGetError` reported `error` when checked at `location`.
+  Gl {
+    error: GlError,
+    location: &'static Location<'static>,
+  },
+  Shader(ShaderError),
+  /// Fields of a `ShaderBindings` struct that could not be bound.
+  Bindings(Vec<BindingError>),
+  Preprocess(PreprocessError),
+  Egl(EglError),
+  /// `GR_HEADLESS` is not `WIDTHxHEIGHT`.
+  HeadlessSize(String),
+  /// `GR_CAPTURE_EVERY` is not a number of frames.
+  CaptureEvery(String),
+  /// `GR_RECORD_FPS` is not a number of frames a second.
+  RecordFps(String),
+  Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+  /// The GL error code, if this is a GL error.
+  pub fn gl_error(&self) -> Option<GlError> {
+    match self {
+      Error::Gl { error, .. } => Some(*error),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::Gl { error, location } => write!(
+        f,
+        "glGetError is non zero at {}: {}\n{}\nCheck https://www.khronos.org/opengl/wiki/OpenGL_Error",
+        location,
+        error,
+        error.description()
+      ),
+      Error::Shader(err) => write!(f, "{}", err),
+      Error::Bindings(errors) => {
+        write!(f, "shader bindings failed:")?;
+        for err in errors {
+          write!(f, "\n  {}", err)?;
+        }
+        Ok(())
+      }
+      Error::Preprocess(err) => write!(f, "{}", err),
+      Error::Egl(err) => write!(f, "{}", err),
+      Error::HeadlessSize(size) => write!(
+        f,
+        "GR_HEADLESS should be a size like 640x480, not {:?}",
+        size
+      ),
+      Error::CaptureEvery(every) => write!(
+        f,
+        "GR_CAPTURE_EVERY should be a number of frames, not {:?}",
+        every
+      ),
+      Error::RecordFps(fps) => write!(
+        f,
+        "GR_RECORD_FPS should be a number of frames a second, not {:?}",
+        fps
+      ),
+      Error::Io(err) => write!(f, "{}", err),
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::Gl { error, .. } => Some(error),
+      Error::Shader(err) => Some(err),
+      Error::Bindings(errors) => errors
+        .first()
+        .map(|err| err as &(dyn std::error::Error + 'static)),
+      Error::Preprocess(err) => Some(err),
+      Error::Egl(err) => Some(err),
+      Error::HeadlessSize(_) | Error::CaptureEvery(_) | Error::RecordFps(_) => None,
+      Error::Io(err) => Some(err),
+    }
+  }
+}
+
+impl From<ShaderError> for Error {
+  fn from(err: ShaderError) -> Error {
+    Error::Shader(err)
+  }
+}
+
+impl From<PreprocessError> for Error {
+  fn from(err: PreprocessError) -> Error {
+    Error::Preprocess(err)
+  }
+}
+
+impl From<EglError> for Error {
+  fn from(err: EglError) -> Error {
+    Error::Egl(err)
+  }
+}
+
+impl From<io::Error> for Error {
+  fn from(err: io::Error) -> Error {
+    Error::Io(err)
+  }
+}
+
+/// How many error flags GLES2 has, one for each [`GlError`].
+const GL_ERROR_FLAGS: usize = 5;
+
+/// Checks `glGetError`, returning the first pending error along with the caller's location.
+///
+/// GL keeps one flag per error kind, so any further pending errors are cleared to keep them
+/// from being blamed on a later check. GLES2 has five kinds, and a lost context may report one
+/// forever, so no more than that are read.
+#[track_caller]
+pub fn gl_check() -> Result<()> {
+  let error = match GlError::from_code(gl::get_error()) {
+    Some(error) => error,
+    None => return Ok(()),
+  };
+
+  for _ in 0..GL_ERROR_FLAGS {
+    if gl::get_error() == gl::GL_NO_ERROR {
+      break;
+    }
+  }
+
+  Err(Error::Gl {
+    error,
+    location: Location::caller(),
+  })
+}
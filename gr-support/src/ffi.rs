@@ -0,0 +1,134 @@
+//! GL and EGL entry points that `opengles` and `gr-context` do not wrap.
+
+use opengles::glesv2 as gl;
+
+extern "C" {
+  pub fn glGetShaderiv(shader: gl::GLuint, pname: gl::GLenum, params: *mut gl::GLint);
+  pub fn glGetProgramiv(program: gl::GLuint, pname: gl::GLenum, params: *mut gl::GLint);
+  pub fn glGetActiveAttrib(
+    program: gl::GLuint,
+    index: gl::GLuint,
+    bufsize: gl::GLsizei,
+    length: *mut gl::GLsizei,
+    size: *mut gl::GLint,
+    type_: *mut gl::GLenum,
+    name: *mut gl::GLchar,
+  );
+  pub fn glGetActiveUniform(
+    program: gl::GLuint,
+    index: gl::GLuint,
+    bufsize: gl::GLsizei,
+    length: *mut gl::GLsizei,
+    size: *mut gl::GLint,
+    type_: *mut gl::GLenum,
+    name: *mut gl::GLchar,
+  );
+  pub fn glGetString(name: gl::GLenum) -> *const gl::GLubyte;
+  pub fn glGetIntegerv(pname: gl::GLenum, params: *mut gl::GLint);
+  pub fn glDeleteShader(shader: gl::GLuint);
+  pub fn glDeleteProgram(program: gl::GLuint);
+  pub fn glDeleteBuffers(n: gl::GLsizei, buffers: *const gl::GLuint);
+  pub fn glDeleteTextures(n: gl::GLsizei, textures: *const gl::GLuint);
+  pub fn glDeleteFramebuffers(n: gl::GLsizei, framebuffers: *const gl::GLuint);
+  pub fn glIsShader(shader: gl::GLuint) -> gl::GLboolean;
+  pub fn glIsProgram(program: gl::GLuint) -> gl::GLboolean;
+  pub fn glIsBuffer(buffer: gl::GLuint) -> gl::GLboolean;
+  pub fn glIsTexture(texture: gl::GLuint) -> gl::GLboolean;
+  pub fn glIsFramebuffer(framebuffer: gl::GLuint) -> gl::GLboolean;
+}
+
+/// The EGL subset behind [`Headless`](crate::Headless). `gr-context` links the library.
+pub mod egl {
+  use std::os::raw::{c_char, c_void};
+  use std::ptr;
+
+  pub type EGLBoolean = u32;
+  pub type EGLenum = u32;
+  pub type EGLint = i32;
+  pub type EGLConfig = *mut c_void;
+  pub type EGLContext = *mut c_void;
+  pub type EGLDisplay = *mut c_void;
+  pub type EGLSurface = *mut c_void;
+  pub type EGLNativeDisplayType = *mut c_void;
+
+  pub const EGL_DEFAULT_DISPLAY: EGLNativeDisplayType = ptr::null_mut();
+  pub const EGL_NO_CONTEXT: EGLContext = ptr::null_mut();
+  pub const EGL_NO_DISPLAY: EGLDisplay = ptr::null_mut();
+  pub const EGL_NO_SURFACE: EGLSurface = ptr::null_mut();
+
+  pub const EGL_FALSE: EGLBoolean = 0;
+  pub const EGL_SUCCESS: EGLint = 0x3000;
+  pub const EGL_NOT_INITIALIZED: EGLint = 0x3001;
+  pub const EGL_BAD_ACCESS: EGLint = 0x3002;
+  pub const EGL_BAD_ALLOC: EGLint = 0x3003;
+  pub const EGL_BAD_ATTRIBUTE: EGLint = 0x3004;
+  pub const EGL_BAD_CONFIG: EGLint = 0x3005;
+  pub const EGL_BAD_CONTEXT: EGLint = 0x3006;
+  pub const EGL_BAD_CURRENT_SURFACE: EGLint = 0x3007;
+  pub const EGL_BAD_DISPLAY: EGLint = 0x3008;
+  pub const EGL_BAD_MATCH: EGLint = 0x3009;
+  pub const EGL_BAD_NATIVE_PIXMAP: EGLint = 0x300A;
+  pub const EGL_BAD_NATIVE_WINDOW: EGLint = 0x300B;
+  pub const EGL_BAD_PARAMETER: EGLint = 0x300C;
+  pub const EGL_BAD_SURFACE: EGLint = 0x300D;
+  pub const EGL_CONTEXT_LOST: EGLint = 0x300E;
+
+  pub const EGL_ALPHA_SIZE: EGLint = 0x3021;
+  pub const EGL_BLUE_SIZE: EGLint = 0x3022;
+  pub const EGL_GREEN_SIZE: EGLint = 0x3023;
+  pub const EGL_RED_SIZE: EGLint = 0x3024;
+  pub const EGL_DEPTH_SIZE: EGLint = 0x3025;
+  pub const EGL_SURFACE_TYPE: EGLint = 0x3033;
+  pub const EGL_NONE: EGLint = 0x3038;
+  pub const EGL_RENDERABLE_TYPE: EGLint = 0x3040;
+  pub const EGL_HEIGHT: EGLint = 0x3056;
+  pub const EGL_WIDTH: EGLint = 0x3057;
+  pub const EGL_CONTEXT_CLIENT_VERSION: EGLint = 0x3098;
+  pub const EGL_PBUFFER_BIT: EGLint = 0x0001;
+  pub const EGL_OPENGL_ES2_BIT: EGLint = 0x0004;
+  pub const EGL_OPENGL_ES_API: EGLenum = 0x30A0;
+  /// `EGL_MESA_platform_surfaceless`: no window system at all.
+  pub const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31DD;
+
+  pub type EglGetPlatformDisplayExt = unsafe extern "C" fn(
+    platform: EGLenum,
+    native_display: *mut c_void,
+    attrib_list: *const EGLint,
+  ) -> EGLDisplay;
+
+  extern "C" {
+    pub fn eglGetError() -> EGLint;
+    pub fn eglGetDisplay(display_id: EGLNativeDisplayType) -> EGLDisplay;
+    pub fn eglGetProcAddress(procname: *const c_char) -> *mut c_void;
+    pub fn eglInitialize(dpy: EGLDisplay, major: *mut EGLint, minor: *mut EGLint) -> EGLBoolean;
+    pub fn eglTerminate(dpy: EGLDisplay) -> EGLBoolean;
+    pub fn eglBindAPI(api: EGLenum) -> EGLBoolean;
+    pub fn eglChooseConfig(
+      dpy: EGLDisplay,
+      attrib_list: *const EGLint,
+      configs: *mut EGLConfig,
+      config_size: EGLint,
+      num_config: *mut EGLint,
+    ) -> EGLBoolean;
+    pub fn eglCreatePbufferSurface(
+      dpy: EGLDisplay,
+      config: EGLConfig,
+      attrib_list: *const EGLint,
+    ) -> EGLSurface;
+    pub fn eglDestroySurface(dpy: EGLDisplay, surface: EGLSurface) -> EGLBoolean;
+    pub fn eglCreateContext(
+      dpy: EGLDisplay,
+      config: EGLConfig,
+      share_context: EGLContext,
+      attrib_list: *const EGLint,
+    ) -> EGLContext;
+    pub fn eglDestroyContext(dpy: EGLDisplay, ctx: EGLContext) -> EGLBoolean;
+    pub fn eglMakeCurrent(
+      dpy: EGLDisplay,
+      draw: EGLSurface,
+      read: EGLSurface,
+      ctx: EGLContext,
+    ) -> EGLBoolean;
+    pub fn eglSwapBuffers(dpy: EGLDisplay, surface: EGLSurface) -> EGLBoolean;
+  }
+}
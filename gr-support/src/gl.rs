@@ -0,0 +1,303 @@
+//! `opengles::glesv2`, with the calls the examples make going to the [`soft`](crate::soft)
+//! rasterizer while one is current on the thread.
+//!
+//! Use it in place of `opengles::glesv2`, everything it does not override is re-exported as is.
+//! It also wraps the entry points in [`ffi`](crate::ffi) that GL objects need, such as deleting
+//! them or listing a program's attributes.
+
+pub use opengles::glesv2::*;
+
+use crate::{ffi, soft};
+use opengles::glesv2 as driver;
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::c_char;
+use std::slice;
+
+/// Functions with the same signature in `opengles` and the software rasterizer.
+macro_rules! dispatch {
+  ($(pub fn $name:ident($($arg:ident: $type_:ty),*) $(-> $result:ty)?;)*) => {
+    $(
+      pub fn $name($($arg: $type_),*) $(-> $result)? {
+        match soft::current() {
+          Some(state) => state.borrow_mut().$name($($arg),*),
+          None => driver::$name($($arg),*),
+        }
+      }
+    )*
+  };
+}
+
+dispatch! {
+  pub fn get_error() -> GLenum;
+  pub fn flush();
+  pub fn finish();
+  pub fn viewport(x: GLint, y: GLint, width: GLsizei, height: GLsizei);
+  pub fn clear_color(red: GLclampf, green: GLclampf, blue: GLclampf, alpha: GLclampf);
+  pub fn clear(mask: GLbitfield);
+  pub fn read_pixels(
+    x: GLint,
+    y: GLint,
+    width: GLsizei,
+    height: GLsizei,
+    format: GLenum,
+    type_: GLenum,
+    pixels: &mut [u8]
+  );
+
+  pub fn gen_buffers(n: GLsizei) -> Vec<GLuint>;
+  pub fn bind_buffer(target: GLenum, buffer: GLuint);
+  pub fn gen_textures(n: GLsizei) -> Vec<GLuint>;
+  pub fn bind_texture(target: GLenum, texture: GLuint);
+  pub fn tex_parameterf(target: GLenum, pname: GLenum, param: GLfloat);
+  pub fn gen_framebuffers(n: GLsizei) -> Vec<GLuint>;
+  pub fn bind_framebuffer(target: GLenum, framebuffer: GLuint);
+  pub fn framebuffer_texture_2d(
+    target: GLenum,
+    attachment: GLenum,
+    textarget: GLenum,
+    texture: GLuint,
+    level: GLint
+  );
+
+  pub fn create_shader(type_: GLenum) -> GLuint;
+  pub fn shader_source(shader: GLuint, source: &[u8]);
+  pub fn compile_shader(shader: GLuint);
+  pub fn get_shader_info_log(shader: GLuint, max_length: GLsizei) -> Option<String>;
+  pub fn create_program() -> GLuint;
+  pub fn attach_shader(program: GLuint, shader: GLuint);
+  pub fn link_program(program: GLuint);
+  pub fn get_program_info_log(program: GLuint, max_length: GLsizei) -> Option<String>;
+  pub fn use_program(program: GLuint);
+  pub fn get_attrib_location(program: GLuint, name: &str) -> GLint;
+  pub fn get_uniform_location(program: GLuint, name: &str) -> GLint;
+
+  pub fn uniform1i(location: GLint, x: GLint);
+  pub fn uniform2f(location: GLint, x: GLfloat, y: GLfloat);
+  pub fn uniform4f(location: GLint, x: GLfloat, y: GLfloat, z: GLfloat, w: GLfloat);
+  pub fn uniform_matrix4fv(location: GLint, transpose: bool, value: &[GLfloat]);
+
+  pub fn enable_vertex_attrib_array(index: GLuint);
+  pub fn vertex_attrib_pointer_offset(
+    index: GLuint,
+    size: GLint,
+    type_: GLenum,
+    normalized: bool,
+    stride: GLsizei,
+    offset: GLuint
+  );
+  pub fn draw_arrays(mode: GLenum, first: GLint, count: GLsizei);
+}
+
+fn bytes<T>(data: &[T]) -> &[u8] {
+  unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, mem::size_of_val(data)) }
+}
+
+pub fn buffer_data<T>(target: GLenum, data: &[T], usage: GLenum) {
+  match soft::current() {
+    Some(state) => state.borrow_mut().buffer_data(target, bytes(data), usage),
+    None => driver::buffer_data(target, data, usage),
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn tex_image_2d<T>(
+  target: GLenum,
+  level: GLint,
+  internal_format: GLint,
+  width: GLsizei,
+  height: GLsizei,
+  border: GLint,
+  format: GLenum,
+  type_: GLenum,
+  buffer: &[T],
+) {
+  match soft::current() {
+    Some(state) => state.borrow_mut().tex_image_2d(
+      target,
+      level,
+      internal_format,
+      width,
+      height,
+      border,
+      format,
+      type_,
+      bytes(buffer),
+    ),
+    None => driver::tex_image_2d(
+      target,
+      level,
+      internal_format,
+      width,
+      height,
+      border,
+      format,
+      type_,
+      buffer,
+    ),
+  }
+}
+
+// ----------------------------------------------------------------------------
+
+pub fn get_string(name: GLenum) -> Option<String> {
+  if let Some(state) = soft::current() {
+    return state.borrow_mut().get_string(name);
+  }
+  let string = unsafe { ffi::glGetString(name) };
+  if string.is_null() {
+    return None;
+  }
+  let string = unsafe { CStr::from_ptr(string as *const c_char) };
+  Some(string.to_string_lossy().into_owned())
+}
+
+pub fn get_integerv(pname: GLenum, params: &mut [GLint]) {
+  match soft::current() {
+    Some(state) => state.borrow_mut().get_integerv(pname, params),
+    None => unsafe { ffi::glGetIntegerv(pname, params.as_mut_ptr()) },
+  }
+}
+
+pub fn get_shaderiv(shader: GLuint, pname: GLenum) -> GLint {
+  if let Some(state) = soft::current() {
+    return state.borrow_mut().get_shaderiv(shader, pname);
+  }
+  let mut value: GLint = 0;
+  unsafe { ffi::glGetShaderiv(shader, pname, &mut value) };
+  value
+}
+
+pub fn get_programiv(program: GLuint, pname: GLenum) -> GLint {
+  if let Some(state) = soft::current() {
+    return state.borrow_mut().get_programiv(program, pname);
+  }
+  let mut value: GLint = 0;
+  unsafe { ffi::glGetProgramiv(program, pname, &mut value) };
+  value
+}
+
+/// The `(name, size, type)` of the program's active attribute at `index`.
+pub fn get_active_attrib(program: GLuint, index: GLuint) -> Option<(String, GLint, GLenum)> {
+  match soft::current() {
+    Some(state) => state.borrow_mut().get_active_attrib(program, index),
+    None => driver_active(program, index, false),
+  }
+}
+
+/// The `(name, size, type)` of the program's active uniform at `index`.
+pub fn get_active_uniform(program: GLuint, index: GLuint) -> Option<(String, GLint, GLenum)> {
+  match soft::current() {
+    Some(state) => state.borrow_mut().get_active_uniform(program, index),
+    None => driver_active(program, index, true),
+  }
+}
+
+fn driver_active(
+  program: GLuint,
+  index: GLuint,
+  uniforms: bool,
+) -> Option<(String, GLint, GLenum)> {
+  let max_length = if uniforms {
+    get_programiv(program, GL_ACTIVE_UNIFORM_MAX_LENGTH)
+  } else {
+    get_programiv(program, GL_ACTIVE_ATTRIBUTE_MAX_LENGTH)
+  };
+  let get_active = if uniforms {
+    ffi::glGetActiveUniform
+  } else {
+    ffi::glGetActiveAttrib
+  };
+  let mut name = vec![0_u8; max_length.max(1) as usize];
+  let mut length: GLsizei = 0;
+  let mut size: GLint = 0;
+  let mut type_: GLenum = 0;
+  unsafe {
+    get_active(
+      program,
+      index,
+      name.len() as GLsizei,
+      &mut length,
+      &mut size,
+      &mut type_,
+      name.as_mut_ptr() as *mut GLchar,
+    )
+  };
+  if length <= 0 {
+    return None;
+  }
+  let name = String::from_utf8_lossy(&name[..length as usize]).into_owned();
+  Some((name, size, type_))
+}
+
+pub fn delete_shader(shader: GLuint) {
+  match soft::current() {
+    Some(state) => state.borrow_mut().delete_shader(shader),
+    None => unsafe { ffi::glDeleteShader(shader) },
+  }
+}
+
+pub fn delete_program(program: GLuint) {
+  match soft::current() {
+    Some(state) => state.borrow_mut().delete_program(program),
+    None => unsafe { ffi::glDeleteProgram(program) },
+  }
+}
+
+pub fn delete_buffers(buffers: &[GLuint]) {
+  match soft::current() {
+    Some(state) => state.borrow_mut().delete_buffers(buffers),
+    None => unsafe { ffi::glDeleteBuffers(buffers.len() as GLsizei, buffers.as_ptr()) },
+  }
+}
+
+pub fn delete_textures(textures: &[GLuint]) {
+  match soft::current() {
+    Some(state) => state.borrow_mut().delete_textures(textures),
+    None => unsafe { ffi::glDeleteTextures(textures.len() as GLsizei, textures.as_ptr()) },
+  }
+}
+
+pub fn delete_framebuffers(framebuffers: &[GLuint]) {
+  match soft::current() {
+    Some(state) => state.borrow_mut().delete_framebuffers(framebuffers),
+    None => unsafe {
+      ffi::glDeleteFramebuffers(framebuffers.len() as GLsizei, framebuffers.as_ptr())
+    },
+  }
+}
+
+pub fn is_shader(shader: GLuint) -> bool {
+  match soft::current() {
+    Some(state) => state.borrow_mut().is_shader(shader),
+    None => unsafe { ffi::glIsShader(shader) != 0 },
+  }
+}
+
+pub fn is_program(program: GLuint) -> bool {
+  match soft::current() {
+    Some(state) => state.borrow_mut().is_program(program),
+    None => unsafe { ffi::glIsProgram(program) != 0 },
+  }
+}
+
+pub fn is_buffer(buffer: GLuint) -> bool {
+  match soft::current() {
+    Some(state) => state.borrow_mut().is_buffer(buffer),
+    None => unsafe { ffi::glIsBuffer(buffer) != 0 },
+  }
+}
+
+pub fn is_texture(texture: GLuint) -> bool {
+  match soft::current() {
+    Some(state) => state.borrow_mut().is_texture(texture),
+    None => unsafe { ffi::glIsTexture(texture) != 0 },
+  }
+}
+
+pub fn is_framebuffer(framebuffer: GLuint) -> bool {
+  match soft::current() {
+    Some(state) => state.borrow_mut().is_framebuffer(framebuffer),
+    None => unsafe { ffi::glIsFramebuffer(framebuffer) != 0 },
+  }
+}
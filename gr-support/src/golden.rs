@@ -0,0 +1,184 @@
+//! Compares rendered frames with reference PNGs checked in next to the tests.
+//!
+//! ```ignore
+//! // In an integration test
+//! let golden = Golden::new(
+//!   concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"),
+//!   concat!(env!("CARGO_TARGET_TMPDIR"), "/golden"),
+//! );
+//! draw(&context)?;
+//! golden.assert("frame", &Image::read(&context)?);
+//! ```
+//!
+//! A frame that does not match is written to the output directory as `NAME.actual.png`, along
+//! with `NAME.diff.png` showing the pixels that differ in red. Running the tests with
+//! `GR_UPDATE_GOLDEN=1` writes the frames as the new references instead.
+
+use crate::image::Image;
+use crate::Error;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The references in a directory, and where to write frames that do not match them.
+#[derive(Clone, Debug)]
+pub struct Golden {
+  references: PathBuf,
+  output: PathBuf,
+  tolerance: u8,
+}
+
+/// Why a frame did not match its reference.
+#[derive(Debug)]
+pub enum Mismatch {
+  /// There is no reference, the frame was written to `actual`.
+  Missing { reference: PathBuf, actual: PathBuf },
+  /// The frame was written to `actual`.
+  Size {
+    reference: PathBuf,
+    expected: (u32, u32),
+    found: (u32, u32),
+    actual: PathBuf,
+  },
+  /// `differing` pixels are off by more than the tolerance, see `diff`.
+  Pixels {
+    reference: PathBuf,
+    differing: usize,
+    max_difference: u8,
+    actual: PathBuf,
+    diff: PathBuf,
+  },
+  /// Reading the reference or writing the frames failed.
+  Io(Error),
+}
+
+impl Golden {
+  /// References are read from `references` and failures written to `output`, both created as
+  /// needed. Channels may be off by 2 by default.
+  pub fn new<P: Into<PathBuf>, Q: Into<PathBuf>>(references: P, output: Q) -> Golden {
+    Golden {
+      references: references.into(),
+      output: output.into(),
+      tolerance: 2,
+    }
+  }
+
+  /// How far each channel of a pixel may be from the reference's.
+  pub fn tolerance(self, tolerance: u8) -> Golden {
+    Golden { tolerance, ..self }
+  }
+
+  pub fn reference(&self, name: &str) -> PathBuf {
+    self.references.join(format!("{}.png", name))
+  }
+
+  /// Compares `image` with the reference `NAME.png`, or replaces the reference when
+  /// `GR_UPDATE_GOLDEN` is set.
+  pub fn check(&self, name: &str, image: &Image) -> Result<(), Mismatch> {
+    let reference = self.reference(name);
+    if env::var_os("GR_UPDATE_GOLDEN").is_some() {
+      return write(&reference, image);
+    }
+
+    let actual = self.output.join(format!("{}.actual.png", name));
+    if !reference.exists() {
+      write(&actual, image)?;
+      return Err(Mismatch::Missing { reference, actual });
+    }
+
+    let expected = Image::load_png(&reference).map_err(Mismatch::Io)?;
+    if (expected.width(), expected.height()) != (image.width(), image.height()) {
+      write(&actual, image)?;
+      return Err(Mismatch::Size {
+        reference,
+        expected: (expected.width(), expected.height()),
+        found: (image.width(), image.height()),
+        actual,
+      });
+    }
+
+    let diff = match expected.diff(image, self.tolerance) {
+      Some(diff) => diff,
+      None => return Ok(()),
+    };
+    let diff_path = self.output.join(format!("{}.diff.png", name));
+    write(&actual, image)?;
+    write(&diff_path, &diff.image)?;
+    Err(Mismatch::Pixels {
+      reference,
+      differing: diff.differing,
+      max_difference: diff.max_difference,
+      actual,
+      diff: diff_path,
+    })
+  }
+
+  /// [`check`](Golden::check), panicking with the mismatch.
+  #[track_caller]
+  pub fn assert(&self, name: &str, image: &Image) {
+    if let Err(mismatch) = self.check(name, image) {
+      panic!("{}: {}", name, mismatch);
+    }
+  }
+}
+
+fn write(path: &Path, image: &Image) -> Result<(), Mismatch> {
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir).map_err(|err| Mismatch::Io(err.into()))?;
+  }
+  image.save_png(path).map_err(Mismatch::Io)
+}
+
+impl fmt::Display for Mismatch {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Mismatch::Missing { reference, actual } => write!(
+        f,
+        "no reference at {}, the frame is in {} (GR_UPDATE_GOLDEN=1 saves it as the reference)",
+        reference.display(),
+        actual.display()
+      ),
+      Mismatch::Size {
+        reference,
+        expected,
+        found,
+        actual,
+      } => write!(
+        f,
+        "{} is {}x{}, the frame in {} is {}x{}",
+        reference.display(),
+        expected.0,
+        expected.1,
+        actual.display(),
+        found.0,
+        found.1
+      ),
+      Mismatch::Pixels {
+        reference,
+        differing,
+        max_difference,
+        actual,
+        diff,
+      } => write!(
+        f,
+        "{} pixels differ from {} by up to {}, see {} and {}",
+        differing,
+        reference.display(),
+        max_difference,
+        actual.display(),
+        diff.display()
+      ),
+      Mismatch::Io(err) => write!(f, "{}", err),
+    }
+  }
+}
+
+impl std::error::Error for Mismatch {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Mismatch::Io(err) => Some(err),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,216 @@
+//! Which VideoCore the program runs on, and which GL driver drives it.
+//!
+//! The GPU generation only changes how hard the examples can push the shaders, so it is picked
+//! at runtime. The driver is fixed when linking: `gr-context` either links the legacy Broadcom
+//! libraries in `/opt/vc/lib` (`vc4` feature) or Mesa (`vc6` feature, which also drives VC4
+//! boards booted with `dtoverlay=vc4-kms-v3d`). [`Gpu::driver_mismatch`] explains how to rebuild
+//! when the two disagree.
+
+use crate::{gl, GlContext};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// A VideoCore generation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VideoCore {
+  /// VideoCore IV: Raspberry Pi 1 to 3, Zero and Compute Modules 1 and 3.
+  Vc4,
+  /// VideoCore VI: Raspberry Pi 4, 400 and Compute Module 4. Later boards are at least as
+  /// capable, so they count as this too.
+  Vc6,
+}
+
+impl VideoCore {
+  /// Parses `/proc/device-tree/model`, e.g. `Raspberry Pi 4 Model B Rev 1.4`.
+  pub fn from_model(model: &str) -> Option<VideoCore> {
+    let board = model
+      .trim_end_matches('\0')
+      .trim()
+      .strip_prefix("Raspberry Pi ")?;
+    let board = board.strip_prefix("Compute Module ").unwrap_or(board);
+
+    // Pi 1 is plain `Model B`, Zeros are `Zero W`, `Zero 2 W`, ...
+    let generation: u32 = match board.chars().next() {
+      Some(c) if c.is_ascii_digit() => c.to_digit(10)?,
+      _ => 1,
+    };
+    if board.starts_with("Zero") || generation < 4 {
+      Some(VideoCore::Vc4)
+    } else {
+      Some(VideoCore::Vc6)
+    }
+  }
+
+  /// Parses `GL_RENDERER`: `VideoCore IV HW` (firmware driver), `VC4 V3D 2.1` or `V3D 4.2`
+  /// (Mesa).
+  pub fn from_renderer(renderer: &str) -> Option<VideoCore> {
+    if renderer.contains("VideoCore IV") || renderer.starts_with("VC4") {
+      return Some(VideoCore::Vc4);
+    }
+
+    let version = renderer.split("V3D ").nth(1)?;
+    let major: u32 = version.split('.').next()?.trim().parse().ok()?;
+    if major >= 4 {
+      Some(VideoCore::Vc6)
+    } else {
+      Some(VideoCore::Vc4)
+    }
+  }
+}
+
+impl fmt::Display for VideoCore {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      VideoCore::Vc4 => write!(f, "VideoCore IV"),
+      VideoCore::Vc6 => write!(f, "VideoCore VI"),
+    }
+  }
+}
+
+/// A GL driver stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Driver {
+  /// The closed Broadcom driver in the firmware, used by VC4 boards without a KMS overlay.
+  Firmware,
+  /// Mesa's `vc4` / `v3d` kernel modesetting drivers.
+  Mesa,
+}
+
+impl Driver {
+  /// The driver this binary was linked against, `None` when built without `vc4` or `vc6`.
+  pub fn linked() -> Option<Driver> {
+    if cfg!(feature = "vc4") {
+      Some(Driver::Firmware)
+    } else if cfg!(feature = "vc6") {
+      Some(Driver::Mesa)
+    } else {
+      None
+    }
+  }
+
+  /// The cargo feature that links this driver.
+  pub fn feature(self) -> &'static str {
+    match self {
+      Driver::Firmware => "vc4",
+      Driver::Mesa => "vc6",
+    }
+  }
+
+  /// Where the driver's libraries are, when the linker does not look there already.
+  pub fn link_path(self) -> Option<&'static str> {
+    match self {
+      Driver::Firmware => Some("/opt/vc/lib"),
+      Driver::Mesa => None,
+    }
+  }
+}
+
+impl fmt::Display for Driver {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Driver::Firmware => write!(f, "Broadcom firmware driver"),
+      Driver::Mesa => write!(f, "Mesa KMS driver"),
+    }
+  }
+}
+
+/// What [`Gpu::detect`] found out about the machine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gpu {
+  /// `/proc/device-tree/model`, if there is one.
+  pub model: Option<String>,
+  /// `None` when neither the model nor the renderer is recognised.
+  pub videocore: Option<VideoCore>,
+  /// The driver the kernel is set up for.
+  pub driver: Driver,
+}
+
+impl Gpu {
+  pub fn detect() -> Gpu {
+    Gpu::detect_in(Path::new("/"))
+  }
+
+  /// Like [`detect`](Gpu::detect), with `/proc` and `/dev` looked up under `root`.
+  pub fn detect_in(root: &Path) -> Gpu {
+    let model = fs::read_to_string(root.join("proc/device-tree/model"))
+      .ok()
+      .map(|model| model.trim_end_matches('\0').trim().to_string());
+    let videocore = model.as_deref().and_then(VideoCore::from_model);
+    // Mesa needs the KMS driver, which shows up as a DRM device
+    let driver = if root.join("dev/dri").is_dir() {
+      Driver::Mesa
+    } else {
+      Driver::Firmware
+    };
+
+    Gpu {
+      model,
+      videocore,
+      driver,
+    }
+  }
+
+  /// Falls back to the `GL_RENDERER` string when the model gave nothing away.
+  pub fn or_renderer(self, context: &GlContext) -> Gpu {
+    match renderer(context) {
+      Some(renderer) => self.or_renderer_str(&renderer),
+      None => self,
+    }
+  }
+
+  /// [`or_renderer`](Gpu::or_renderer) with a given renderer string.
+  pub fn or_renderer_str(mut self, renderer: &str) -> Gpu {
+    if self.videocore.is_none() {
+      self.videocore = VideoCore::from_renderer(renderer);
+    }
+    self
+  }
+
+  /// Explains how to rebuild when the binary links a different driver than the kernel runs.
+  pub fn driver_mismatch(&self) -> Option<String> {
+    self.mismatch_with(Driver::linked()?)
+  }
+
+  /// [`driver_mismatch`](Gpu::driver_mismatch) against a given linked driver.
+  pub fn mismatch_with(&self, linked: Driver) -> Option<String> {
+    if linked == self.driver {
+      return None;
+    }
+
+    let rebuild = match self.driver.link_path() {
+      Some(path) => format!(
+        "RUSTFLAGS='-L {}' cargo build --no-default-features --features={}",
+        path,
+        self.driver.feature()
+      ),
+      None => format!(
+        "cargo build --no-default-features --features={}",
+        self.driver.feature()
+      ),
+    };
+    Some(format!(
+      "built for the {} but this machine runs the {}, rebuild with `{}`",
+      linked, self.driver, rebuild
+    ))
+  }
+}
+
+impl fmt::Display for Gpu {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.model {
+      Some(model) => write!(f, "{}", model)?,
+      None => write!(f, "unknown board")?,
+    }
+    match self.videocore {
+      Some(videocore) => write!(f, ", {}", videocore)?,
+      None => write!(f, ", unknown GPU")?,
+    }
+    write!(f, ", {}", self.driver)
+  }
+}
+
+/// The `GL_RENDERER` string of the current context.
+pub fn renderer(_context: &GlContext) -> Option<String> {
+  gl::get_string(gl::GL_RENDERER)
+}
@@ -0,0 +1,217 @@
+//! An offscreen [`RenderTarget`]: an EGL pbuffer, no display or window system needed.
+//!
+//! On Mesa this runs on a plain Linux box, and with `LIBGL_ALWAYS_SOFTWARE=1` on its software
+//! rasterizer, so the examples can draw in CI and the result can be read back with
+//! `glReadPixels`.
+
+use crate::ffi::egl::*;
+use crate::RenderTarget;
+use std::ffi::CString;
+use std::fmt;
+use std::mem;
+use std::ptr;
+
+/// An EGL call that failed, with the code `eglGetError` reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EglError {
+  pub function: &'static str,
+  pub code: EGLint,
+}
+
+impl EglError {
+  fn last(function: &'static str) -> EglError {
+    EglError {
+      function,
+      code: unsafe { eglGetError() },
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self.code {
+      EGL_SUCCESS => "EGL_SUCCESS",
+      EGL_NOT_INITIALIZED => "EGL_NOT_INITIALIZED",
+      EGL_BAD_ACCESS => "EGL_BAD_ACCESS",
+      EGL_BAD_ALLOC => "EGL_BAD_ALLOC",
+      EGL_BAD_ATTRIBUTE => "EGL_BAD_ATTRIBUTE",
+      EGL_BAD_CONFIG => "EGL_BAD_CONFIG",
+      EGL_BAD_CONTEXT => "EGL_BAD_CONTEXT",
+      EGL_BAD_CURRENT_SURFACE => "EGL_BAD_CURRENT_SURFACE",
+      EGL_BAD_DISPLAY => "EGL_BAD_DISPLAY",
+      EGL_BAD_MATCH => "EGL_BAD_MATCH",
+      EGL_BAD_NATIVE_PIXMAP => "EGL_BAD_NATIVE_PIXMAP",
+      EGL_BAD_NATIVE_WINDOW => "EGL_BAD_NATIVE_WINDOW",
+      EGL_BAD_PARAMETER => "EGL_BAD_PARAMETER",
+      EGL_BAD_SURFACE => "EGL_BAD_SURFACE",
+      EGL_CONTEXT_LOST => "EGL_CONTEXT_LOST",
+      _ => "unknown EGL error",
+    }
+  }
+}
+
+impl fmt::Display for EglError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} failed: {} ({:#06x})",
+      self.function,
+      self.name(),
+      self.code
+    )
+  }
+}
+
+impl std::error::Error for EglError {}
+
+/// A GLES 2 context drawing to a `width` x `height` RGBA pbuffer.
+pub struct Headless {
+  display: EGLDisplay,
+  surface: EGLSurface,
+  context: EGLContext,
+  width: u32,
+  height: u32,
+  version: (i32, i32),
+}
+
+impl Headless {
+  /// Creates the context and makes it current.
+  pub fn new(width: u32, height: u32) -> Result<Headless, EglError> {
+    let display = headless_display();
+    if display == EGL_NO_DISPLAY {
+      return Err(EglError::last("eglGetDisplay"));
+    }
+
+    let (mut major, mut minor) = (0, 0);
+    if unsafe { eglInitialize(display, &mut major, &mut minor) } == EGL_FALSE {
+      return Err(EglError::last("eglInitialize"));
+    }
+
+    // From here on, dropping `headless` cleans up whatever was created
+    let mut headless = Headless {
+      display,
+      surface: EGL_NO_SURFACE,
+      context: EGL_NO_CONTEXT,
+      width,
+      height,
+      version: (major, minor),
+    };
+
+    #[rustfmt::skip]
+    let config_attributes = [
+      EGL_SURFACE_TYPE, EGL_PBUFFER_BIT,
+      EGL_RENDERABLE_TYPE, EGL_OPENGL_ES2_BIT,
+      EGL_RED_SIZE, 8,
+      EGL_GREEN_SIZE, 8,
+      EGL_BLUE_SIZE, 8,
+      EGL_ALPHA_SIZE, 8,
+      EGL_DEPTH_SIZE, 16,
+      EGL_NONE,
+    ];
+    let mut config: EGLConfig = ptr::null_mut();
+    let mut configs = 0;
+    let chosen = unsafe {
+      eglChooseConfig(
+        display,
+        config_attributes.as_ptr(),
+        &mut config,
+        1,
+        &mut configs,
+      )
+    };
+    if chosen == EGL_FALSE || configs < 1 {
+      return Err(EglError::last("eglChooseConfig"));
+    }
+
+    let surface_attributes = [
+      EGL_WIDTH,
+      width as EGLint,
+      EGL_HEIGHT,
+      height as EGLint,
+      EGL_NONE,
+    ];
+    headless.surface =
+      unsafe { eglCreatePbufferSurface(display, config, surface_attributes.as_ptr()) };
+    if headless.surface == EGL_NO_SURFACE {
+      return Err(EglError::last("eglCreatePbufferSurface"));
+    }
+
+    if unsafe { eglBindAPI(EGL_OPENGL_ES_API) } == EGL_FALSE {
+      return Err(EglError::last("eglBindAPI"));
+    }
+    let context_attributes = [EGL_CONTEXT_CLIENT_VERSION, 2, EGL_NONE];
+    headless.context =
+      unsafe { eglCreateContext(display, config, EGL_NO_CONTEXT, context_attributes.as_ptr()) };
+    if headless.context == EGL_NO_CONTEXT {
+      return Err(EglError::last("eglCreateContext"));
+    }
+
+    let current = unsafe {
+      eglMakeCurrent(
+        display,
+        headless.surface,
+        headless.surface,
+        headless.context,
+      )
+    };
+    if current == EGL_FALSE {
+      return Err(EglError::last("eglMakeCurrent"));
+    }
+
+    Ok(headless)
+  }
+}
+
+/// Mesa's surfaceless platform when there is one, as the default display may want X or Wayland.
+fn headless_display() -> EGLDisplay {
+  let name = CString::new("eglGetPlatformDisplayEXT").unwrap();
+  let get_platform_display = unsafe { eglGetProcAddress(name.as_ptr()) };
+  if !get_platform_display.is_null() {
+    let get_platform_display: EglGetPlatformDisplayExt =
+      unsafe { mem::transmute(get_platform_display) };
+    let display = unsafe {
+      get_platform_display(
+        EGL_PLATFORM_SURFACELESS_MESA,
+        EGL_DEFAULT_DISPLAY,
+        ptr::null(),
+      )
+    };
+    if display != EGL_NO_DISPLAY {
+      return display;
+    }
+  }
+
+  unsafe { eglGetDisplay(EGL_DEFAULT_DISPLAY) }
+}
+
+impl RenderTarget for Headless {
+  fn width(&self) -> u32 {
+    self.width
+  }
+
+  fn height(&self) -> u32 {
+    self.height
+  }
+
+  fn egl_version(&self) -> (i32, i32) {
+    self.version
+  }
+
+  /// Pbuffers are single buffered, the frame stays in place to be read back.
+  fn swap_buffers(&mut self) {
+    unsafe { eglSwapBuffers(self.display, self.surface) };
+  }
+}
+
+impl Drop for Headless {
+  fn drop(&mut self) {
+    unsafe {
+      eglMakeCurrent(self.display, EGL_NO_SURFACE, EGL_NO_SURFACE, EGL_NO_CONTEXT);
+      if self.context != EGL_NO_CONTEXT {
+        eglDestroyContext(self.display, self.context);
+      }
+      if self.surface != EGL_NO_SURFACE {
+        eglDestroySurface(self.display, self.surface);
+      }
+      eglTerminate(self.display);
+    }
+  }
+}
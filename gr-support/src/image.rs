@@ -0,0 +1,428 @@
+//! RGBA images read back from the framebuffer, and comparing them.
+//!
+//! They can be saved as PPM files or raw dumps, and with the `png` feature as PNG files. Raw dumps,
+//! and PNGs with the feature, can be loaded back.
+
+use crate::{gl, gl_check, Error, GlContext, Result};
+use std::fmt;
+use std::fs::{self, File};
+#[cfg(feature = "png")]
+use std::io::BufReader;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The bytes of a `width` by `height` image with `bytes_per_pixel`, or `InvalidInput` when
+/// they would not fit in memory.
+fn byte_size(width: u32, height: u32, bytes_per_pixel: usize) -> io::Result<usize> {
+  (width as usize)
+    .checked_mul(height as usize)
+    .and_then(|pixels| pixels.checked_mul(bytes_per_pixel))
+    .ok_or_else(|| {
+      io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("a {}x{} image is too large", width, height),
+      )
+    })
+}
+
+/// 8 bit RGBA pixels, top row first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+  width: u32,
+  height: u32,
+  pixels: Vec<u8>,
+}
+
+impl Image {
+  /// Panics unless there are `width * height` RGBA pixels.
+  pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Image {
+    assert_eq!(
+      Some(pixels.len()),
+      byte_size(width, height, 4).ok(),
+      "{}x{} RGBA pixels",
+      width,
+      height
+    );
+    Image {
+      width,
+      height,
+      pixels,
+    }
+  }
+
+  /// The whole of the bound framebuffer, `context`'s size. GL reads the bottom row first, the
+  /// rows are flipped.
+  pub fn read(context: &GlContext) -> Result<Image> {
+    let (width, height) = (context.width(), context.height());
+    let mut pixels = vec![0; byte_size(width, height, 4)?];
+    gl::read_pixels(
+      0,
+      0,
+      width as gl::GLsizei,
+      height as gl::GLsizei,
+      gl::GL_RGBA,
+      gl::GL_UNSIGNED_BYTE,
+      &mut pixels,
+    );
+    gl_check()?;
+
+    let mut image = Image::new(width, height, pixels);
+    image.flip();
+    Ok(image)
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn pixels(&self) -> &[u8] {
+    &self.pixels
+  }
+
+  pub fn into_pixels(self) -> Vec<u8> {
+    self.pixels
+  }
+
+  pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+    let start = (y as usize * self.width as usize + x as usize) * 4;
+    let mut pixel = [0; 4];
+    pixel.copy_from_slice(&self.pixels[start..start + 4]);
+    pixel
+  }
+
+  /// Turns the image upside down.
+  pub fn flip(&mut self) {
+    let row = self.width as usize * 4;
+    let height = self.height as usize;
+    for y in 0..height / 2 {
+      let (top, bottom) = self.pixels.split_at_mut((height - 1 - y) * row);
+      top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+    }
+  }
+
+  /// How `other` differs from this image, `None` when every channel of every pixel is within
+  /// `tolerance`. Panics if the sizes differ.
+  pub fn diff(&self, other: &Image, tolerance: u8) -> Option<Diff> {
+    assert_eq!(
+      (self.width, self.height),
+      (other.width, other.height),
+      "comparing images of different sizes"
+    );
+
+    let mut differs = Vec::with_capacity(self.pixels.len() / 4);
+    let mut max_difference = 0;
+    for (expected, actual) in self.pixels.chunks(4).zip(other.pixels.chunks(4)) {
+      let difference = expected
+        .iter()
+        .zip(actual)
+        .map(|(expected, actual)| (*expected as i16 - *actual as i16).unsigned_abs() as u8)
+        .max()
+        .unwrap_or(0);
+      max_difference = max_difference.max(difference);
+      differs.push(difference > tolerance);
+    }
+
+    let differing = differs.iter().filter(|differs| **differs).count();
+    if differing == 0 {
+      return None;
+    }
+    Some(Diff {
+      differing,
+      max_difference,
+      image: self.highlight(differs),
+    })
+  }
+
+  /// The image in dim grey, with the pixels where `differs` is true in red.
+  pub fn highlight<I: IntoIterator<Item = bool>>(&self, differs: I) -> Image {
+    let mut pixels = Vec::with_capacity(self.pixels.len());
+    for (pixel, differs) in self.pixels.chunks(4).zip(differs) {
+      if differs {
+        pixels.extend_from_slice(&[255, 0, 0, 255]);
+      } else {
+        let luma = (pixel[0] as u32 * 3 + pixel[1] as u32 * 6 + pixel[2] as u32) / 40;
+        pixels.extend_from_slice(&[luma as u8, luma as u8, luma as u8, 255]);
+      }
+    }
+    Image::new(self.width, self.height, pixels)
+  }
+
+  /// The Y, then U, then V planes of the image, full range BT.601 like JPEG. U and V are
+  /// averaged over blocks of 2x2 pixels, the last row and column of blocks being cut short when
+  /// the size is odd.
+  pub fn to_yuv420(&self) -> Vec<u8> {
+    let (width, height) = (self.width as usize, self.height as usize);
+    let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+    let mut yuv = vec![0; width * height + 2 * chroma_width * chroma_height];
+    let (luma, chroma) = yuv.split_at_mut(width * height);
+    let (u, v) = chroma.split_at_mut(chroma_width * chroma_height);
+
+    let rgb = |x: usize, y: usize| {
+      let start = (y * width + x) * 4;
+      let pixel = &self.pixels[start..start + 3];
+      (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32)
+    };
+    for y in 0..height {
+      for x in 0..width {
+        let (r, g, b) = rgb(x, y);
+        luma[y * width + x] = (0.299 * r + 0.587 * g + 0.114 * b).round() as u8;
+      }
+    }
+    for cy in 0..chroma_height {
+      for cx in 0..chroma_width {
+        let (mut r, mut g, mut b, mut count) = (0.0, 0.0, 0.0, 0.0);
+        for y in 2 * cy..(2 * cy + 2).min(height) {
+          for x in 2 * cx..(2 * cx + 2).min(width) {
+            let pixel = rgb(x, y);
+            r += pixel.0;
+            g += pixel.1;
+            b += pixel.2;
+            count += 1.0;
+          }
+        }
+        let (r, g, b) = (r / count, g / count, b / count);
+        let i = cy * chroma_width + cx;
+        u[i] = (128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b)
+          .round()
+          .clamp(0.0, 255.0) as u8;
+        v[i] = (128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b)
+          .round()
+          .clamp(0.0, 255.0) as u8;
+      }
+    }
+    yuv
+  }
+
+  /// Reads a raw dump in `format`, rows in GL's order, bottom first.
+  pub fn from_raw(width: u32, height: u32, format: RawFormat, raw: &[u8]) -> Result<Image> {
+    let size = byte_size(width, height, format.bytes_per_pixel())?;
+    if raw.len() != size {
+      return Err(
+        io::Error::new(
+          io::ErrorKind::InvalidData,
+          format!(
+            "a {}x{} {} dump is {} bytes, not {}",
+            width,
+            height,
+            format,
+            size,
+            raw.len()
+          ),
+        )
+        .into(),
+      );
+    }
+
+    let pixels = match format {
+      RawFormat::Rgba8 => raw.to_vec(),
+      RawFormat::Rgb565 => raw
+        .chunks(2)
+        .flat_map(|bytes| {
+          let rgb = u16::from_le_bytes([bytes[0], bytes[1]]);
+          // Widened to 8 bits the way GL does, repeating the high bits
+          let widen =
+            |value: u16, bits: u32| ((value << (8 - bits)) | (value >> (2 * bits - 8))) as u8;
+          [
+            widen(rgb >> 11, 5),
+            widen((rgb >> 5) & 0x3f, 6),
+            widen(rgb & 0x1f, 5),
+            255,
+          ]
+        })
+        .collect(),
+    };
+    let mut image = Image::new(width, height, pixels);
+    image.flip();
+    Ok(image)
+  }
+
+  /// Loads a PNG, or a raw dump when `raw` gives its size and format.
+  pub fn load<P: AsRef<Path>>(path: P, raw: Option<(u32, u32, RawFormat)>) -> Result<Image> {
+    let path = path.as_ref();
+    match raw {
+      Some((width, height, format)) => Image::from_raw(width, height, format, &fs::read(path)?)
+        .map_err(|err| match err {
+          Error::Io(err) => {
+            io::Error::new(err.kind(), format!("{}: {}", path.display(), err)).into()
+          }
+          err => err,
+        }),
+      #[cfg(feature = "png")]
+      None => Image::load_png(path),
+      #[cfg(not(feature = "png"))]
+      None => Err(
+        io::Error::new(
+          io::ErrorKind::InvalidInput,
+          format!(
+            "{}: loading PNGs needs the png feature, or give the size of a raw dump",
+            path.display()
+          ),
+        )
+        .into(),
+      ),
+    }
+  }
+
+  /// Saves the image as a PNG, a PPM or a raw RGBA dump, going by the extension of `path`.
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    match extension.map(str::to_ascii_lowercase).as_deref() {
+      #[cfg(feature = "png")]
+      Some("png") => self.save_png(path),
+      Some("ppm") => self.save_ppm(path),
+      Some("raw") => self.save_raw(path),
+      _ => Err(
+        io::Error::new(
+          io::ErrorKind::InvalidInput,
+          format!(
+            "{}: can only save images as {}",
+            path.display(),
+            if cfg!(feature = "png") {
+              ".png, .ppm or .raw"
+            } else {
+              ".ppm or .raw (.png needs the png feature)"
+            }
+          ),
+        )
+        .into(),
+      ),
+    }
+  }
+
+  /// Saves the image as a binary PPM, which has no alpha channel.
+  pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(file, "P6\n{} {}\n255\n", self.width, self.height)?;
+    for pixel in self.pixels.chunks(4) {
+      file.write_all(&pixel[..3])?;
+    }
+    file.flush()?;
+    Ok(())
+  }
+
+  /// Saves the pixels as they came from `glReadPixels`: RGBA, bottom row first, no header.
+  pub fn save_raw<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    // A zero width image has no pixels to write, and `chunks(0)` panics
+    if self.width > 0 {
+      for row in self.pixels.chunks(self.width as usize * 4).rev() {
+        file.write_all(row)?;
+      }
+    }
+    file.flush()?;
+    Ok(())
+  }
+}
+
+/// The pixel layout of a raw dump, which has no header to tell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawFormat {
+  /// `GL_RGBA` / `GL_UNSIGNED_BYTE`, what `glReadPixels` gives and [`Image::save_raw`] writes.
+  Rgba8,
+  /// `GL_RGB` / `GL_UNSIGNED_SHORT_5_6_5`, little endian.
+  Rgb565,
+}
+
+impl RawFormat {
+  pub fn bytes_per_pixel(self) -> usize {
+    match self {
+      RawFormat::Rgba8 => 4,
+      RawFormat::Rgb565 => 2,
+    }
+  }
+}
+
+impl fmt::Display for RawFormat {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      RawFormat::Rgba8 => "rgba",
+      RawFormat::Rgb565 => "rgb565",
+    })
+  }
+}
+
+impl FromStr for RawFormat {
+  type Err = String;
+
+  fn from_str(format: &str) -> std::result::Result<RawFormat, String> {
+    match format {
+      "rgba" => Ok(RawFormat::Rgba8),
+      "rgb565" => Ok(RawFormat::Rgb565),
+      _ => Err(format!(
+        "unknown raw format {:?}, expected rgba or rgb565",
+        format
+      )),
+    }
+  }
+}
+
+/// The pixels that differ between two images.
+#[derive(Clone, Debug)]
+pub struct Diff {
+  /// Pixels with a channel beyond the tolerance.
+  pub differing: usize,
+  /// The largest difference of any channel, tolerated or not.
+  pub max_difference: u8,
+  /// The differing pixels in red over the expected image in grey.
+  pub image: Image,
+}
+
+#[cfg(feature = "png")]
+impl Image {
+  /// Reads an 8 bit RGB or RGBA PNG.
+  pub fn load_png<P: AsRef<Path>>(path: P) -> Result<Image> {
+    let path = path.as_ref();
+    let invalid = |err: &dyn std::fmt::Display| {
+      io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), err),
+      )
+    };
+
+    let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    let mut reader = decoder.read_info().map_err(|err| invalid(&err))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+      .next_frame(&mut buffer)
+      .map_err(|err| invalid(&err))?;
+    buffer.truncate(info.buffer_size());
+
+    let pixels = match (info.color_type, info.bit_depth) {
+      (png::ColorType::Rgba, png::BitDepth::Eight) => buffer,
+      (png::ColorType::Rgb, png::BitDepth::Eight) => buffer
+        .chunks(3)
+        .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+        .collect(),
+      (color_type, bit_depth) => {
+        let format = format!("{:?} {:?} is not 8 bit RGB(A)", color_type, bit_depth);
+        return Err(invalid(&format).into());
+      }
+    };
+    Ok(Image::new(info.width, info.height, pixels))
+  }
+
+  pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let invalid = |err: png::EncodingError| {
+      io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), err),
+      )
+    };
+
+    let mut encoder =
+      png::Encoder::new(BufWriter::new(File::create(path)?), self.width, self.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(invalid)?;
+    writer.write_image_data(&self.pixels).map_err(invalid)?;
+    writer.finish().map_err(invalid)?;
+    Ok(())
+  }
+}
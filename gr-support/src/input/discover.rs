@@ -0,0 +1,143 @@
+//! Finding the input devices there are.
+//!
+//! The kernel makes an `eventN` node in `/dev/input` for every device, and a legacy `mice` node
+//! merging every mouse into a single PS/2 stream. udev links them from `/dev/input/by-id` with
+//! names that stay the same from one boot to the next, such as
+//! `usb-Logitech_USB_Optical_Mouse-event-mouse`, for the devices that are plugged in rather than
+//! built in. The legacy `mouseN` nodes repeat what an event node reports and are left out.
+
+use super::{evdev, Class};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Where the kernel puts input device nodes.
+pub const INPUT_DIR: &str = "/dev/input";
+
+/// What a legacy node calls itself, having no name of its own.
+pub const PS2_NAME: &str = "PS/2 mouse";
+
+/// A device [`scan`] found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Found {
+  /// The node, `eventN` or `mice`.
+  pub path: PathBuf,
+  /// udev's link to it in `by-id`, if it made one.
+  pub link: Option<PathBuf>,
+  pub name: String,
+  /// Empty for a device of no class, such as a power button.
+  pub classes: Vec<Class>,
+}
+
+impl Found {
+  /// The evdev `device` opened at `path`.
+  pub(crate) fn evdev(path: PathBuf, link: Option<PathBuf>, device: &evdev::Device) -> Found {
+    Found {
+      path,
+      link,
+      name: device.name().to_string(),
+      classes: Class::ALL
+        .iter()
+        .copied()
+        .filter(|&class| device.is(class))
+        .collect(),
+    }
+  }
+
+  pub fn is(&self, class: Class) -> bool {
+    self.classes.contains(&class)
+  }
+}
+
+/// The `N` of an `eventN` node, `None` for any other file.
+pub fn event_number(path: &Path) -> Option<u32> {
+  path
+    .file_name()?
+    .to_str()?
+    .strip_prefix("event")?
+    .parse()
+    .ok()
+}
+
+/// The nodes in `dir` worth opening: the event nodes in order, then `mice` if there is one.
+pub fn nodes<P: AsRef<Path>>(dir: P) -> io::Result<Vec<PathBuf>> {
+  let dir = dir.as_ref();
+  let mut events: Vec<(u32, PathBuf)> = fs::read_dir(dir)?
+    .filter_map(|entry| entry.ok())
+    .map(|entry| entry.path())
+    .filter_map(|path| event_number(&path).map(|number| (number, path)))
+    .collect();
+  events.sort();
+
+  let mut nodes: Vec<PathBuf> = events.into_iter().map(|(_, path)| path).collect();
+  let mice = dir.join("mice");
+  if mice.exists() {
+    nodes.push(mice);
+  }
+  Ok(nodes)
+}
+
+/// udev's links in `dir/by-id`, by name, with the nodes they point to. None when there is no
+/// `by-id`, which udev only makes for the first device that has a stable name.
+pub fn links<P: AsRef<Path>>(dir: P) -> Vec<(PathBuf, PathBuf)> {
+  let mut links: Vec<(PathBuf, PathBuf)> = fs::read_dir(dir.as_ref().join("by-id"))
+    .into_iter()
+    .flatten()
+    .filter_map(|entry| entry.ok())
+    .filter_map(|entry| {
+      let link = entry.path();
+      fs::canonicalize(&link).ok().map(|node| (link, node))
+    })
+    .collect();
+  links.sort();
+  links
+}
+
+/// The devices in `dir`, normally [`INPUT_DIR`], those with a `by-id` link first. Nodes that
+/// cannot be opened, for lack of permission or because they just went away, are left out.
+pub fn scan<P: AsRef<Path>>(dir: P) -> io::Result<Vec<Found>> {
+  let dir = dir.as_ref();
+  let links = links(dir);
+  let link = |path: &Path| {
+    let node = fs::canonicalize(path).ok()?;
+    links
+      .iter()
+      .find(|(_, target)| *target == node)
+      .map(|(link, _)| link.clone())
+  };
+
+  let mut found = Vec::new();
+  for path in nodes(dir)? {
+    if event_number(&path).is_none() {
+      if File::open(&path).is_ok() {
+        found.push(Found {
+          link: None,
+          name: PS2_NAME.to_string(),
+          classes: vec![Class::Pointer],
+          path,
+        });
+      }
+      continue;
+    }
+
+    let device = match evdev::Device::open(&path) {
+      Ok(device) => device,
+      Err(_) => continue,
+    };
+    found.push(Found::evdev(path.clone(), link(&path), &device));
+  }
+
+  // Stable, so `mice` stays last
+  found.sort_by_key(|found| found.link.is_none());
+  Ok(found)
+}
+
+/// Whether to read the legacy `mice` node for a pointer, next to the evdev devices in `found`:
+/// not while there is an evdev pointer, which it repeats, nor a touchscreen, whose touches the
+/// kernel also sends it as presses of the left button.
+pub fn reads_mice(found: &[Found]) -> bool {
+  !found.iter().any(|found| {
+    event_number(&found.path).is_some()
+      && (found.is(Class::Pointer) || found.is(Class::Touchscreen))
+  })
+}
@@ -0,0 +1,550 @@
+//! The kernel's evdev protocol, as read from `/dev/input/event*`.
+//!
+//! Each read returns whole `struct input_event`s: a timestamp, a type, a code and a value. A
+//! device reports a batch of them, such as the X and Y motion and a button of one mouse report,
+//! then a `SYN_REPORT` to say the batch is complete. [`Decoder`] turns the batches into
+//! [`Event`]s, and [`Device`] feeds it from a device node.
+//!
+//! ```ignore
+//! let mut mouse = Device::open("/dev/input/event0")?;
+//! for event in mouse.poll()? {
+//!   if let Event::Motion { dx, dy } = event {
+//!     x += dx;
+//!     y -= dy;
+//!   }
+//! }
+//! ```
+
+use super::{Axis, Button, Class, Event, Key, KeyState, TouchPhase};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+use std::mem;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// From linux/input-event-codes.h
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const EV_ABS: u16 = 0x03;
+
+const SYN_REPORT: u16 = 0;
+const SYN_DROPPED: u16 = 3;
+
+const KEY_MAX: u16 = 0x2ff;
+const REL_MAX: u16 = 0x0f;
+const ABS_MAX: u16 = 0x3f;
+
+const REL_X: u16 = 0x00;
+const REL_Y: u16 = 0x01;
+const REL_HWHEEL: u16 = 0x06;
+const REL_WHEEL: u16 = 0x08;
+
+/// `KEY_KP0` to `KEY_KP9`.
+const KEYPAD: [u16; 10] = [82, 79, 80, 81, 75, 76, 77, 71, 72, 73];
+
+const BTN_LEFT: u16 = 0x110;
+const BTN_RIGHT: u16 = 0x111;
+const BTN_MIDDLE: u16 = 0x112;
+const BTN_SIDE: u16 = 0x113;
+const BTN_EXTRA: u16 = 0x114;
+const BTN_FORWARD: u16 = 0x115;
+const BTN_BACK: u16 = 0x116;
+const BTN_TOOL_PEN: u16 = 0x140;
+const BTN_TOOL_FINGER: u16 = 0x145;
+const BTN_TOUCH: u16 = 0x14a;
+
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_MT_SLOT: u16 = 0x2f;
+const ABS_MT_POSITION_X: u16 = 0x35;
+const ABS_MT_POSITION_Y: u16 = 0x36;
+const ABS_MT_TRACKING_ID: u16 = 0x39;
+const ABS_MT_TOOL_Y: u16 = 0x3d;
+
+/// More fingers than any touchscreen tracks, to ignore slots that are not.
+const MAX_SLOTS: usize = 32;
+
+/// `EVIOCGNAME`, without the length.
+const EVIOCGNAME: u8 = 0x06;
+/// `EVIOCGBIT` for the `EV_*` type added to it, without the length.
+const EVIOCGBIT: u8 = 0x20;
+/// `EVIOCGABS` for the `ABS_*` axis added to it.
+const EVIOCGABS: u8 = 0x40;
+
+/// One `struct input_event`, as the kernel sent it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawEvent {
+  /// Since the epoch, or whichever clock the device was switched to.
+  pub time: Duration,
+  /// `EV_*`.
+  pub kind: u16,
+  pub code: u16,
+  pub value: i32,
+}
+
+/// How wide the two fields of the timestamp in `struct input_event` are: a `long` each, in the
+/// kernel's byte order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+  /// 16 bytes an event, on 32-bit systems such as Raspberry Pi OS before its 64-bit release.
+  Long32,
+  /// 24 bytes an event, on 64-bit systems.
+  Long64,
+}
+
+impl Layout {
+  /// The layout of the system this runs on.
+  pub const NATIVE: Layout = if mem::size_of::<libc::c_long>() == 8 {
+    Layout::Long64
+  } else {
+    Layout::Long32
+  };
+
+  /// The size of an event.
+  pub fn size(self) -> usize {
+    match self {
+      Layout::Long32 => 16,
+      Layout::Long64 => 24,
+    }
+  }
+
+  /// Reads an event from its [`size`](Layout::size) bytes.
+  pub fn parse(self, bytes: &[u8]) -> RawEvent {
+    let long = (self.size() - 8) / 2;
+    let read = |at: usize| match self {
+      Layout::Long32 => {
+        u32::from_ne_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as u64
+      }
+      Layout::Long64 => {
+        let mut value = [0; 8];
+        value.copy_from_slice(&bytes[at..at + 8]);
+        u64::from_ne_bytes(value)
+      }
+    };
+    let (seconds, micros) = (read(0), read(long));
+    let rest = &bytes[2 * long..];
+    RawEvent {
+      time: Duration::from_secs(seconds) + Duration::from_micros(micros),
+      kind: u16::from_ne_bytes([rest[0], rest[1]]),
+      code: u16::from_ne_bytes([rest[2], rest[3]]),
+      value: i32::from_ne_bytes([rest[4], rest[5], rest[6], rest[7]]),
+    }
+  }
+}
+
+/// A multi-touch slot, as the kernel's type B protocol updates it.
+#[derive(Clone, Copy, Debug, Default)]
+struct Slot {
+  /// The tracking ID of the finger in the slot, if any.
+  id: Option<i32>,
+  /// The finger reported down, by its tracking ID.
+  reported: Option<i32>,
+  x: i32,
+  y: i32,
+  moved: bool,
+}
+
+/// Turns the bytes read from an evdev node into [`Event`]s, a batch at a time.
+///
+/// The multi-touch axes are not reported as [`Event::Absolute`], but tracked by slot and
+/// reported as an [`Event::Touch`] for each finger that went down, moved or was lifted, at the
+/// end of the batch.
+#[derive(Clone, Debug)]
+pub struct Decoder {
+  layout: Layout,
+  /// The start of an event the last read stopped in.
+  partial: Vec<u8>,
+  batch: Vec<RawEvent>,
+  /// Set by `SYN_DROPPED`, when the kernel's buffer overflowed: the events up to the next
+  /// `SYN_REPORT` are incomplete and dropped.
+  dropped: bool,
+  /// The slot `ABS_MT_*` events are for, `None` for one past [`MAX_SLOTS`].
+  slot: Option<usize>,
+  slots: Vec<Slot>,
+}
+
+impl Decoder {
+  /// Decodes events laid out as on this system.
+  pub fn new() -> Decoder {
+    Decoder::with_layout(Layout::NATIVE)
+  }
+
+  /// Decodes events laid out as `layout`, e.g. recorded on another system.
+  pub fn with_layout(layout: Layout) -> Decoder {
+    Decoder {
+      layout,
+      partial: Vec::new(),
+      batch: Vec::new(),
+      dropped: false,
+      slot: Some(0),
+      slots: Vec::new(),
+    }
+  }
+
+  /// Decodes `bytes`, which may start or stop in the middle of an event, adding the events of
+  /// the batches they complete to `events`.
+  pub fn feed(&mut self, bytes: &[u8], events: &mut Vec<Event>) {
+    let mut bytes = bytes;
+    let size = self.layout.size();
+    if !self.partial.is_empty() {
+      let missing = (size - self.partial.len()).min(bytes.len());
+      self.partial.extend_from_slice(&bytes[..missing]);
+      bytes = &bytes[missing..];
+      if self.partial.len() < size {
+        return;
+      }
+      let raw = self.layout.parse(&self.partial);
+      self.partial.clear();
+      self.raw(raw, events);
+    }
+
+    let mut chunks = bytes.chunks_exact(size);
+    for chunk in &mut chunks {
+      let raw = self.layout.parse(chunk);
+      self.raw(raw, events);
+    }
+    self.partial.extend_from_slice(chunks.remainder());
+  }
+
+  /// Adds a single event, for events that did not come as bytes.
+  pub fn raw(&mut self, raw: RawEvent, events: &mut Vec<Event>) {
+    match (raw.kind, raw.code) {
+      (EV_SYN, SYN_REPORT) => {
+        if !self.dropped {
+          translate(&self.batch, events);
+          self.touches(events);
+        }
+        self.batch.clear();
+        self.dropped = false;
+      }
+      (EV_SYN, SYN_DROPPED) => {
+        self.batch.clear();
+        self.dropped = true;
+      }
+      (EV_SYN, _) => {}
+      _ if self.dropped => {}
+      (EV_ABS, ABS_MT_SLOT) => {
+        self.slot = Some(raw.value as usize).filter(|&slot| slot < MAX_SLOTS);
+      }
+      (EV_ABS, ABS_MT_TRACKING_ID) | (EV_ABS, ABS_MT_POSITION_X) | (EV_ABS, ABS_MT_POSITION_Y) => {
+        let index = match self.slot {
+          Some(index) => index,
+          None => return,
+        };
+        if self.slots.len() <= index {
+          self.slots.resize(index + 1, Slot::default());
+        }
+        let slot = &mut self.slots[index];
+        match raw.code {
+          // -1 when the finger is lifted
+          ABS_MT_TRACKING_ID => slot.id = Some(raw.value).filter(|&id| id >= 0),
+          ABS_MT_POSITION_X => {
+            slot.x = raw.value;
+            slot.moved = true;
+          }
+          _ => {
+            slot.y = raw.value;
+            slot.moved = true;
+          }
+        }
+      }
+      // The other multi-touch axes, such as the size of the finger
+      (EV_ABS, ABS_MT_SLOT..=ABS_MT_TOOL_Y) => {}
+      _ => self.batch.push(raw),
+    }
+  }
+
+  /// The touch events of the slots that changed since the last batch.
+  fn touches(&mut self, events: &mut Vec<Event>) {
+    for (index, slot) in self.slots.iter_mut().enumerate() {
+      let (x, y) = (slot.x, slot.y);
+      let touch = |phase| Event::Touch {
+        slot: index as u16,
+        phase,
+        x,
+        y,
+      };
+      // A finger lifted and another put down in the same batch is both
+      if slot.reported.is_some() && slot.reported != slot.id {
+        events.push(touch(TouchPhase::Up));
+        slot.reported = None;
+      }
+      match (slot.reported, slot.id) {
+        (None, Some(_)) => events.push(touch(TouchPhase::Down)),
+        (Some(_), Some(_)) if slot.moved => events.push(touch(TouchPhase::Move)),
+        _ => {}
+      }
+      slot.reported = slot.id;
+      slot.moved = false;
+    }
+  }
+}
+
+impl Default for Decoder {
+  fn default() -> Self {
+    Decoder::new()
+  }
+}
+
+fn button(code: u16) -> Option<Button> {
+  let button = match code {
+    BTN_LEFT => Button::Left,
+    BTN_RIGHT => Button::Right,
+    BTN_MIDDLE => Button::Middle,
+    BTN_SIDE => Button::Side,
+    BTN_EXTRA => Button::Extra,
+    BTN_FORWARD => Button::Forward,
+    BTN_BACK => Button::Back,
+    BTN_TOUCH => Button::Touch,
+    // BTN_MISC up to the end of BTN_DIGI and the wheel buttons, then BTN_TRIGGER_HAPPY
+    0x100..=0x15f | 0x2c0..=0x2ff => Button::Other(code),
+    _ => return None,
+  };
+  Some(button)
+}
+
+/// The events of a complete batch. The relative axes of a batch add up to a single motion and
+/// a single wheel event, where the first of them was.
+fn translate(batch: &[RawEvent], events: &mut Vec<Event>) {
+  let mut motion = None;
+  let mut wheel = None;
+  for raw in batch {
+    match (raw.kind, raw.code) {
+      (EV_REL, REL_X) | (EV_REL, REL_Y) => {
+        let index = *motion.get_or_insert_with(|| {
+          events.push(Event::Motion { dx: 0, dy: 0 });
+          events.len() - 1
+        });
+        if let Event::Motion { dx, dy } = &mut events[index] {
+          match raw.code {
+            REL_X => *dx += raw.value,
+            _ => *dy += raw.value,
+          }
+        }
+      }
+      (EV_REL, REL_WHEEL) | (EV_REL, REL_HWHEEL) => {
+        let index = *wheel.get_or_insert_with(|| {
+          events.push(Event::Wheel {
+            vertical: 0,
+            horizontal: 0,
+          });
+          events.len() - 1
+        });
+        if let Event::Wheel {
+          vertical,
+          horizontal,
+        } = &mut events[index]
+        {
+          match raw.code {
+            REL_WHEEL => *vertical += raw.value,
+            _ => *horizontal += raw.value,
+          }
+        }
+      }
+      (EV_KEY, code) => events.push(match button(code) {
+        Some(button) => Event::Button {
+          button,
+          pressed: raw.value != 0,
+        },
+        None => Event::Key {
+          key: Key(code),
+          state: match raw.value {
+            0 => KeyState::Released,
+            1 => KeyState::Pressed,
+            _ => KeyState::Repeated,
+          },
+        },
+      }),
+      (EV_ABS, code) => events.push(Event::Absolute {
+        axis: Axis(code),
+        value: raw.value,
+      }),
+      // The high resolution wheels repeat REL_WHEEL and REL_HWHEEL, EV_MSC carries scan codes
+      _ => {}
+    }
+  }
+}
+
+/// Which keys, buttons and axes a device has, as `EVIOCGBIT` reports them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+  keys: Vec<u8>,
+  relative: Vec<u8>,
+  absolute: Vec<u8>,
+}
+
+fn bit(bitmap: &[u8], code: u16) -> bool {
+  bitmap
+    .get(code as usize / 8)
+    .is_some_and(|byte| byte & (1 << (code % 8)) != 0)
+}
+
+impl Capabilities {
+  /// From the bitmaps of the `EV_KEY`, `EV_REL` and `EV_ABS` codes, where code `n` is bit
+  /// `n % 8` of byte `n / 8`.
+  pub fn from_bitmaps(keys: &[u8], relative: &[u8], absolute: &[u8]) -> Capabilities {
+    Capabilities {
+      keys: keys.to_vec(),
+      relative: relative.to_vec(),
+      absolute: absolute.to_vec(),
+    }
+  }
+
+  /// Whether the device has the key or button `code`, `KEY_*` or `BTN_*`.
+  pub fn key(&self, code: u16) -> bool {
+    bit(&self.keys, code)
+  }
+
+  /// Whether the device has the relative axis `code`, `REL_*`.
+  pub fn relative(&self, code: u16) -> bool {
+    bit(&self.relative, code)
+  }
+
+  /// Whether the device has the absolute axis `code`, `ABS_*`.
+  pub fn absolute(&self, code: u16) -> bool {
+    bit(&self.absolute, code)
+  }
+
+  /// Whether the device is of `class`, roughly by the rules udev's `input_id` tags devices with.
+  /// A device may be of several, such as a keyboard with a touchpad, or of none, such as a power
+  /// button.
+  pub fn is(&self, class: Class) -> bool {
+    let positions = (self.absolute(ABS_X) && self.absolute(ABS_Y))
+      || (self.absolute(ABS_MT_POSITION_X) && self.absolute(ABS_MT_POSITION_Y));
+    match class {
+      Class::Pointer => self.relative(REL_X) && self.relative(REL_Y) && self.key(BTN_LEFT),
+      // Every key from Esc to D, which media remotes and power buttons do not have, or the
+      // digits of a keypad
+      Class::Keyboard => {
+        (1..32).all(|code| self.key(code)) || KEYPAD.iter().all(|&code| self.key(code))
+      }
+      Class::Touchscreen => {
+        positions && self.key(BTN_TOUCH) && !self.key(BTN_TOOL_FINGER) && !self.key(BTN_TOOL_PEN)
+      }
+      Class::Touchpad => positions && self.key(BTN_TOOL_FINGER) && !self.key(BTN_TOOL_PEN),
+    }
+  }
+}
+
+/// An evdev node, read without blocking.
+pub struct Device {
+  file: File,
+  path: PathBuf,
+  name: String,
+  capabilities: Capabilities,
+  /// The smallest and largest value of each absolute axis, as it was when opened.
+  ranges: Vec<(u16, (i32, i32))>,
+  decoder: Decoder,
+}
+
+impl Device {
+  /// Opens `path`, failing with `InvalidInput` for anything that is not an evdev node, such as
+  /// the legacy `/dev/input/mouse0`.
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Device> {
+    let path = path.as_ref();
+    let file = OpenOptions::new()
+      .read(true)
+      .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+      .open(path)?;
+
+    let mut name = [0u8; 256];
+    let length = ioctl_read(&file, EVIOCGNAME, &mut name).map_err(|err| {
+      if err.raw_os_error() == Some(libc::ENOTTY) || err.raw_os_error() == Some(libc::EINVAL) {
+        io::Error::new(
+          io::ErrorKind::InvalidInput,
+          format!("{} is not an evdev device", path.display()),
+        )
+      } else {
+        err
+      }
+    })?;
+    let name = &name[..length.min(name.len())];
+    let name = name.split(|&byte| byte == 0).next().unwrap_or_default();
+
+    let bitmap = |kind: u16, max: u16| {
+      let mut bitmap = vec![0u8; max as usize / 8 + 1];
+      ioctl_read(&file, EVIOCGBIT + kind as u8, &mut bitmap).map(|_| bitmap)
+    };
+    let capabilities = Capabilities {
+      keys: bitmap(EV_KEY, KEY_MAX)?,
+      relative: bitmap(EV_REL, REL_MAX)?,
+      absolute: bitmap(EV_ABS, ABS_MAX)?,
+    };
+    let ranges = (0..=ABS_MAX)
+      .filter(|&axis| capabilities.absolute(axis))
+      .filter_map(|axis| {
+        // struct input_absinfo: the value, minimum, maximum, fuzz, flat and resolution
+        let mut info = [0u8; 24];
+        ioctl_read(&file, EVIOCGABS + axis as u8, &mut info).ok()?;
+        let field =
+          |at: usize| i32::from_ne_bytes([info[at], info[at + 1], info[at + 2], info[at + 3]]);
+        Some((axis, (field(4), field(8))))
+      })
+      .collect();
+
+    Ok(Device {
+      file,
+      path: path.to_owned(),
+      name: String::from_utf8_lossy(name).into_owned(),
+      capabilities,
+      ranges,
+      decoder: Decoder::new(),
+    })
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// What the device calls itself, e.g. `Logitech USB Optical Mouse`.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn capabilities(&self) -> &Capabilities {
+    &self.capabilities
+  }
+
+  pub fn is(&self, class: Class) -> bool {
+    self.capabilities.is(class)
+  }
+
+  /// The smallest and largest values of the absolute axis `axis`, such as the edges of a
+  /// touchscreen, if the device has it. They are read once, when the device is opened.
+  pub fn range(&self, axis: Axis) -> Option<(i32, i32)> {
+    self
+      .ranges
+      .iter()
+      .find(|&&(code, _)| code == axis.0)
+      .map(|&(_, range)| range)
+  }
+
+  /// The events waiting to be read. Fails with `ENODEV` once the device is unplugged.
+  pub fn poll(&mut self) -> io::Result<Vec<Event>> {
+    let mut events = Vec::new();
+    let mut buffer = [0u8; 64 * 24];
+    loop {
+      match self.file.read(&mut buffer) {
+        Ok(0) => break,
+        Ok(count) => self.decoder.feed(&buffer[..count], &mut events),
+        Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+        Err(err) => return Err(err),
+      }
+    }
+    Ok(events)
+  }
+}
+
+/// An `_IOC(_IOC_READ, 'E', nr, buffer.len())` ioctl, which fills `buffer`.
+fn ioctl_read(file: &File, nr: u8, buffer: &mut [u8]) -> io::Result<usize> {
+  let request = (2 << 30) | ((buffer.len() as u64) << 16) | ((b'E' as u64) << 8) | nr as u64;
+  let result = unsafe { libc::ioctl(file.as_raw_fd(), request as _, buffer.as_mut_ptr()) };
+  if result < 0 {
+    return Err(io::Error::last_os_error());
+  }
+  Ok(result as usize)
+}
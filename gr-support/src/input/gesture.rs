@@ -0,0 +1,241 @@
+//! Taps, drags, pinches and rotations, from the [`Event::Touch`]es of a multi-touch surface.
+//!
+//! ```ignore
+//! let mut gestures = Recogniser::new();
+//! loop {
+//!   for gesture in gestures.feed(&touchscreen.poll(), start.elapsed()) {
+//!     match gesture {
+//!       Gesture::Pinch { scale, .. } => zoom *= scale,
+//!       ...
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Positions are in the units of the events fed, scaled to pixels or not. A drag is reported
+//! as the fingers move, a pinch and a rotation as the first two fingers down move apart or
+//! around each other, and a tap once the finger is lifted. The moves fed together make one
+//! gesture: a device reports each finger on its own, and two fingers sliding side by side would
+//! otherwise look like a pinch back and forth.
+
+use super::{Event, TouchPhase};
+use std::f32::consts::PI;
+use std::time::Duration;
+
+/// What the fingers did.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+  /// A finger put down and lifted soon after, without moving further than the slop, alone on
+  /// the surface all along.
+  Tap { x: f32, y: f32 },
+  /// The fingers moved by `(dx, dy)`, to where their centroid is `(x, y)`.
+  Drag {
+    fingers: usize,
+    x: f32,
+    y: f32,
+    dx: f32,
+    dy: f32,
+  },
+  /// Two fingers moved apart, for a `scale` over 1, or together, about `(x, y)`.
+  Pinch { x: f32, y: f32, scale: f32 },
+  /// Two fingers turned about `(x, y)` by `angle` radians, clockwise on a surface whose `y`
+  /// grows downwards.
+  Rotate { x: f32, y: f32, angle: f32 },
+}
+
+/// Where the fingers are, to tell how they moved.
+#[derive(Clone, Copy, Debug)]
+struct Measure {
+  centroid: (f32, f32),
+  /// The distance between the first two fingers and the angle of the line through them.
+  span: Option<(f32, f32)>,
+}
+
+/// Turns touch events into [`Gesture`]s.
+#[derive(Clone, Debug)]
+pub struct Recogniser {
+  slop: f32,
+  tap_time: Duration,
+  /// The fingers down, by slot.
+  fingers: Vec<(u16, f32, f32)>,
+  /// When and where the finger went down, while it may still be a tap.
+  tap: Option<(Duration, f32, f32)>,
+  /// Whether the fingers moved far enough to drag.
+  dragging: bool,
+  /// Whether the fingers moved since last reported.
+  moved: bool,
+  /// Where the fingers were when last reported.
+  last: Option<Measure>,
+}
+
+impl Default for Recogniser {
+  fn default() -> Self {
+    Recogniser::new()
+  }
+}
+
+impl Recogniser {
+  /// Taps up to 250 ms long, with a slop of 10 units.
+  pub fn new() -> Recogniser {
+    Recogniser {
+      slop: 10.0,
+      tap_time: Duration::from_millis(250),
+      fingers: Vec::new(),
+      tap: None,
+      dragging: false,
+      moved: false,
+      last: None,
+    }
+  }
+
+  /// How far a single finger moves before it drags rather than taps, in the units of the events.
+  pub fn slop(self, slop: f32) -> Recogniser {
+    Recogniser { slop, ..self }
+  }
+
+  /// How long a tap lasts at most.
+  pub fn tap_time(self, tap_time: Duration) -> Recogniser {
+    Recogniser { tap_time, ..self }
+  }
+
+  /// The number of fingers down.
+  pub fn fingers(&self) -> usize {
+    self.fingers.len()
+  }
+
+  /// The gestures of the touch events in `events`, which happened at `time`, counted from any
+  /// fixed point. Other events are skipped. Feed whole batches, as a poll returns them: the
+  /// moves in `events` are reported together, up to a finger going down or up.
+  pub fn feed(&mut self, events: &[Event], time: Duration) -> Vec<Gesture> {
+    let mut gestures = Vec::new();
+    for event in events {
+      if let Event::Touch { slot, phase, x, y } = *event {
+        self.touch(slot, phase, x as f32, y as f32, time, &mut gestures);
+      }
+    }
+    self.flush(&mut gestures);
+    gestures
+  }
+
+  fn touch(
+    &mut self,
+    slot: u16,
+    phase: TouchPhase,
+    x: f32,
+    y: f32,
+    time: Duration,
+    gestures: &mut Vec<Gesture>,
+  ) {
+    let index = self.fingers.iter().position(|&(down, _, _)| down == slot);
+    match (phase, index) {
+      (TouchPhase::Down, _) | (TouchPhase::Move, None) => {
+        self.flush(gestures);
+        if let Some(index) = index {
+          self.fingers.remove(index);
+        }
+        self.fingers.push((slot, x, y));
+        self.fingers.sort_by_key(|&(slot, _, _)| slot);
+        // A second finger makes it something else than a tap
+        self.tap = match self.fingers.len() {
+          1 => Some((time, x, y)),
+          _ => None,
+        };
+        self.dragging = self.fingers.len() > 1;
+        self.last = self.measure();
+      }
+      (TouchPhase::Move, Some(index)) => {
+        self.fingers[index] = (slot, x, y);
+        if !self.dragging {
+          let moved = self
+            .tap
+            .is_some_and(|(_, x0, y0)| (x - x0).hypot(y - y0) > self.slop);
+          if !moved {
+            return;
+          }
+          self.tap = None;
+          self.dragging = true;
+        }
+        self.moved = true;
+      }
+      (TouchPhase::Up, index) => {
+        self.flush(gestures);
+        if let Some(index) = index {
+          self.fingers.remove(index);
+        }
+        if self.fingers.is_empty() {
+          if let Some((down, _, _)) = self.tap.take() {
+            if time.saturating_sub(down) <= self.tap_time {
+              gestures.push(Gesture::Tap { x, y });
+            }
+          }
+          self.dragging = false;
+        }
+        // The fingers left go on from where they are
+        self.last = self.measure();
+      }
+    }
+  }
+
+  /// Reports how the fingers moved since last reported, before they change or the batch ends.
+  fn flush(&mut self, gestures: &mut Vec<Gesture>) {
+    if !std::mem::take(&mut self.moved) {
+      return;
+    }
+    if let (Some(last), Some(now)) = (self.last, self.measure()) {
+      report(last, now, self.fingers.len(), gestures);
+      self.last = Some(now);
+    }
+  }
+
+  fn measure(&self) -> Option<Measure> {
+    if self.fingers.is_empty() {
+      return None;
+    }
+    let count = self.fingers.len() as f32;
+    let centroid = self
+      .fingers
+      .iter()
+      .fold((0.0, 0.0), |(sx, sy), &(_, x, y)| {
+        (sx + x / count, sy + y / count)
+      });
+    let span = match self.fingers[..] {
+      [(_, x0, y0), (_, x1, y1), ..] => Some(((x1 - x0).hypot(y1 - y0), (y1 - y0).atan2(x1 - x0))),
+      _ => None,
+    };
+    Some(Measure { centroid, span })
+  }
+}
+
+fn report(last: Measure, now: Measure, fingers: usize, gestures: &mut Vec<Gesture>) {
+  let (x, y) = now.centroid;
+  let (dx, dy) = (x - last.centroid.0, y - last.centroid.1);
+  if dx != 0.0 || dy != 0.0 {
+    gestures.push(Gesture::Drag {
+      fingers,
+      x,
+      y,
+      dx,
+      dy,
+    });
+  }
+
+  if let (Some((distance0, angle0)), Some((distance, angle))) = (last.span, now.span) {
+    if distance0 > 0.0 && distance != distance0 {
+      gestures.push(Gesture::Pinch {
+        x,
+        y,
+        scale: distance / distance0,
+      });
+    }
+    // The smaller way round
+    let mut turn = angle - angle0;
+    if turn > PI {
+      turn -= 2.0 * PI;
+    } else if turn <= -PI {
+      turn += 2.0 * PI;
+    }
+    if turn != 0.0 {
+      gestures.push(Gesture::Rotate { x, y, angle: turn });
+    }
+  }
+}
@@ -0,0 +1,300 @@
+//! Following input devices as they are plugged in and unplugged.
+//!
+//! ```ignore
+//! let mut pointers = Devices::any(Class::Pointer);
+//! loop {
+//!   for event in pointers.poll() {
+//!     if let Event::Motion { dx, dy } = event {
+//!       x += dx;
+//!       y -= dy;
+//!     }
+//!   }
+//!   ...
+//! }
+//! ```
+
+use super::discover::{self, Found, INPUT_DIR};
+use super::{evdev, ps2, Class, Device, Event};
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A node, or a `by-id` link, appearing or going away.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+  Added(PathBuf),
+  Removed(PathBuf),
+}
+
+/// Watches an input directory, normally [`INPUT_DIR`], and its `by-id` links with inotify.
+///
+/// The kernel makes the node of a device as soon as it is plugged in, readable by root only,
+/// and udev then changes its group and permissions. A node is `Added` again when that happens,
+/// as it may only now be possible to open it.
+pub struct Watcher {
+  inotify: Inotify,
+  dir: PathBuf,
+  watches: Vec<(WatchDescriptor, PathBuf)>,
+  buffer: Vec<u8>,
+}
+
+impl Watcher {
+  pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<Watcher> {
+    let dir = dir.as_ref();
+    let mut watcher = Watcher {
+      inotify: Inotify::init()?,
+      dir: dir.to_owned(),
+      watches: Vec::new(),
+      buffer: vec![0; 4096],
+    };
+
+    watcher.watch(dir)?;
+    let by_id = dir.join("by-id");
+    if by_id.is_dir() {
+      watcher.watch(&by_id)?;
+    }
+
+    Ok(watcher)
+  }
+
+  fn watch(&mut self, dir: &Path) -> io::Result<()> {
+    let watch = self.inotify.add_watch(
+      dir,
+      WatchMask::CREATE
+        | WatchMask::ATTRIB
+        | WatchMask::MOVED_TO
+        | WatchMask::DELETE
+        | WatchMask::MOVED_FROM,
+    )?;
+    if !self.watches.iter().any(|(wd, _)| *wd == watch) {
+      self.watches.push((watch, dir.to_owned()));
+    }
+    Ok(())
+  }
+
+  /// What changed since the last call. Never blocks.
+  pub fn changes(&mut self) -> io::Result<Vec<Change>> {
+    let mut changes = Vec::new();
+    let by_id = self.dir.join("by-id");
+    let mut by_id_created = false;
+
+    loop {
+      let events = self.inotify.read_events(&mut self.buffer)?;
+      let mut any = false;
+      for event in events {
+        any = true;
+        let dir = self.watches.iter().find(|(wd, _)| *wd == event.wd);
+        let path = match (dir, event.name) {
+          (Some((_, dir)), Some(name)) => dir.join(name),
+          _ => continue,
+        };
+
+        let removed = event
+          .mask
+          .intersects(EventMask::DELETE | EventMask::MOVED_FROM);
+        if event.mask.contains(EventMask::ISDIR) {
+          by_id_created |= path == by_id && !removed;
+        } else if removed {
+          changes.push(Change::Removed(path));
+        } else {
+          changes.push(Change::Added(path));
+        }
+      }
+      if !any {
+        break;
+      }
+    }
+
+    // Links made before the watch was are only found by looking
+    if by_id_created {
+      self.watch(&by_id)?;
+      for (link, _) in discover::links(&self.dir) {
+        changes.push(Change::Added(link));
+      }
+    }
+
+    Ok(changes)
+  }
+}
+
+/// What [`Devices`] reads.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Wanted {
+  Any(Class),
+  Path(PathBuf),
+}
+
+/// The devices of a class, or the one at a path, opened as they are plugged in and dropped as
+/// they are unplugged, their events merged.
+///
+/// Without inotify, or while the input directory does not exist, which is the case on a system
+/// that never had an input device, it tries again to watch it every second.
+pub struct Devices {
+  dir: PathBuf,
+  wanted: Wanted,
+  open: Vec<Device>,
+  /// Every evdev device opened to see whether it is wanted, to tell whether to read `mice`.
+  seen: Vec<Found>,
+  watcher: Option<Watcher>,
+  /// When to try to watch the directory again, without a watcher.
+  retry: Instant,
+}
+
+impl Devices {
+  /// Every device of `class` in [`INPUT_DIR`]. Pointers are read from the legacy `mice` node
+  /// while there is no evdev one that can be, and no touchscreen, see [`discover::reads_mice`].
+  pub fn any(class: Class) -> Devices {
+    Devices::new(Wanted::Any(class))
+  }
+
+  /// The device at `path`, an evdev or legacy node or a `by-id` link to one, opened again when
+  /// it comes back after being unplugged.
+  pub fn path<P: Into<PathBuf>>(path: P) -> Devices {
+    Devices::new(Wanted::Path(path.into()))
+  }
+
+  fn new(wanted: Wanted) -> Devices {
+    let dir = PathBuf::from(INPUT_DIR);
+    // Before scanning, so a device plugged in meanwhile is not missed
+    let watcher = Watcher::new(&dir).ok();
+    let mut devices = Devices {
+      dir,
+      wanted,
+      open: Vec::new(),
+      seen: Vec::new(),
+      watcher,
+      retry: Instant::now() + Duration::from_secs(1),
+    };
+    devices.rescan();
+    devices
+  }
+
+  /// The devices being read.
+  pub fn devices(&self) -> &[Device] {
+    &self.open
+  }
+
+  /// The events of every device since the last call. A device that fails to read, as it does
+  /// once unplugged, is dropped until it is plugged in again.
+  pub fn poll(&mut self) -> Vec<Event> {
+    self.poll_with(|_, _| {})
+  }
+
+  /// Like [`poll`](Devices::poll), handing the events of each device to `each` before they are
+  /// merged, e.g. to scale touches by the ranges of the screen they came from.
+  pub fn poll_with<F: FnMut(&Device, &mut [Event])>(&mut self, mut each: F) -> Vec<Event> {
+    self.hotplug();
+
+    let mut events = Vec::new();
+    let before = self.open.len();
+    let seen = &mut self.seen;
+    self.open.retain_mut(|device| match device.poll() {
+      Ok(mut polled) => {
+        each(device, &mut polled);
+        events.append(&mut polled);
+        true
+      }
+      Err(_) => {
+        seen.retain(|found| found.path != device.path());
+        false
+      }
+    });
+    if self.open.len() != before {
+      self.fallback();
+    }
+    events
+  }
+
+  fn hotplug(&mut self) {
+    let changes = match &mut self.watcher {
+      Some(watcher) => watcher.changes(),
+      None => {
+        if Instant::now() >= self.retry {
+          self.retry = Instant::now() + Duration::from_secs(1);
+          self.watcher = Watcher::new(&self.dir).ok();
+          if self.watcher.is_some() {
+            self.rescan();
+          }
+        }
+        return;
+      }
+    };
+    let changes = match changes {
+      Ok(changes) => changes,
+      Err(_) => {
+        self.watcher = None;
+        return;
+      }
+    };
+    if changes.is_empty() {
+      return;
+    }
+    for change in changes {
+      match change {
+        Change::Added(path) => self.add(&path),
+        Change::Removed(path) => {
+          self.open.retain(|device| device.path() != path);
+          self.seen.retain(|found| found.path != path);
+        }
+      }
+    }
+    self.fallback();
+  }
+
+  fn rescan(&mut self) {
+    let paths = match &self.wanted {
+      Wanted::Path(path) => vec![path.clone()],
+      Wanted::Any(_) => discover::nodes(&self.dir).unwrap_or_default(),
+    };
+    for path in paths {
+      self.add(&path);
+    }
+    self.fallback();
+  }
+
+  /// Opens `path` if it is wanted and not open yet.
+  fn add(&mut self, path: &Path) {
+    match &self.wanted {
+      // Whichever node or link changed, the device may be back
+      Wanted::Path(wanted) => {
+        if self.open.is_empty() {
+          if let Ok(device) = Device::open(wanted) {
+            self.open.push(device);
+          }
+        }
+      }
+      Wanted::Any(class) => {
+        if discover::event_number(path).is_none()
+          || self.open.iter().any(|device| device.path() == path)
+        {
+          return;
+        }
+        if let Ok(device) = evdev::Device::open(path) {
+          self.seen.retain(|found| found.path != path);
+          self.seen.push(Found::evdev(path.to_owned(), None, &device));
+          if device.is(*class) {
+            self.open.push(Device::Evdev(device));
+          }
+        }
+      }
+    }
+  }
+
+  /// Reads the legacy `mice` node while [`discover::reads_mice`] says to, and drops it once an
+  /// evdev pointer or touchscreen turns up.
+  fn fallback(&mut self) {
+    if self.wanted != Wanted::Any(Class::Pointer) {
+      return;
+    }
+    if !discover::reads_mice(&self.seen) {
+      self
+        .open
+        .retain(|device| matches!(device, Device::Evdev(_)));
+    } else if self.open.is_empty() {
+      if let Ok(device) = ps2::Device::open(self.dir.join("mice")) {
+        self.open.push(Device::Ps2(device));
+      }
+    }
+  }
+}
@@ -0,0 +1,139 @@
+//! Which key does what, in a table a demo starts from and a file can change.
+//!
+//! A demo names its actions with a type implementing [`FromStr`], and binds keys to them:
+//!
+//! ```ignore
+//! let keymap = Keymap::new()
+//!   .bind(Action::Quit, &[Key::ESC, Key::Q])
+//!   .bind(Action::Reset, &[Key::R, Key::KP5])
+//!   .load(&fs::read_to_string("keys.conf")?)?;
+//! for action in keymap.actions(&events) {
+//!   ...
+//! }
+//! ```
+//!
+//! The file has a line for each action to bind differently, with the names of its keys, as
+//! [`Key::from_name`] reads them, after an `=`. An action with no keys is not bound at all:
+//!
+//! ```text
+//! # The keypad of the kiosk
+//! quit =
+//! zoom-in = kpplus kp9
+//! zoom-out = kpminus kp3
+//! ```
+
+use super::{Event, Key, KeyState};
+use std::fmt;
+use std::str::FromStr;
+
+/// A line of a keymap file that could not be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeymapError {
+  /// Counting from 1.
+  pub line: usize,
+  pub message: String,
+}
+
+impl fmt::Display for KeymapError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+impl std::error::Error for KeymapError {}
+
+/// Keys bound to actions of type `A`, a key to a single action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap<A> {
+  bindings: Vec<(Key, A)>,
+}
+
+impl<A> Default for Keymap<A> {
+  fn default() -> Self {
+    Keymap {
+      bindings: Vec::new(),
+    }
+  }
+}
+
+impl<A: Copy + PartialEq + FromStr> Keymap<A> {
+  /// A keymap with nothing bound.
+  pub fn new() -> Keymap<A> {
+    Keymap::default()
+  }
+
+  /// Binds `keys` to `action`, besides the keys it has already. Keys bound to another action
+  /// are taken from it.
+  pub fn bind(mut self, action: A, keys: &[Key]) -> Keymap<A> {
+    self.bindings.retain(|(key, _)| !keys.contains(key));
+    self.bindings.extend(keys.iter().map(|&key| (key, action)));
+    self
+  }
+
+  /// Unbinds every key of `action`.
+  pub fn unbind(mut self, action: A) -> Keymap<A> {
+    self.bindings.retain(|&(_, bound)| bound != action);
+    self
+  }
+
+  /// What `key` does.
+  pub fn action(&self, key: Key) -> Option<A> {
+    self
+      .bindings
+      .iter()
+      .find(|&&(bound, _)| bound == key)
+      .map(|&(_, action)| action)
+  }
+
+  /// The keys bound to `action`, in the order they were.
+  pub fn keys(&self, action: A) -> Vec<Key> {
+    self
+      .bindings
+      .iter()
+      .filter(|&&(_, bound)| bound == action)
+      .map(|&(key, _)| key)
+      .collect()
+  }
+
+  /// The actions of the keys pressed in `events`, and of those held down long enough to
+  /// repeat.
+  pub fn actions(&self, events: &[Event]) -> Vec<A> {
+    events
+      .iter()
+      .filter_map(|event| match *event {
+        Event::Key { key, state } if state != KeyState::Released => self.action(key),
+        _ => None,
+      })
+      .collect()
+  }
+
+  /// Binds the actions `text` has a line for to the keys it lists instead, see the
+  /// [module](self) for the format.
+  pub fn load(self, text: &str) -> Result<Keymap<A>, KeymapError> {
+    let mut keymap = self;
+    for (index, line) in text.lines().enumerate() {
+      let error = |message: String| KeymapError {
+        line: index + 1,
+        message,
+      };
+      let line = line.split('#').next().unwrap_or_default().trim();
+      if line.is_empty() {
+        continue;
+      }
+
+      let (name, keys) = line
+        .split_once('=')
+        .ok_or_else(|| error(format!("expected ACTION = KEYS, not {:?}", line)))?;
+      let action = name
+        .trim()
+        .parse::<A>()
+        .map_err(|_| error(format!("no action called {:?}", name.trim())))?;
+      let keys = keys
+        .split_whitespace()
+        .map(|name| Key::from_name(name).ok_or_else(|| error(format!("no key called {:?}", name))))
+        .collect::<Result<Vec<Key>, KeymapError>>()?;
+      keymap = keymap.unbind(action).bind(action, &keys);
+    }
+    Ok(keymap)
+  }
+}
@@ -0,0 +1,307 @@
+//! Mice, keyboards and other input devices.
+//!
+//! [`evdev`] reads the kernel's `/dev/input/event*` nodes and turns what they report into
+//! [`Event`]s, the same for every kind of device. [`ps2`] reads the legacy mouse nodes into the
+//! same events. [`discover`] finds the devices there are, and, with the `hotplug` feature,
+//! [`hotplug::Devices`] keeps reading those of a class as they are plugged and unplugged.
+//! [`tty`] reads keys from a terminal instead, and [`keymap`] maps keys to what they do.
+//! [`gesture`] recognises taps, drags, pinches and rotations in multi-touch events.
+
+pub mod discover;
+pub mod evdev;
+pub mod gesture;
+#[cfg(feature = "hotplug")]
+pub mod hotplug;
+pub mod keymap;
+pub mod ps2;
+pub mod tty;
+
+use std::io;
+use std::path::Path;
+
+/// What a device is for, judged from the keys and axes it has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Class {
+  /// A mouse, trackball or anything else reporting relative motion and a left button.
+  Pointer,
+  /// A keyboard, or a numeric keypad on its own.
+  Keyboard,
+  Touchscreen,
+  Touchpad,
+}
+
+impl Class {
+  pub const ALL: [Class; 4] = [
+    Class::Pointer,
+    Class::Keyboard,
+    Class::Touchscreen,
+    Class::Touchpad,
+  ];
+}
+
+/// An open device node of either kind.
+pub enum Device {
+  Evdev(evdev::Device),
+  Ps2(ps2::Device),
+}
+
+impl Device {
+  /// Opens `path` as an evdev node, or as a legacy PS/2 mouse if it is not one.
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Device> {
+    let path = path.as_ref();
+    match evdev::Device::open(path) {
+      Ok(device) => Ok(Device::Evdev(device)),
+      Err(err) if err.kind() == io::ErrorKind::InvalidInput => {
+        ps2::Device::open(path).map(Device::Ps2)
+      }
+      Err(err) => Err(err),
+    }
+  }
+
+  pub fn path(&self) -> &Path {
+    match self {
+      Device::Evdev(device) => device.path(),
+      Device::Ps2(device) => device.path(),
+    }
+  }
+
+  pub fn name(&self) -> &str {
+    match self {
+      Device::Evdev(device) => device.name(),
+      Device::Ps2(_) => discover::PS2_NAME,
+    }
+  }
+
+  /// Whether the device is of `class`. PS/2 nodes are always pointers.
+  pub fn is(&self, class: Class) -> bool {
+    match self {
+      Device::Evdev(device) => device.is(class),
+      Device::Ps2(_) => class == Class::Pointer,
+    }
+  }
+
+  /// The events waiting to be read. Fails once the device is unplugged.
+  pub fn poll(&mut self) -> io::Result<Vec<Event>> {
+    match self {
+      Device::Evdev(device) => device.poll(),
+      Device::Ps2(device) => device.poll(),
+    }
+  }
+}
+
+/// A pointer button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Button {
+  Left,
+  Right,
+  Middle,
+  Side,
+  Extra,
+  Forward,
+  Back,
+  /// A finger or stylus on a touchscreen or tablet.
+  Touch,
+  /// Any other `BTN_*` code.
+  Other(u16),
+}
+
+/// A key, by its Linux code, `KEY_*` in `linux/input-event-codes.h`. US layout names, whatever
+/// the keyboard prints on the key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Key(pub u16);
+
+impl Key {
+  pub const ESC: Key = Key(1);
+  pub const MINUS: Key = Key(12);
+  pub const EQUAL: Key = Key(13);
+  pub const BACKSPACE: Key = Key(14);
+  pub const TAB: Key = Key(15);
+  pub const Q: Key = Key(16);
+  pub const R: Key = Key(19);
+  pub const ENTER: Key = Key(28);
+  pub const S: Key = Key(31);
+  pub const J: Key = Key(36);
+  pub const COMMA: Key = Key(51);
+  pub const DOT: Key = Key(52);
+  pub const SLASH: Key = Key(53);
+  pub const KP_ASTERISK: Key = Key(55);
+  pub const SPACE: Key = Key(57);
+  pub const KP7: Key = Key(71);
+  pub const KP8: Key = Key(72);
+  pub const KP9: Key = Key(73);
+  pub const KP_MINUS: Key = Key(74);
+  pub const KP4: Key = Key(75);
+  pub const KP5: Key = Key(76);
+  pub const KP6: Key = Key(77);
+  pub const KP_PLUS: Key = Key(78);
+  pub const KP1: Key = Key(79);
+  pub const KP2: Key = Key(80);
+  pub const KP3: Key = Key(81);
+  pub const KP0: Key = Key(82);
+  pub const KP_DOT: Key = Key(83);
+  pub const KP_ENTER: Key = Key(96);
+  pub const KP_SLASH: Key = Key(98);
+  pub const HOME: Key = Key(102);
+  pub const UP: Key = Key(103);
+  pub const PAGE_UP: Key = Key(104);
+  pub const LEFT: Key = Key(105);
+  pub const RIGHT: Key = Key(106);
+  pub const END: Key = Key(107);
+  pub const DOWN: Key = Key(108);
+  pub const PAGE_DOWN: Key = Key(109);
+  pub const INSERT: Key = Key(110);
+  pub const DELETE: Key = Key(111);
+
+  /// The key for a letter, either case, or a digit, on the main block of a US keyboard.
+  pub fn from_char(c: char) -> Option<Key> {
+    let c = c.to_ascii_lowercase();
+    let code = match c {
+      '1'..='9' => c as u16 - '1' as u16 + 2,
+      '0' => 11,
+      'a'..='z' => LETTERS[(c as u8 - b'a') as usize],
+      _ => return None,
+    };
+    Some(Key(code))
+  }
+
+  /// The key called `name`, case aside: a letter or digit, `kp0` to `kp9` on the keypad, or one
+  /// of the names in [`NAMES`](Key::NAMES).
+  pub fn from_name(name: &str) -> Option<Key> {
+    let name = name.to_ascii_lowercase();
+    let mut chars = name.chars();
+    match (chars.next(), chars.next(), chars.next(), chars.next()) {
+      (Some(c), None, _, _) => return Key::from_char(c),
+      (Some('k'), Some('p'), Some(digit @ '0'..='9'), None) => {
+        return Some(KEYPAD[digit as usize - '0' as usize])
+      }
+      (Some('f'), Some(_), _, _) => {
+        if let Ok(n @ 1..=12) = name[1..].parse::<u16>() {
+          // F11 and F12 came after the keypad
+          return Some(Key(if n <= 10 { 58 + n } else { 76 + n }));
+        }
+      }
+      _ => {}
+    }
+    Key::NAMES
+      .iter()
+      .find(|(known, _)| *known == name)
+      .map(|&(_, key)| key)
+  }
+
+  /// The names of the keys that are not letters, digits, keypad digits or function keys.
+  pub const NAMES: &'static [(&'static str, Key)] = &[
+    ("esc", Key::ESC),
+    ("minus", Key::MINUS),
+    ("equal", Key::EQUAL),
+    ("backspace", Key::BACKSPACE),
+    ("tab", Key::TAB),
+    ("enter", Key::ENTER),
+    ("comma", Key::COMMA),
+    ("dot", Key::DOT),
+    ("slash", Key::SLASH),
+    ("space", Key::SPACE),
+    ("kpasterisk", Key::KP_ASTERISK),
+    ("kpminus", Key::KP_MINUS),
+    ("kpplus", Key::KP_PLUS),
+    ("kpdot", Key::KP_DOT),
+    ("kpenter", Key::KP_ENTER),
+    ("kpslash", Key::KP_SLASH),
+    ("home", Key::HOME),
+    ("up", Key::UP),
+    ("pageup", Key::PAGE_UP),
+    ("left", Key::LEFT),
+    ("right", Key::RIGHT),
+    ("end", Key::END),
+    ("down", Key::DOWN),
+    ("pagedown", Key::PAGE_DOWN),
+    ("insert", Key::INSERT),
+    ("delete", Key::DELETE),
+  ];
+}
+
+/// `KEY_A` to `KEY_Z`, in the order of the alphabet rather than the keyboard.
+const LETTERS: [u16; 26] = [
+  30, 48, 46, 32, 18, 33, 34, 35, 23, 36, 37, 38, 50, 49, 24, 25, 16, 19, 31, 20, 22, 47, 17, 45,
+  21, 44,
+];
+
+/// `KEY_KP0` to `KEY_KP9`.
+const KEYPAD: [Key; 10] = [
+  Key::KP0,
+  Key::KP1,
+  Key::KP2,
+  Key::KP3,
+  Key::KP4,
+  Key::KP5,
+  Key::KP6,
+  Key::KP7,
+  Key::KP8,
+  Key::KP9,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyState {
+  Released,
+  Pressed,
+  /// Held down long enough for the keyboard to repeat it.
+  Repeated,
+}
+
+/// An absolute axis, by its Linux code, `ABS_*` in `linux/input-event-codes.h`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Axis(pub u16);
+
+impl Axis {
+  pub const X: Axis = Axis(0x00);
+  pub const Y: Axis = Axis(0x01);
+  pub const PRESSURE: Axis = Axis(0x18);
+  pub const MT_SLOT: Axis = Axis(0x2f);
+  pub const MT_POSITION_X: Axis = Axis(0x35);
+  pub const MT_POSITION_Y: Axis = Axis(0x36);
+  pub const MT_TRACKING_ID: Axis = Axis(0x39);
+}
+
+/// What a finger on a multi-touch surface did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TouchPhase {
+  Down,
+  Move,
+  Up,
+}
+
+/// What a device reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+  /// Pointer motion, in the device's units. `dy` grows downwards, as on screen.
+  Motion {
+    dx: i32,
+    dy: i32,
+  },
+  Button {
+    button: Button,
+    pressed: bool,
+  },
+  /// Wheel clicks, up and right positive.
+  Wheel {
+    vertical: i32,
+    horizontal: i32,
+  },
+  Key {
+    key: Key,
+    state: KeyState,
+  },
+  /// The position on an absolute axis, such as a touchscreen's or a tablet's.
+  Absolute {
+    axis: Axis,
+    value: i32,
+  },
+  /// A finger on a multi-touch surface, in the slot the device tracks it in from when it goes
+  /// down to when it is lifted. The position is in the device's units, `y` growing downwards,
+  /// and where the finger was last for `Up`.
+  Touch {
+    slot: u16,
+    phase: TouchPhase,
+    x: i32,
+    y: i32,
+  },
+}
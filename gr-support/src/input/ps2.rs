@@ -0,0 +1,303 @@
+//! The PS/2 mouse protocol, as the kernel's legacy `/dev/input/mouseN` and `/dev/input/mice`
+//! nodes still speak it.
+//!
+//! A standard packet is 3 bytes: the buttons, sign and overflow bits, then the low 8 bits of the
+//! X and Y motion. Together with their sign bits, the motions are 9-bit two's complement
+//! numbers, up positive. An IntelliMouse adds a 4th byte, the wheel, once switched to it by
+//! [`enable_wheel`].
+
+use super::{Button, Event};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+const LEFT: u8 = 1 << 0;
+const RIGHT: u8 = 1 << 1;
+const MIDDLE: u8 = 1 << 2;
+/// Always set in the first byte, the only way to find where packets start.
+const ALWAYS_SET: u8 = 1 << 3;
+const X_SIGN: u8 = 1 << 4;
+const Y_SIGN: u8 = 1 << 5;
+const X_OVERFLOW: u8 = 1 << 6;
+const Y_OVERFLOW: u8 = 1 << 7;
+
+const SET_SAMPLE_RATE: u8 = 0xf3;
+const GET_DEVICE_ID: u8 = 0xf2;
+const ACK: u8 = 0xfa;
+
+/// Setting these sample rates in a row is how an IntelliMouse is asked to report its wheel.
+pub const INTELLIMOUSE_SEQUENCE: [u8; 6] = [
+  SET_SAMPLE_RATE,
+  200,
+  SET_SAMPLE_RATE,
+  100,
+  SET_SAMPLE_RATE,
+  80,
+];
+
+/// Which packets a mouse sends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+  /// 3 bytes, no wheel.
+  Standard,
+  /// 4 bytes, the last one the wheel.
+  IntelliMouse,
+}
+
+impl Protocol {
+  pub fn packet_size(self) -> usize {
+    match self {
+      Protocol::Standard => 3,
+      Protocol::IntelliMouse => 4,
+    }
+  }
+}
+
+/// One decoded packet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Packet {
+  pub left: bool,
+  pub right: bool,
+  pub middle: bool,
+  /// From -256 to 255, right positive.
+  pub dx: i32,
+  /// From -256 to 255, up positive.
+  pub dy: i32,
+  /// Wheel clicks, up positive. A real IntelliMouse stays within -8 to 7, the kernel sends up to
+  /// 127 each way.
+  pub wheel: i32,
+  /// The mouse moved further along X than a packet holds, so `dx` is as far as it goes, in the
+  /// direction of the sign bit.
+  pub x_overflow: bool,
+  pub y_overflow: bool,
+}
+
+/// The first byte of a packet does not have bit 3 set, so the stream is not where a packet
+/// starts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfSync(pub u8);
+
+impl fmt::Display for OutOfSync {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:#04x} cannot start a PS/2 packet", self.0)
+  }
+}
+
+impl std::error::Error for OutOfSync {}
+
+/// A 9-bit motion from its low byte and sign bit, saturated when it overflowed.
+fn motion(low: u8, negative: bool, overflow: bool) -> i32 {
+  match (overflow, negative) {
+    (true, true) => -256,
+    (true, false) => 255,
+    (false, true) => low as i32 - 256,
+    (false, false) => low as i32,
+  }
+}
+
+/// Decodes a packet of `protocol`, the first [`packet_size`](Protocol::packet_size) bytes of
+/// `bytes`.
+///
+/// # Panics
+///
+/// If `bytes` is shorter than a packet.
+pub fn decode(protocol: Protocol, bytes: &[u8]) -> Result<Packet, OutOfSync> {
+  let flags = bytes[0];
+  if flags & ALWAYS_SET == 0 {
+    return Err(OutOfSync(flags));
+  }
+
+  let x_overflow = flags & X_OVERFLOW != 0;
+  let y_overflow = flags & Y_OVERFLOW != 0;
+  let wheel = match protocol {
+    Protocol::Standard => 0,
+    // Signed, down positive
+    Protocol::IntelliMouse => -(bytes[3] as i8 as i32),
+  };
+  Ok(Packet {
+    left: flags & LEFT != 0,
+    right: flags & RIGHT != 0,
+    middle: flags & MIDDLE != 0,
+    dx: motion(bytes[1], flags & X_SIGN != 0, x_overflow),
+    dy: motion(bytes[2], flags & Y_SIGN != 0, y_overflow),
+    wheel,
+    x_overflow,
+    y_overflow,
+  })
+}
+
+/// Turns a stream of packets into [`Event`]s.
+#[derive(Clone, Debug)]
+pub struct Decoder {
+  protocol: Protocol,
+  /// The start of a packet the last read stopped in.
+  partial: Vec<u8>,
+  buttons: [bool; 3],
+  skipped: u64,
+}
+
+impl Decoder {
+  pub fn new(protocol: Protocol) -> Decoder {
+    Decoder {
+      protocol,
+      partial: Vec::new(),
+      buttons: [false; 3],
+      skipped: 0,
+    }
+  }
+
+  pub fn protocol(&self) -> Protocol {
+    self.protocol
+  }
+
+  /// Bytes dropped to find the start of a packet again. A few after opening the device are
+  /// normal, many mean the protocol is wrong.
+  pub fn skipped(&self) -> u64 {
+    self.skipped
+  }
+
+  /// Decodes `bytes`, which may start or stop in the middle of a packet, adding an event for
+  /// each button that changed, the motion and the wheel to `events`. Motion is reported with
+  /// `dy` growing downwards, as evdev does.
+  pub fn feed(&mut self, bytes: &[u8], events: &mut Vec<Event>) {
+    let size = self.protocol.packet_size();
+    self.partial.extend_from_slice(bytes);
+    let mut start = 0;
+    while self.partial.len() - start >= size {
+      match decode(self.protocol, &self.partial[start..start + size]) {
+        Ok(packet) => {
+          self.packet(&packet, events);
+          start += size;
+        }
+        Err(OutOfSync(_)) => {
+          self.skipped += 1;
+          start += 1;
+        }
+      }
+    }
+    self.partial.drain(..start);
+  }
+
+  fn packet(&mut self, packet: &Packet, events: &mut Vec<Event>) {
+    let buttons = [packet.left, packet.right, packet.middle];
+    let names = [Button::Left, Button::Right, Button::Middle];
+    for ((was, &is), &button) in self.buttons.iter_mut().zip(&buttons).zip(&names) {
+      if *was != is {
+        events.push(Event::Button {
+          button,
+          pressed: is,
+        });
+        *was = is;
+      }
+    }
+    if packet.dx != 0 || packet.dy != 0 {
+      events.push(Event::Motion {
+        dx: packet.dx,
+        dy: -packet.dy,
+      });
+    }
+    if packet.wheel != 0 {
+      events.push(Event::Wheel {
+        vertical: packet.wheel,
+        horizontal: 0,
+      });
+    }
+  }
+}
+
+/// Asks the mouse behind `port` to report its wheel, by sending the
+/// [`INTELLIMOUSE_SEQUENCE`] and reading its ID back: 3 for an IntelliMouse. Returns the
+/// protocol it speaks from now on.
+///
+/// The kernel's `mousedev` nodes answer right away, which this expects: a `port` that has
+/// nothing to read, or ends, means no wheel. Motion packets waiting to be read are lost.
+pub fn enable_wheel<P: Read + Write>(port: &mut P) -> io::Result<Protocol> {
+  port.write_all(&INTELLIMOUSE_SEQUENCE)?;
+  port.write_all(&[GET_DEVICE_ID])?;
+
+  // An acknowledgement for each byte, then the ID
+  let mut acks = 0;
+  let mut buffer = [0u8; 32];
+  loop {
+    let count = match port.read(&mut buffer) {
+      Ok(0) => return Ok(Protocol::Standard),
+      Ok(count) => count,
+      Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(Protocol::Standard),
+      Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+      Err(err) => return Err(err),
+    };
+    for &byte in &buffer[..count] {
+      if acks <= INTELLIMOUSE_SEQUENCE.len() {
+        if byte == ACK {
+          acks += 1;
+        }
+        continue;
+      }
+      return Ok(match byte {
+        3 => Protocol::IntelliMouse,
+        _ => Protocol::Standard,
+      });
+    }
+  }
+}
+
+/// A legacy mouse node, such as `/dev/input/mouse0` or `/dev/input/mice`, read without blocking.
+pub struct Device {
+  file: File,
+  path: PathBuf,
+  decoder: Decoder,
+}
+
+impl Device {
+  /// Opens `path`, with the wheel if the mouse has one. Switching to the wheel protocol takes
+  /// writing to the node, without which the mouse is read as a standard one.
+  pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Device> {
+    let path = path.as_ref();
+    let open = |write| {
+      OpenOptions::new()
+        .read(true)
+        .write(write)
+        .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+        .open(path)
+    };
+    let (file, protocol) = match open(true) {
+      Ok(mut file) => {
+        let protocol = enable_wheel(&mut file)?;
+        (file, protocol)
+      }
+      Err(_) => (open(false)?, Protocol::Standard),
+    };
+
+    Ok(Device {
+      file,
+      path: path.to_owned(),
+      decoder: Decoder::new(protocol),
+    })
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  pub fn protocol(&self) -> Protocol {
+    self.decoder.protocol()
+  }
+
+  /// The events waiting to be read.
+  pub fn poll(&mut self) -> io::Result<Vec<Event>> {
+    let mut events = Vec::new();
+    let mut buffer = [0u8; 256];
+    loop {
+      match self.file.read(&mut buffer) {
+        Ok(0) => break,
+        Ok(count) => self.decoder.feed(&buffer[..count], &mut events),
+        Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+        Err(err) => return Err(err),
+      }
+    }
+    Ok(events)
+  }
+}
@@ -0,0 +1,182 @@
+//! Keys typed on a terminal, such as the console a demo runs on or an SSH session, for when no
+//! keyboard can be read through evdev.
+//!
+//! A terminal in raw mode sends the characters typed, and escape sequences for the keys that
+//! have none, such as `ESC [ A` for Up. There are no releases, and no way to tell the keypad
+//! from the main keys: each character is reported as a press of the key a US keyboard types it
+//! with, except `+` and `*`, which take Shift there and are reported as the keypad's.
+
+use super::{Event, Key, KeyState};
+use crate::shutdown;
+use std::io;
+use std::mem::MaybeUninit;
+
+const ESC: u8 = 0x1b;
+
+/// The key for a character, on its own rather than in an escape sequence.
+fn key(byte: u8) -> Option<Key> {
+  let key = match byte {
+    ESC => Key::ESC,
+    b'\r' | b'\n' => Key::ENTER,
+    b'\t' => Key::TAB,
+    // Backspace, or Ctrl-H
+    0x7f | 0x08 => Key::BACKSPACE,
+    b' ' => Key::SPACE,
+    b'-' => Key::MINUS,
+    b'=' => Key::EQUAL,
+    b'+' => Key::KP_PLUS,
+    b'*' => Key::KP_ASTERISK,
+    b'/' => Key::SLASH,
+    b'.' => Key::DOT,
+    b',' => Key::COMMA,
+    _ => return Key::from_char(byte as char),
+  };
+  Some(key)
+}
+
+/// The key for the escape sequence `ESC [ parameter final` or `ESC O final`.
+fn sequence(parameter: &[u8], last: u8) -> Option<Key> {
+  let key = match (parameter, last) {
+    (_, b'A') => Key::UP,
+    (_, b'B') => Key::DOWN,
+    (_, b'C') => Key::RIGHT,
+    (_, b'D') => Key::LEFT,
+    (_, b'H') | (b"1", b'~') | (b"7", b'~') => Key::HOME,
+    (_, b'F') | (b"4", b'~') | (b"8", b'~') => Key::END,
+    // The keypad's Enter, in application mode
+    (_, b'M') => Key::KP_ENTER,
+    (b"2", b'~') => Key::INSERT,
+    (b"3", b'~') => Key::DELETE,
+    (b"5", b'~') => Key::PAGE_UP,
+    (b"6", b'~') => Key::PAGE_DOWN,
+    _ => return None,
+  };
+  Some(key)
+}
+
+/// Turns what a terminal sends into key presses.
+#[derive(Clone, Debug, Default)]
+pub struct Decoder {
+  /// The start of an escape sequence the last read stopped in.
+  partial: Vec<u8>,
+}
+
+impl Decoder {
+  pub fn new() -> Decoder {
+    Decoder::default()
+  }
+
+  /// Decodes `bytes`, adding a [`KeyState::Pressed`] event for each key to `events`. Escape
+  /// sequences may be split across calls, but `ESC` at the end of `bytes` is taken for the Esc
+  /// key, as terminals send a sequence in a single write. Unknown sequences are skipped.
+  pub fn feed(&mut self, bytes: &[u8], events: &mut Vec<Event>) {
+    self.partial.extend_from_slice(bytes);
+    let input = std::mem::take(&mut self.partial);
+    let press = |key| Event::Key {
+      key,
+      state: KeyState::Pressed,
+    };
+
+    let mut at = 0;
+    while at < input.len() {
+      let rest = &input[at..];
+      if rest[0] != ESC {
+        events.extend(key(rest[0]).map(press));
+        at += 1;
+        continue;
+      }
+      match rest.get(1) {
+        None => {
+          events.push(press(Key::ESC));
+          at += 1;
+        }
+        Some(b'[') | Some(b'O') => {
+          // Parameters, then a final byte from `@` to `~`
+          match rest[2..]
+            .iter()
+            .position(|byte| (0x40..=0x7e).contains(byte))
+          {
+            Some(end) => {
+              events.extend(sequence(&rest[2..2 + end], rest[2 + end]).map(press));
+              at += 3 + end;
+            }
+            None => {
+              self.partial = rest.to_vec();
+              return;
+            }
+          }
+        }
+        // Alt and a key, reported as the key
+        Some(_) => at += 1,
+      }
+    }
+  }
+}
+
+/// The terminal on stdin, switched to raw mode until dropped.
+///
+/// Characters are read as they are typed, without echoing them. `Ctrl-C` still sends `SIGINT`,
+/// and a second signal that kills the process through [`shutdown`] puts the terminal back first.
+/// Reads never block, which makes anything else reading stdin see its end: make the `Terminal`
+/// before [`Capture::on_request`](crate::capture::Capture::on_request) starts its thread
+/// waiting for Enter, and bind Enter to [`capture::request`](crate::capture::request) instead.
+pub struct Terminal {
+  saved: libc::termios,
+  decoder: Decoder,
+}
+
+impl Terminal {
+  /// Switches stdin to raw mode. Fails when stdin is not a terminal.
+  pub fn stdin() -> io::Result<Terminal> {
+    let mut saved = MaybeUninit::<libc::termios>::uninit();
+    if unsafe { libc::tcgetattr(libc::STDIN_FILENO, saved.as_mut_ptr()) } < 0 {
+      return Err(io::Error::last_os_error());
+    }
+    let saved = unsafe { saved.assume_init() };
+
+    let mut raw = saved;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_iflag &= !(libc::ICRNL | libc::IXON);
+    raw.c_cc[libc::VMIN] = 0;
+    raw.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } < 0 {
+      return Err(io::Error::last_os_error());
+    }
+    shutdown::restore_terminal(&saved);
+
+    Ok(Terminal {
+      saved,
+      decoder: Decoder::new(),
+    })
+  }
+
+  /// The keys typed since the last call.
+  pub fn poll(&mut self) -> io::Result<Vec<Event>> {
+    let mut events = Vec::new();
+    let mut buffer = [0u8; 64];
+    loop {
+      let count =
+        unsafe { libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), buffer.len()) };
+      match count {
+        0 => break,
+        count if count > 0 => self.decoder.feed(&buffer[..count as usize], &mut events),
+        _ => {
+          let err = io::Error::last_os_error();
+          match err.kind() {
+            io::ErrorKind::Interrupted => {}
+            io::ErrorKind::WouldBlock => break,
+            _ => return Err(err),
+          }
+        }
+      }
+    }
+    Ok(events)
+  }
+}
+
+impl Drop for Terminal {
+  fn drop(&mut self) {
+    unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.saved) };
+    shutdown::forget_terminal();
+  }
+}
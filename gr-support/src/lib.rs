@@ -0,0 +1,56 @@
+//! Helpers shared by the `gr-context` examples.
+//!
+//! These started life copy-pasted at the top of every example's `main.rs`. They live here so
+//! that the examples, and applications built from them, use a single copy.
+
+pub mod args;
+pub mod bindings;
+pub mod capture;
+pub mod clock;
+pub mod ffi;
+pub mod gl;
+#[cfg(feature = "golden")]
+pub mod golden;
+pub mod gpu;
+pub mod image;
+pub mod input;
+pub mod preprocess;
+pub mod record;
+#[cfg(feature = "reload")]
+pub mod reload;
+pub mod shutdown;
+pub mod soft;
+pub mod timing;
+#[cfg(feature = "validate")]
+pub mod validate;
+
+mod context;
+mod error;
+mod headless;
+mod resource;
+mod shader;
+mod target;
+
+pub use bindings::ShaderBindings;
+pub use context::GlContext;
+pub use error::{gl_check, Error, GlError, Result};
+pub use headless::{EglError, Headless};
+pub use resource::{Buffer, Framebuffer, Texture};
+pub use shader::{
+  print_program_info_log, print_shader_info_log, Program, Shader, ShaderError, ShaderProgram,
+  ShaderProgramBuilder, ShaderStage, SourceLine, StageSource, Variable,
+};
+pub use target::RenderTarget;
+
+pub use gr_support_derive::ShaderBindings;
+
+/// The environment variables every example reads, for the end of its `--help`.
+pub const ENV_HELP: &str = "\
+Set GR_HEADLESS=WIDTHxHEIGHT to draw offscreen, without a display.
+
+Press Enter or send SIGUSR1 to save the next frame as a screenshot. Set GR_CAPTURE=PATH to save
+elsewhere ({frame} is the frame number, .ppm for a PPM), and GR_CAPTURE_EVERY=N to also save
+every Nth frame.
+
+Set GR_RECORD=PATH.y4m to record every frame as a video, or GR_RECORD='|COMMAND' to pipe raw
+RGBA frames to COMMAND, and GR_RECORD_FPS for a rate other than 60 frames a second.";
@@ -0,0 +1,321 @@
+//! A small shader preprocessor: `#include "file"`, `#define`s set at runtime and a map from
+//! output lines back to the files they came from.
+//!
+//! Everything else, including the injected `#define`s, is left for the driver's own
+//! preprocessor.
+//!
+//! ```ignore
+//! let mandelbrot = Preprocessor::new()
+//!   .snippet("hsl2rgb.glsl", include_str!("../shaders/hsl2rgb.glsl"))
+//!   .define("MAX_ITERATIONS", 512)
+//!   .process_file("shaders/mandelbrot.glsl")?;
+//!
+//! let program = ShaderProgram::builder()
+//!   .vertex(VSHADER_SOURCE)
+//!   .fragment(&mandelbrot)
+//!   .build(&context)?;
+//! ```
+
+use crate::{Error, Result, ShaderError};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PreprocessError {
+  /// `#include` of a file that is not next to the including file, in an include directory or
+  /// a registered snippet.
+  IncludeNotFound {
+    file: String,
+    line: usize,
+    name: String,
+  },
+  /// A file that ends up including itself.
+  RecursiveInclude {
+    file: String,
+    line: usize,
+    name: String,
+  },
+  /// `#include` without a `"quoted"` name.
+  BadInclude {
+    file: String,
+    line: usize,
+    text: String,
+  },
+}
+
+impl fmt::Display for PreprocessError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      PreprocessError::IncludeNotFound { file, line, name } => {
+        write!(f, "{}:{}: cannot find included file {}", file, line, name)
+      }
+      PreprocessError::RecursiveInclude { file, line, name } => {
+        write!(f, "{}:{}: {} includes itself", file, line, name)
+      }
+      PreprocessError::BadInclude { file, line, text } => write!(
+        f,
+        "{}:{}: expected #include \"file\", found {}",
+        file,
+        line,
+        text.trim()
+      ),
+    }
+  }
+}
+
+impl std::error::Error for PreprocessError {}
+
+/// Where a line of preprocessed source came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Origin {
+  /// Path or snippet name, `<define>` for injected `#define`s.
+  pub file: String,
+  /// Numbered from 1.
+  pub line: usize,
+}
+
+/// Preprocessed shader source, ready for [`ShaderProgramBuilder`](crate::ShaderProgramBuilder).
+#[derive(Clone, Debug)]
+pub struct Source {
+  text: String,
+  origins: Vec<Origin>,
+  files: Vec<PathBuf>,
+}
+
+impl Source {
+  pub fn text(&self) -> &str {
+    &self.text
+  }
+
+  /// Where line `line` (numbered from 1, as drivers do) of [`text`](Source::text) came from.
+  pub fn origin(&self, line: usize) -> Option<&Origin> {
+    line
+      .checked_sub(1)
+      .and_then(|index| self.origins.get(index))
+  }
+
+  /// Files read from disk, the top level one first. Snippets are not files.
+  pub fn files(&self) -> &[PathBuf] {
+    &self.files
+  }
+
+  /// Points the lines of a compile error at the files they came from.
+  pub fn locate(&self, err: Error) -> Error {
+    match err {
+      Error::Shader(ShaderError::Compile { stage, log, lines }) => {
+        let lines = lines
+          .into_iter()
+          .map(|mut line| {
+            if let Some(origin) = self.origin(line.number) {
+              line.number = origin.line;
+              line.file = Some(origin.file.clone());
+            }
+            line
+          })
+          .collect();
+        Error::Shader(ShaderError::Compile { stage, log, lines })
+      }
+      err => err,
+    }
+  }
+}
+
+/// Expands `#include`s and adds `#define`s, see the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct Preprocessor {
+  defines: Vec<(String, String)>,
+  snippets: HashMap<String, String>,
+  include_dirs: Vec<PathBuf>,
+}
+
+impl Preprocessor {
+  pub fn new() -> Preprocessor {
+    Preprocessor::default()
+  }
+
+  /// Adds `#define name value` after `#version`, or at the top when there is none. `name` may
+  /// take arguments, e.g. `COLOR(i)`.
+  pub fn define<V: ToString>(mut self, name: &str, value: V) -> Self {
+    self.defines.push((name.to_string(), value.to_string()));
+    self
+  }
+
+  /// Makes `#include "name"` resolve to `source` when no file of that name is found.
+  pub fn snippet(mut self, name: &str, source: &str) -> Self {
+    self.snippets.insert(name.to_string(), source.to_string());
+    self
+  }
+
+  /// Looks for included files in `dir` after the including file's own directory.
+  pub fn include_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+    self.include_dirs.push(dir.into());
+    self
+  }
+
+  /// Processes `source`, calling it `name` in [`Origin`]s and errors.
+  pub fn process(&self, name: &str, source: &str) -> Result<Source> {
+    let mut output = Source {
+      text: String::new(),
+      origins: Vec::new(),
+      files: Vec::new(),
+    };
+    self.expand(name, source, None, true, &mut Vec::new(), &mut output)?;
+    Ok(output)
+  }
+
+  /// Reads and processes `path`. Includes are looked for next to it first.
+  pub fn process_file<P: AsRef<Path>>(&self, path: P) -> Result<Source> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let mut output = Source {
+      text: String::new(),
+      origins: Vec::new(),
+      files: vec![path.to_path_buf()],
+    };
+    let name = path.display().to_string();
+    self.expand(
+      &name,
+      &source,
+      path.parent(),
+      true,
+      &mut Vec::new(),
+      &mut output,
+    )?;
+    Ok(output)
+  }
+
+  fn expand(
+    &self,
+    name: &str,
+    source: &str,
+    dir: Option<&Path>,
+    top_level: bool,
+    stack: &mut Vec<String>,
+    output: &mut Source,
+  ) -> Result<()> {
+    stack.push(name.to_string());
+
+    let has_version = top_level && source.lines().any(is_version);
+    let mut defined = !top_level || self.defines.is_empty();
+    if !has_version && !defined {
+      self.push_defines(output);
+      defined = true;
+    }
+
+    for (index, text) in source.lines().enumerate() {
+      let line = index + 1;
+      let directive = text.trim_start();
+
+      if let Some(argument) = directive.strip_prefix("#include") {
+        let include = parse_include(argument).ok_or_else(|| {
+          Error::Preprocess(PreprocessError::BadInclude {
+            file: name.to_string(),
+            line,
+            text: text.to_string(),
+          })
+        })?;
+        self.include(name, line, include, dir, stack, output)?;
+        continue;
+      }
+
+      push_line(output, text, name, line);
+
+      if !defined && is_version(text) {
+        self.push_defines(output);
+        defined = true;
+      }
+    }
+
+    stack.pop();
+    Ok(())
+  }
+
+  fn include(
+    &self,
+    from: &str,
+    line: usize,
+    name: &str,
+    dir: Option<&Path>,
+    stack: &mut Vec<String>,
+    output: &mut Source,
+  ) -> Result<()> {
+    let path = dir
+      .into_iter()
+      .chain(self.include_dirs.iter().map(PathBuf::as_path))
+      .map(|dir| dir.join(name))
+      .find(|path| path.is_file());
+
+    let (key, source, dir) = match &path {
+      Some(path) => (path.display().to_string(), read(path)?, path.parent()),
+      None => match self.snippets.get(name) {
+        Some(source) => (name.to_string(), source.clone(), None),
+        None => {
+          return Err(Error::Preprocess(PreprocessError::IncludeNotFound {
+            file: from.to_string(),
+            line,
+            name: name.to_string(),
+          }))
+        }
+      },
+    };
+
+    if stack.contains(&key) {
+      return Err(Error::Preprocess(PreprocessError::RecursiveInclude {
+        file: from.to_string(),
+        line,
+        name: name.to_string(),
+      }));
+    }
+
+    if let Some(path) = &path {
+      if !output.files.contains(path) {
+        output.files.push(path.clone());
+      }
+    }
+
+    self.expand(&key, &source, dir, false, stack, output)
+  }
+
+  fn push_defines(&self, output: &mut Source) {
+    for (index, (name, value)) in self.defines.iter().enumerate() {
+      let text = format!("#define {} {}", name, value);
+      push_line(output, &text, "<define>", index + 1);
+    }
+  }
+}
+
+fn push_line(output: &mut Source, text: &str, file: &str, line: usize) {
+  output.text.push_str(text);
+  output.text.push('\n');
+  output.origins.push(Origin {
+    file: file.to_string(),
+    line,
+  });
+}
+
+fn is_version(line: &str) -> bool {
+  line.trim_start().starts_with("#version")
+}
+
+/// The name in `"name"`, ignoring surrounding blanks and a trailing `//` comment.
+fn parse_include(argument: &str) -> Option<&str> {
+  let argument = argument.split("//").next()?.trim();
+  let name = argument.strip_prefix('"')?.strip_suffix('"')?;
+  if name.is_empty() || name.contains('"') {
+    None
+  } else {
+    Some(name)
+  }
+}
+
+fn read(path: &Path) -> Result<String> {
+  fs::read_to_string(path).map_err(|err| {
+    Error::Io(io::Error::new(
+      err.kind(),
+      format!("{}: {}", path.display(), err),
+    ))
+  })
+}
@@ -0,0 +1,206 @@
+//! Records every frame a demo draws, as a Y4M video or raw frames piped to another program.
+//!
+//! ```ignore
+//! let mut recorder = Recorder::from_env(&context)?;
+//! loop {
+//!   draw(&context)?;
+//!   if let Some(recorder) = &mut recorder {
+//!     recorder.frame(&context)?;
+//!   }
+//!   context.swap_buffers();
+//! }
+//! ```
+//!
+//! Reading frames back is slow, so while recording, time in a demo should advance by
+//! [`timestep`](Recorder::timestep) each frame rather than follow the clock. The video then
+//! plays at the speed the demo would run at, however long each frame took to record. A
+//! [`FrameClock`](crate::clock::FrameClock) on a [`VirtualClock`](crate::clock::VirtualClock)
+//! does that.
+
+use crate::image::Image;
+use crate::{Error, GlContext, Result};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::time::Duration;
+
+/// What goes out for each frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+  /// A YUV4MPEG2 stream, 4:2:0 with full range BT.601 colors, as most players and `ffmpeg` read.
+  Y4m,
+  /// The RGBA pixels of each frame, top row first, e.g. for
+  /// `ffmpeg -f rawvideo -pix_fmt rgba -s WxH -r FPS -i -`.
+  Raw,
+}
+
+/// Writes frames at a fixed rate.
+pub struct Recorder {
+  output: BufWriter<Box<dyn Write>>,
+  child: Option<Child>,
+  format: Format,
+  width: u32,
+  height: u32,
+  fps: u32,
+  frames: u64,
+}
+
+impl Recorder {
+  /// Records `width` x `height` frames to `output`, to be played at `fps` frames a second.
+  pub fn new<W: Write + 'static>(
+    output: W,
+    format: Format,
+    width: u32,
+    height: u32,
+    fps: u32,
+  ) -> Result<Recorder> {
+    let mut recorder = Recorder {
+      output: BufWriter::new(Box::new(output)),
+      child: None,
+      format,
+      width,
+      height,
+      fps,
+      frames: 0,
+    };
+    if format == Format::Y4m {
+      writeln!(
+        recorder.output,
+        "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C420jpeg XCOLORRANGE=FULL",
+        width, height, fps
+      )?;
+    }
+    Ok(recorder)
+  }
+
+  /// Records a Y4M video to `path`.
+  pub fn create<P: AsRef<Path>>(path: P, width: u32, height: u32, fps: u32) -> Result<Recorder> {
+    Recorder::new(File::create(path)?, Format::Y4m, width, height, fps)
+  }
+
+  /// Pipes raw frames to `command`, run by `sh`, with `{width}`, `{height}` and `{fps}` replaced.
+  pub fn pipe(command: &str, width: u32, height: u32, fps: u32) -> Result<Recorder> {
+    let command = command
+      .replace("{width}", &width.to_string())
+      .replace("{height}", &height.to_string())
+      .replace("{fps}", &fps.to_string());
+    let mut child = Command::new("sh")
+      .arg("-c")
+      .arg(&command)
+      .stdin(Stdio::piped())
+      .spawn()?;
+    let stdin: ChildStdin = child.stdin.take().expect("stdin is piped");
+
+    let mut recorder = Recorder::new(stdin, Format::Raw, width, height, fps)?;
+    recorder.child = Some(child);
+    Ok(recorder)
+  }
+
+  /// Records `context`'s frames when `GR_RECORD` is set: to a Y4M file at that path, or when it
+  /// starts with `|`, piped to the rest as a command (see [`pipe`](Recorder::pipe)). Frames are
+  /// played at `GR_RECORD_FPS`, 60 by default.
+  pub fn from_env(context: &GlContext) -> Result<Option<Recorder>> {
+    let output = match env::var("GR_RECORD") {
+      Ok(output) => output,
+      Err(_) => return Ok(None),
+    };
+    let fps = match env::var("GR_RECORD_FPS") {
+      Ok(fps) => match fps.parse() {
+        Ok(fps) if fps > 0 => fps,
+        _ => return Err(Error::RecordFps(fps)),
+      },
+      Err(_) => 60,
+    };
+
+    let (width, height) = (context.width(), context.height());
+    let recorder = match output.strip_prefix('|') {
+      Some(command) => Recorder::pipe(command, width, height, fps)?,
+      None => Recorder::create(&output, width, height, fps)?,
+    };
+    Ok(Some(recorder))
+  }
+
+  pub fn fps(&self) -> u32 {
+    self.fps
+  }
+
+  /// How far time moves between two frames of the video.
+  pub fn timestep(&self) -> Duration {
+    Duration::from_secs(1) / self.fps
+  }
+
+  /// How long the frames recorded so far play for.
+  pub fn time(&self) -> Duration {
+    Duration::from_secs(self.frames) / self.fps
+  }
+
+  pub fn frames(&self) -> u64 {
+    self.frames
+  }
+
+  /// Reads the frame back and records it. Call it once the frame is drawn, before swapping
+  /// buffers, which leaves the back buffer undefined.
+  pub fn frame(&mut self, context: &GlContext) -> Result<()> {
+    let image = Image::read(context)?;
+    self.write(&image)
+  }
+
+  /// Records `image`, which has to be the size the recorder was created with.
+  pub fn write(&mut self, image: &Image) -> Result<()> {
+    if (image.width(), image.height()) != (self.width, self.height) {
+      return Err(
+        io::Error::new(
+          io::ErrorKind::InvalidInput,
+          format!(
+            "recording {}x{} frames, not {}x{}",
+            self.width,
+            self.height,
+            image.width(),
+            image.height()
+          ),
+        )
+        .into(),
+      );
+    }
+
+    match self.format {
+      Format::Y4m => {
+        self.output.write_all(b"FRAME\n")?;
+        self.output.write_all(&image.to_yuv420())?;
+      }
+      Format::Raw => self.output.write_all(image.pixels())?,
+    }
+    self.frames += 1;
+    Ok(())
+  }
+
+  /// Flushes the output and, when piping, waits for the command to finish.
+  pub fn finish(mut self) -> Result<()> {
+    self.close()
+  }
+
+  fn close(&mut self) -> Result<()> {
+    self.output.flush()?;
+    if let Some(mut child) = self.child.take() {
+      // Closes the pipe, so the command sees the end of its input
+      self.output = BufWriter::new(Box::new(io::sink()));
+      let status = child.wait()?;
+      if !status.success() {
+        return Err(
+          io::Error::other(format!("the recording command exited with {}", status)).into(),
+        );
+      }
+    }
+    Ok(())
+  }
+}
+
+impl Drop for Recorder {
+  fn drop(&mut self) {
+    if let Err(err) = self.close() {
+      eprintln!("recording: {}", err);
+    }
+  }
+}
@@ -0,0 +1,259 @@
+//! Shader programs that rebuild themselves when their source files change.
+//!
+//! ```ignore
+//! let mut julia: ReloadingProgram<JuliaLocations> = ReloadingProgram::new(
+//!   &context,
+//!   ShaderSource::File("shaders/vshader.glsl".into()),
+//!   ShaderSource::File("shaders/julia.glsl".into()),
+//!   Preprocessor::new(),
+//!   false,
+//! )?;
+//!
+//! loop {
+//!   if let Reload::Failed(err) = julia.poll() {
+//!     eprintln!("{}", err);
+//!   }
+//!   julia.program().use_program()?;
+//!   julia.bindings().scale.set(0.003, 0.003);
+//!   ...
+//! }
+//! ```
+
+use crate::preprocess::{Preprocessor, Source};
+use crate::{Error, GlContext, Result, ShaderBindings, ShaderProgram, ShaderStage};
+use inotify::{Inotify, WatchDescriptor, WatchMask};
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Where the source of one shader stage comes from.
+#[derive(Clone, Debug)]
+pub enum ShaderSource {
+  /// Compiled into the binary, never reloaded.
+  Static(&'static str),
+  /// Read from disk, and read again whenever it or a file it includes is written or replaced.
+  File(PathBuf),
+}
+
+impl ShaderSource {
+  /// Reads the source if needed and runs it through `preprocessor`.
+  pub fn load(&self, preprocessor: &Preprocessor, stage: ShaderStage) -> Result<Source> {
+    match self {
+      ShaderSource::Static(source) => preprocessor.process(&stage.to_string(), source),
+      ShaderSource::File(path) => preprocessor.process_file(path),
+    }
+  }
+}
+
+/// Watches a set of files with inotify.
+///
+/// Editors often save by writing a new file and renaming it over the old one, which a watch on
+/// the file itself would miss, so the watches are on the parent directories.
+pub struct SourceWatcher {
+  inotify: Inotify,
+  files: Vec<(WatchDescriptor, OsString)>,
+  buffer: Vec<u8>,
+}
+
+impl SourceWatcher {
+  pub fn new<P: AsRef<Path>>(paths: &[P]) -> io::Result<SourceWatcher> {
+    let mut watcher = SourceWatcher {
+      inotify: Inotify::init()?,
+      files: Vec::new(),
+      buffer: vec![0; 4096],
+    };
+
+    for path in paths {
+      watcher.watch(path)?;
+    }
+
+    Ok(watcher)
+  }
+
+  /// Adds a file to the watched set. Watching the same file twice is harmless.
+  pub fn watch<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    let name = path.file_name().ok_or_else(|| {
+      io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{} is not a file", path.display()),
+      )
+    })?;
+    let directory = match path.parent() {
+      Some(parent) if parent != Path::new("") => parent,
+      _ => Path::new("."),
+    };
+
+    // Watching a directory twice returns the same descriptor
+    let watch = self
+      .inotify
+      .add_watch(directory, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)?;
+    if !self
+      .files
+      .iter()
+      .any(|(wd, file)| *wd == watch && file.as_os_str() == name)
+    {
+      self.files.push((watch, name.to_os_string()));
+    }
+
+    Ok(())
+  }
+
+  /// Whether any of the files was written or replaced since the last call. Never blocks.
+  pub fn changed(&mut self) -> io::Result<bool> {
+    let mut changed = false;
+
+    loop {
+      let events = self.inotify.read_events(&mut self.buffer)?;
+      let mut any = false;
+      for event in events {
+        any = true;
+        changed |= self
+          .files
+          .iter()
+          .any(|(watch, name)| *watch == event.wd && event.name == Some(name.as_os_str()));
+      }
+      if !any {
+        return Ok(changed);
+      }
+    }
+  }
+}
+
+/// What [`ReloadingProgram::poll`] did.
+#[derive(Debug)]
+pub enum Reload {
+  /// No source changed.
+  Unchanged,
+  /// The program was rebuilt and rebound. Locations may have moved, so anything set up from the
+  /// old bindings, such as vertex attribute pointers, has to be set up again.
+  Reloaded,
+  /// A source changed but did not build or bind. The previous program is still in use.
+  Failed(Error),
+}
+
+/// A [`ShaderProgram`] and its [`ShaderBindings`], rebuilt when a [`ShaderSource::File`] or a
+/// file it includes changes.
+pub struct ReloadingProgram<'ctx, B> {
+  context: &'ctx GlContext,
+  vertex: ShaderSource,
+  fragment: ShaderSource,
+  preprocessor: Preprocessor,
+  verbose: bool,
+  watcher: Option<SourceWatcher>,
+  program: ShaderProgram<'ctx>,
+  bindings: B,
+}
+
+impl<'ctx, B: ShaderBindings> ReloadingProgram<'ctx, B> {
+  /// Builds and binds the program, running both sources through `preprocessor`. Unlike a
+  /// reload, a failure here is returned, there is no previous program to fall back to.
+  pub fn new(
+    context: &'ctx GlContext,
+    vertex: ShaderSource,
+    fragment: ShaderSource,
+    preprocessor: Preprocessor,
+    verbose: bool,
+  ) -> Result<ReloadingProgram<'ctx, B>> {
+    let (program, bindings, files) = build(context, &vertex, &fragment, &preprocessor, verbose)?;
+
+    let mut reloading = ReloadingProgram {
+      context,
+      vertex,
+      fragment,
+      preprocessor,
+      verbose,
+      watcher: None,
+      program,
+      bindings,
+    };
+    reloading.watch(&files)?;
+
+    Ok(reloading)
+  }
+
+  pub fn program(&self) -> &ShaderProgram<'ctx> {
+    &self.program
+  }
+
+  pub fn bindings(&self) -> &B {
+    &self.bindings
+  }
+
+  /// Rebuilds the program if a source file changed. Call it once per frame.
+  pub fn poll(&mut self) -> Reload {
+    let changed = match &mut self.watcher {
+      Some(watcher) => watcher.changed(),
+      None => Ok(false),
+    };
+
+    match changed {
+      Ok(false) => Reload::Unchanged,
+      Ok(true) => {
+        let built = build(
+          self.context,
+          &self.vertex,
+          &self.fragment,
+          &self.preprocessor,
+          self.verbose,
+        );
+        match built {
+          Ok((program, bindings, files)) => {
+            self.program = program;
+            self.bindings = bindings;
+            // An edit may have added includes
+            match self.watch(&files) {
+              Ok(()) => Reload::Reloaded,
+              Err(err) => Reload::Failed(err),
+            }
+          }
+          Err(err) => Reload::Failed(err),
+        }
+      }
+      Err(err) => Reload::Failed(Error::Io(err)),
+    }
+  }
+
+  fn watch(&mut self, files: &[PathBuf]) -> Result<()> {
+    if files.is_empty() {
+      return Ok(());
+    }
+    match &mut self.watcher {
+      Some(watcher) => {
+        for file in files {
+          watcher.watch(file)?;
+        }
+      }
+      None => self.watcher = Some(SourceWatcher::new(files)?),
+    }
+    Ok(())
+  }
+}
+
+/// Returns the program, its bindings and every file read to build it.
+fn build<'ctx, B: ShaderBindings>(
+  context: &'ctx GlContext,
+  vertex: &ShaderSource,
+  fragment: &ShaderSource,
+  preprocessor: &Preprocessor,
+  verbose: bool,
+) -> Result<(ShaderProgram<'ctx>, B, Vec<PathBuf>)> {
+  let vertex = vertex.load(preprocessor, ShaderStage::Vertex)?;
+  let fragment = fragment.load(preprocessor, ShaderStage::Fragment)?;
+
+  let program = ShaderProgram::builder()
+    .vertex(&vertex)
+    .fragment(&fragment)
+    .verbose(verbose)
+    .build(context)?;
+  let bindings = program.bind()?;
+
+  let files = vertex
+    .files()
+    .iter()
+    .chain(fragment.files())
+    .cloned()
+    .collect();
+
+  Ok((program, bindings, files))
+}
@@ -0,0 +1,164 @@
+//! Buffers, textures and framebuffers that are deleted when dropped.
+
+use crate::{gl, gl_check, GlContext, Result};
+use std::marker::PhantomData;
+
+/// A buffer object, deleted on drop.
+#[derive(Debug)]
+pub struct Buffer<'ctx> {
+  id: gl::GLuint,
+  context: PhantomData<&'ctx GlContext>,
+}
+
+impl<'ctx> Buffer<'ctx> {
+  pub fn new(_context: &'ctx GlContext) -> Result<Buffer<'ctx>> {
+    let id = gl::gen_buffers(1)[0];
+    gl_check()?;
+    Ok(Buffer {
+      id,
+      context: PhantomData,
+    })
+  }
+
+  pub fn id(&self) -> gl::GLuint {
+    self.id
+  }
+
+  /// Binds to `target`, usually `GL_ARRAY_BUFFER`.
+  pub fn bind(&self, target: gl::GLenum) -> Result<()> {
+    gl::bind_buffer(target, self.id);
+    gl_check()
+  }
+
+  /// Binds to `target` and replaces the contents with `data`.
+  pub fn upload<T>(&self, target: gl::GLenum, data: &[T], usage: gl::GLenum) -> Result<()> {
+    self.bind(target)?;
+    gl::buffer_data(target, data, usage);
+    gl_check()
+  }
+}
+
+impl Drop for Buffer<'_> {
+  fn drop(&mut self) {
+    gl::delete_buffers(&[self.id]);
+  }
+}
+
+/// A `GL_TEXTURE_2D` texture, deleted on drop.
+#[derive(Debug)]
+pub struct Texture<'ctx> {
+  id: gl::GLuint,
+  context: PhantomData<&'ctx GlContext>,
+}
+
+impl<'ctx> Texture<'ctx> {
+  pub fn new(_context: &'ctx GlContext) -> Result<Texture<'ctx>> {
+    let id = gl::gen_textures(1)[0];
+    gl_check()?;
+    Ok(Texture {
+      id,
+      context: PhantomData,
+    })
+  }
+
+  pub fn id(&self) -> gl::GLuint {
+    self.id
+  }
+
+  pub fn bind(&self) -> Result<()> {
+    gl::bind_texture(gl::GL_TEXTURE_2D, self.id);
+    gl_check()
+  }
+
+  /// Binds the texture and (re)allocates level 0. An empty `data` leaves the pixels undefined,
+  /// which is what render targets want.
+  pub fn image_2d<T>(
+    &self,
+    internal_format: gl::GLenum,
+    width: gl::GLsizei,
+    height: gl::GLsizei,
+    format: gl::GLenum,
+    type_: gl::GLenum,
+    data: &[T],
+  ) -> Result<()> {
+    self.bind()?;
+    gl::tex_image_2d(
+      gl::GL_TEXTURE_2D,
+      0,
+      internal_format as gl::GLint,
+      width,
+      height,
+      0,
+      format,
+      type_,
+      data,
+    );
+    gl_check()
+  }
+
+  /// Binds the texture and sets a parameter such as `GL_TEXTURE_MIN_FILTER`.
+  pub fn parameter(&self, pname: gl::GLenum, value: gl::GLenum) -> Result<()> {
+    self.bind()?;
+    gl::tex_parameterf(gl::GL_TEXTURE_2D, pname, value as gl::GLfloat);
+    gl_check()
+  }
+}
+
+impl Drop for Texture<'_> {
+  fn drop(&mut self) {
+    gl::delete_textures(&[self.id]);
+  }
+}
+
+/// A framebuffer object, deleted on drop.
+#[derive(Debug)]
+pub struct Framebuffer<'ctx> {
+  id: gl::GLuint,
+  context: PhantomData<&'ctx GlContext>,
+}
+
+impl<'ctx> Framebuffer<'ctx> {
+  pub fn new(_context: &'ctx GlContext) -> Result<Framebuffer<'ctx>> {
+    let id = gl::gen_framebuffers(1)[0];
+    gl_check()?;
+    Ok(Framebuffer {
+      id,
+      context: PhantomData,
+    })
+  }
+
+  pub fn id(&self) -> gl::GLuint {
+    self.id
+  }
+
+  pub fn bind(&self) -> Result<()> {
+    gl::bind_framebuffer(gl::GL_FRAMEBUFFER, self.id);
+    gl_check()
+  }
+
+  /// Goes back to drawing on the window.
+  pub fn bind_default() -> Result<()> {
+    gl::bind_framebuffer(gl::GL_FRAMEBUFFER, 0);
+    gl_check()
+  }
+
+  /// Binds the framebuffer and renders into level 0 of `texture` at `attachment`, usually
+  /// `GL_COLOR_ATTACHMENT0`. The framebuffer stays bound.
+  pub fn attach_texture(&self, attachment: gl::GLenum, texture: &Texture<'ctx>) -> Result<()> {
+    self.bind()?;
+    gl::framebuffer_texture_2d(
+      gl::GL_FRAMEBUFFER,
+      attachment,
+      gl::GL_TEXTURE_2D,
+      texture.id(),
+      0,
+    );
+    gl_check()
+  }
+}
+
+impl Drop for Framebuffer<'_> {
+  fn drop(&mut self) {
+    gl::delete_framebuffers(&[self.id]);
+  }
+}
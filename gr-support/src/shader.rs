@@ -0,0 +1,476 @@
+use crate::preprocess::Source;
+use crate::{gl, gl_check, Error, GlContext, Result, ShaderBindings};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+pub fn print_shader_info_log(shader: gl::GLuint) {
+  // Prints the compile log for a shader
+  if let Some(log) = shader_info_log(shader) {
+    println!("{}:shader:\n{}\n", shader, log);
+  }
+}
+
+pub fn print_program_info_log(program: gl::GLuint) {
+  // Prints the information log for a program object
+  if let Some(log) = program_info_log(program) {
+    println!("{}:program:\n{}\n", program, log);
+  }
+}
+
+fn shader_info_log(shader: gl::GLuint) -> Option<String> {
+  let length = gl::get_shaderiv(shader, gl::GL_INFO_LOG_LENGTH);
+  if length <= 1 {
+    return None;
+  }
+  gl::get_shader_info_log(shader, length).filter(|log| !log.trim().is_empty())
+}
+
+fn program_info_log(program: gl::GLuint) -> Option<String> {
+  let length = gl::get_programiv(program, gl::GL_INFO_LOG_LENGTH);
+  if length <= 1 {
+    return None;
+  }
+  gl::get_program_info_log(program, length).filter(|log| !log.trim().is_empty())
+}
+
+// ----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderStage {
+  Vertex,
+  Fragment,
+}
+
+impl ShaderStage {
+  pub fn gl_type(self) -> gl::GLenum {
+    match self {
+      ShaderStage::Vertex => gl::GL_VERTEX_SHADER,
+      ShaderStage::Fragment => gl::GL_FRAGMENT_SHADER,
+    }
+  }
+}
+
+impl fmt::Display for ShaderStage {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ShaderStage::Vertex => write!(f, "vertex shader"),
+      ShaderStage::Fragment => write!(f, "fragment shader"),
+    }
+  }
+}
+
+/// A line of shader source, numbered from 1 as drivers report them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLine {
+  pub number: usize,
+  pub text: String,
+  /// The file the line came from, for preprocessed sources. `number` is then a line of that file.
+  pub file: Option<String>,
+}
+
+impl SourceLine {
+  /// Picks the lines of `source` that an info log complains about.
+  ///
+  /// Understands the `0:12(5):` (Mesa), `ERROR: 0:12:` (Broadcom, ARM) and `0(12) :` styles of
+  /// pointing at a line; log lines that point nowhere are ignored.
+  pub fn from_log(source: &str, log: &str) -> Vec<SourceLine> {
+    let mut numbers: Vec<usize> = log.lines().filter_map(log_line_number).collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    let lines: Vec<&str> = source.lines().collect();
+    numbers
+      .into_iter()
+      .filter(|&number| number >= 1 && number <= lines.len())
+      .map(|number| SourceLine {
+        number,
+        text: lines[number - 1].to_string(),
+        file: None,
+      })
+      .collect()
+  }
+}
+
+fn leading_number(s: &str) -> Option<(usize, &str)> {
+  let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+  if end == 0 {
+    return None;
+  }
+  s[..end].parse().ok().map(|n| (n, &s[end..]))
+}
+
+fn log_line_number(line: &str) -> Option<usize> {
+  // Look for `<string>:<line>` or `<string>(<line>)` at every digit run in the line.
+  let mut rest = line;
+  while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+    let (_, after) = leading_number(&rest[start..])?;
+    let line_number = if let Some(tail) = after.strip_prefix(':') {
+      leading_number(tail).map(|(n, _)| n)
+    } else if let Some(tail) = after.strip_prefix('(') {
+      leading_number(tail)
+        .filter(|(_, tail)| tail.starts_with(')'))
+        .map(|(n, _)| n)
+    } else {
+      None
+    };
+    if line_number.is_some() {
+      return line_number;
+    }
+    rest = after;
+  }
+  None
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShaderError {
+  /// `build` was called without a source for `stage`.
+  MissingSource(ShaderStage),
+  /// `GL_COMPILE_STATUS` was false. `lines` are the source lines the log points at.
+  Compile {
+    stage: ShaderStage,
+    log: String,
+    lines: Vec<SourceLine>,
+  },
+  /// `GL_LINK_STATUS` was false.
+  Link { log: String },
+  /// The program has no active attribute with this name.
+  MissingAttribute(String),
+}
+
+impl fmt::Display for ShaderError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ShaderError::MissingSource(stage) => write!(f, "no source given for the {}", stage),
+      ShaderError::Compile { stage, log, lines } => {
+        write!(f, "{} failed to compile:\n{}", stage, log.trim_end())?;
+        for line in lines {
+          match &line.file {
+            Some(file) => write!(f, "\n{}:{} | {}", file, line.number, line.text)?,
+            None => write!(f, "\n{:>4} | {}", line.number, line.text)?,
+          }
+        }
+        Ok(())
+      }
+      ShaderError::Link { log } => write!(f, "program failed to link:\n{}", log.trim_end()),
+      ShaderError::MissingAttribute(name) => write!(f, "{} is not an active attribute", name),
+    }
+  }
+}
+
+impl std::error::Error for ShaderError {}
+
+// ----------------------------------------------------------------------------
+
+/// A compiled shader, deleted on drop.
+#[derive(Debug)]
+pub struct Shader<'ctx> {
+  id: gl::GLuint,
+  stage: ShaderStage,
+  context: PhantomData<&'ctx GlContext>,
+}
+
+impl<'ctx> Shader<'ctx> {
+  /// Compiles `source`, checking `GL_COMPILE_STATUS`.
+  pub fn compile(
+    _context: &'ctx GlContext,
+    stage: ShaderStage,
+    source: &str,
+  ) -> Result<Shader<'ctx>> {
+    let id = gl::create_shader(stage.gl_type());
+    gl_check()?;
+    let shader = Shader {
+      id,
+      stage,
+      context: PhantomData,
+    };
+
+    gl::shader_source(id, source.as_bytes());
+    gl::compile_shader(id);
+    gl_check()?;
+
+    if gl::get_shaderiv(id, gl::GL_COMPILE_STATUS) == 0 {
+      let log = shader.info_log().unwrap_or_default();
+      return Err(Error::Shader(ShaderError::Compile {
+        stage,
+        lines: SourceLine::from_log(source, &log),
+        log,
+      }));
+    }
+
+    Ok(shader)
+  }
+
+  pub fn id(&self) -> gl::GLuint {
+    self.id
+  }
+
+  pub fn stage(&self) -> ShaderStage {
+    self.stage
+  }
+
+  pub fn info_log(&self) -> Option<String> {
+    shader_info_log(self.id)
+  }
+}
+
+impl Drop for Shader<'_> {
+  fn drop(&mut self) {
+    // Shaders still attached to a program are only flagged for deletion, they go away with it.
+    gl::delete_shader(self.id);
+  }
+}
+
+/// A linked program, deleted on drop.
+#[derive(Debug)]
+pub struct Program<'ctx> {
+  id: gl::GLuint,
+  context: PhantomData<&'ctx GlContext>,
+}
+
+impl<'ctx> Program<'ctx> {
+  /// Links `shaders` into a program, checking `GL_LINK_STATUS`.
+  pub fn link(shaders: &[&Shader<'ctx>]) -> Result<Program<'ctx>> {
+    let id = gl::create_program();
+    gl_check()?;
+    let program = Program {
+      id,
+      context: PhantomData,
+    };
+
+    for shader in shaders {
+      gl::attach_shader(id, shader.id());
+    }
+    gl::link_program(id);
+    gl_check()?;
+
+    if gl::get_programiv(id, gl::GL_LINK_STATUS) == 0 {
+      let log = program.info_log().unwrap_or_default();
+      return Err(Error::Shader(ShaderError::Link { log }));
+    }
+
+    Ok(program)
+  }
+
+  pub fn id(&self) -> gl::GLuint {
+    self.id
+  }
+
+  pub fn use_program(&self) -> Result<()> {
+    gl::use_program(self.id);
+    gl_check()
+  }
+
+  pub fn info_log(&self) -> Option<String> {
+    program_info_log(self.id)
+  }
+}
+
+impl Drop for Program<'_> {
+  fn drop(&mut self) {
+    gl::delete_program(self.id);
+  }
+}
+
+// ----------------------------------------------------------------------------
+
+/// An active attribute or uniform of a linked program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Variable {
+  pub location: gl::GLint,
+  /// GLSL type, e.g. `GL_FLOAT_VEC4` or `GL_SAMPLER_2D`.
+  pub type_: gl::GLenum,
+  /// Array length, 1 for non-arrays.
+  pub size: gl::GLint,
+}
+
+/// A linked program together with its active attributes and uniforms.
+///
+/// Build one with [`ShaderProgram::builder`].
+#[derive(Debug)]
+pub struct ShaderProgram<'ctx> {
+  program: Program<'ctx>,
+  attributes: HashMap<String, Variable>,
+  uniforms: HashMap<String, Variable>,
+}
+
+impl<'ctx> ShaderProgram<'ctx> {
+  pub fn builder<'a>() -> ShaderProgramBuilder<'a> {
+    ShaderProgramBuilder {
+      vertex: None,
+      fragment: None,
+      verbose: false,
+    }
+  }
+
+  pub fn id(&self) -> gl::GLuint {
+    self.program.id()
+  }
+
+  pub fn program(&self) -> &Program<'ctx> {
+    &self.program
+  }
+
+  pub fn use_program(&self) -> Result<()> {
+    self.program.use_program()
+  }
+
+  pub fn attribute(&self, name: &str) -> Option<&Variable> {
+    self.attributes.get(name)
+  }
+
+  pub fn uniform(&self, name: &str) -> Option<&Variable> {
+    self.uniforms.get(name)
+  }
+
+  /// Location of an active attribute, ready for `vertex_attrib_pointer` and friends.
+  pub fn attrib_location(&self, name: &str) -> Result<gl::GLuint> {
+    self
+      .attribute(name)
+      .map(|attribute| attribute.location as gl::GLuint)
+      .ok_or_else(|| Error::Shader(ShaderError::MissingAttribute(name.to_string())))
+  }
+
+  /// Fills a `#[derive(ShaderBindings)]` struct from this program.
+  pub fn bind<B: ShaderBindings>(&self) -> Result<B> {
+    B::bind(self)
+  }
+
+  /// Location of a uniform, or -1 when it is not active, which GL silently ignores.
+  pub fn uniform_location(&self, name: &str) -> gl::GLint {
+    self.uniform(name).map_or(-1, |uniform| uniform.location)
+  }
+}
+
+/// Source of one stage given to [`ShaderProgramBuilder`], plain text or preprocessed.
+#[derive(Clone, Copy, Debug)]
+pub enum StageSource<'a> {
+  Text(&'a str),
+  /// Compile errors point at the files the lines came from.
+  Preprocessed(&'a Source),
+}
+
+impl<'a> StageSource<'a> {
+  fn compile<'ctx>(self, context: &'ctx GlContext, stage: ShaderStage) -> Result<Shader<'ctx>> {
+    match self {
+      StageSource::Text(text) => Shader::compile(context, stage, text),
+      StageSource::Preprocessed(source) => {
+        Shader::compile(context, stage, source.text()).map_err(|err| source.locate(err))
+      }
+    }
+  }
+}
+
+impl<'a> From<&'a str> for StageSource<'a> {
+  fn from(text: &'a str) -> StageSource<'a> {
+    StageSource::Text(text)
+  }
+}
+
+impl<'a> From<&'a String> for StageSource<'a> {
+  fn from(text: &'a String) -> StageSource<'a> {
+    StageSource::Text(text)
+  }
+}
+
+impl<'a> From<&'a Source> for StageSource<'a> {
+  fn from(source: &'a Source) -> StageSource<'a> {
+    StageSource::Preprocessed(source)
+  }
+}
+
+pub struct ShaderProgramBuilder<'a> {
+  vertex: Option<StageSource<'a>>,
+  fragment: Option<StageSource<'a>>,
+  verbose: bool,
+}
+
+impl<'a> ShaderProgramBuilder<'a> {
+  pub fn vertex<S: Into<StageSource<'a>>>(mut self, source: S) -> Self {
+    self.vertex = Some(source.into());
+    self
+  }
+
+  pub fn fragment<S: Into<StageSource<'a>>>(mut self, source: S) -> Self {
+    self.fragment = Some(source.into());
+    self
+  }
+
+  /// Prints the info logs of shaders and programs that did build, which may hold warnings.
+  pub fn verbose(mut self, verbose: bool) -> Self {
+    self.verbose = verbose;
+    self
+  }
+
+  /// Compiles both stages and links them, checking `GL_COMPILE_STATUS` and `GL_LINK_STATUS`.
+  pub fn build<'ctx>(self, context: &'ctx GlContext) -> Result<ShaderProgram<'ctx>> {
+    let vertex_source = self.vertex.ok_or(Error::Shader(ShaderError::MissingSource(
+      ShaderStage::Vertex,
+    )))?;
+    let fragment_source = self
+      .fragment
+      .ok_or(Error::Shader(ShaderError::MissingSource(
+        ShaderStage::Fragment,
+      )))?;
+
+    let vertex = vertex_source.compile(context, ShaderStage::Vertex)?;
+    let fragment = fragment_source.compile(context, ShaderStage::Fragment)?;
+    if self.verbose {
+      print_shader_info_log(vertex.id());
+      print_shader_info_log(fragment.id());
+    }
+
+    let program = Program::link(&[&vertex, &fragment])?;
+    if self.verbose {
+      print_program_info_log(program.id());
+    }
+
+    let id = program.id();
+    Ok(ShaderProgram {
+      program,
+      attributes: active_variables(id, false),
+      uniforms: active_variables(id, true),
+    })
+  }
+}
+
+fn active_variables(program: gl::GLuint, uniforms: bool) -> HashMap<String, Variable> {
+  let count = if uniforms {
+    gl::get_programiv(program, gl::GL_ACTIVE_UNIFORMS)
+  } else {
+    gl::get_programiv(program, gl::GL_ACTIVE_ATTRIBUTES)
+  };
+
+  let mut variables = HashMap::new();
+  for index in 0..count.max(0) as gl::GLuint {
+    let active = if uniforms {
+      gl::get_active_uniform(program, index)
+    } else {
+      gl::get_active_attrib(program, index)
+    };
+    let (full_name, size, type_) = match active {
+      Some(active) => active,
+      None => continue,
+    };
+    // Arrays are reported as `name[0]`, but looked up by plain name.
+    let name = full_name.trim_end_matches("[0]").to_string();
+    let location = if uniforms {
+      gl::get_uniform_location(program, &name)
+    } else {
+      gl::get_attrib_location(program, &name)
+    };
+    if location < 0 {
+      continue;
+    }
+
+    variables.insert(
+      name,
+      Variable {
+        location,
+        type_,
+        size,
+      },
+    );
+  }
+  variables
+}
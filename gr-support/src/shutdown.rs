@@ -0,0 +1,93 @@
+//! Stops a render loop on `SIGINT` and `SIGTERM`, so it can return and drop its GL objects, the
+//! context and input devices instead of being killed mid-frame.
+//!
+//! ```ignore
+//! shutdown::install();
+//! while !shutdown::requested() {
+//!   draw(&context)?;
+//!   context.swap_buffers();
+//! }
+//! drop(context);
+//! process::exit(shutdown::exit_code().unwrap_or(0));
+//! ```
+//!
+//! A second signal, for a loop that does not get round to checking, kills the process as usual,
+//! without dropping anything. A terminal in raw mode is put back first, or the shell would be
+//! left without echo.
+
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Once;
+
+/// The signal that asked to stop, or 0.
+static SIGNAL: AtomicI32 = AtomicI32::new(0);
+static INSTALL: Once = Once::new();
+
+/// The settings of stdin to restore before being killed, valid while `TERMINAL_SAVED`. Only
+/// written while it is not.
+static mut TERMINAL: MaybeUninit<libc::termios> = MaybeUninit::uninit();
+static TERMINAL_SAVED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn stop(signal: libc::c_int) {
+  if SIGNAL.swap(signal, Ordering::SeqCst) != 0 {
+    // All async-signal-safe
+    unsafe {
+      if TERMINAL_SAVED.load(Ordering::SeqCst) {
+        let saved = ptr::addr_of!(TERMINAL).cast::<libc::termios>();
+        libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, saved);
+      }
+      libc::signal(signal, libc::SIG_DFL);
+      libc::raise(signal);
+    }
+  }
+}
+
+/// Has a second signal set stdin back to `saved` before killing the process, until
+/// [`forget_terminal`].
+pub(crate) fn restore_terminal(saved: &libc::termios) {
+  TERMINAL_SAVED.store(false, Ordering::SeqCst);
+  unsafe {
+    ptr::addr_of_mut!(TERMINAL)
+      .cast::<libc::termios>()
+      .write(*saved)
+  };
+  TERMINAL_SAVED.store(true, Ordering::SeqCst);
+}
+
+pub(crate) fn forget_terminal() {
+  TERMINAL_SAVED.store(false, Ordering::SeqCst);
+}
+
+/// Handles `SIGINT` and `SIGTERM` from now on. Only the first call installs the handlers.
+pub fn install() {
+  INSTALL.call_once(|| {
+    for &signal in &[libc::SIGINT, libc::SIGTERM] {
+      unsafe {
+        libc::signal(
+          signal,
+          stop as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        )
+      };
+    }
+  });
+}
+
+/// Whether a signal asked to stop.
+pub fn requested() -> bool {
+  signal().is_some()
+}
+
+/// The signal that asked to stop.
+pub fn signal() -> Option<libc::c_int> {
+  match SIGNAL.load(Ordering::SeqCst) {
+    0 => None,
+    signal => Some(signal),
+  }
+}
+
+/// The status to exit with after stopping for a signal: 128 plus its number, as a shell reports a
+/// process killed by it. 130 for `SIGINT`, 143 for `SIGTERM`.
+pub fn exit_code() -> Option<i32> {
+  signal().map(|signal| 128 + signal)
+}
@@ -0,0 +1,1374 @@
+//! A [`RenderTarget`] drawn on the CPU, for tests on machines without a GPU or EGL.
+//!
+//! While a [`Soft`] target is current, the functions in [`gl`](crate::gl) run here instead of in
+//! the driver, so the examples' setup and draw code produces pixels that `gl::read_pixels` reads
+//! back. GLSL is not run: each shader source is registered with a Rust closure standing in for
+//! it, and only its declarations are read, for the attributes, uniforms and `#define`s.
+//!
+//! It covers what the examples use: float vertex arrays, `GL_TRIANGLES` and `GL_TRIANGLE_FAN`,
+//! float, vector, matrix and sampler uniforms, one 2D texture sampled nearest and repeating, and
+//! framebuffers with a texture attached. There is no clipping, depth test or blending.
+
+use crate::bindings::glsl_type;
+use crate::RenderTarget;
+use opengles::glesv2 as gl;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
+
+type VertexFn = dyn Fn(&Vertex) -> VertexOutput;
+type FragmentFn = dyn Fn(&Fragment) -> [f32; 4];
+
+thread_local! {
+  static CURRENT: RefCell<Option<Rc<RefCell<State>>>> = const { RefCell::new(None) };
+}
+
+/// The state of the [`Soft`] target current on this thread, if any.
+pub(crate) fn current() -> Option<Rc<RefCell<State>>> {
+  CURRENT.with(|current| current.borrow().clone())
+}
+
+/// A `width` x `height` RGBA surface. Creating one makes it current on this thread, until it is
+/// dropped or another one is created.
+pub struct Soft {
+  state: Rc<RefCell<State>>,
+}
+
+impl Soft {
+  pub fn new(width: u32, height: u32) -> Soft {
+    let state = Rc::new(RefCell::new(State::new(width, height)));
+    CURRENT.with(|current| *current.borrow_mut() = Some(state.clone()));
+    Soft { state }
+  }
+
+  /// Runs `shader` in place of vertex shaders compiled from `source`.
+  ///
+  /// Sources are compared line by line, skipping preprocessor directives and lines that are not
+  /// in `source`, so the closure stands in for the shader whatever it is built with `#define`d
+  /// or `#include`d. When several sources match, the longest wins.
+  pub fn vertex_shader<F>(self, source: &str, shader: F) -> Soft
+  where
+    F: Fn(&Vertex) -> VertexOutput + 'static,
+  {
+    let closure = Closure::Vertex(Rc::new(shader));
+    self
+      .state
+      .borrow_mut()
+      .closures
+      .push((code_lines(source), closure));
+    self
+  }
+
+  /// Runs `shader` in place of fragment shaders compiled from `source`, matched like
+  /// [`vertex_shader`](Soft::vertex_shader). It returns `gl_FragColor`.
+  pub fn fragment_shader<F>(self, source: &str, shader: F) -> Soft
+  where
+    F: Fn(&Fragment) -> [f32; 4] + 'static,
+  {
+    let closure = Closure::Fragment(Rc::new(shader));
+    self
+      .state
+      .borrow_mut()
+      .closures
+      .push((code_lines(source), closure));
+    self
+  }
+}
+
+impl RenderTarget for Soft {
+  fn width(&self) -> u32 {
+    self.state.borrow().surface.width as u32
+  }
+
+  fn height(&self) -> u32 {
+    self.state.borrow().surface.height as u32
+  }
+
+  /// There is no EGL underneath.
+  fn egl_version(&self) -> (i32, i32) {
+    (0, 0)
+  }
+
+  /// The surface is single buffered, the frame stays in place to be read back.
+  fn swap_buffers(&mut self) {}
+}
+
+impl Drop for Soft {
+  fn drop(&mut self) {
+    CURRENT.with(|current| {
+      let mut current = current.borrow_mut();
+      if matches!(&*current, Some(state) if Rc::ptr_eq(state, &self.state)) {
+        *current = None;
+      }
+    });
+  }
+}
+
+/// `matrix * vector` for a column-major `mat4`, as uniforms hold it.
+pub fn transform(matrix: &[f32], vector: [f32; 4]) -> [f32; 4] {
+  let mut result = [0.0; 4];
+  for (row, value) in result.iter_mut().enumerate() {
+    *value = (0..4)
+      .map(|column| matrix[column * 4 + row] * vector[column])
+      .sum();
+  }
+  result
+}
+
+// ----------------------------------------------------------------------------
+
+/// What a vertex shader closure sees.
+pub struct Vertex<'a> {
+  attributes: &'a [[f32; 4]],
+  program: &'a Linked,
+}
+
+impl Vertex<'_> {
+  /// The attribute's value, with the components the array does not have taken from
+  /// `(0, 0, 0, 1)`.
+  pub fn attribute(&self, name: &str) -> [f32; 4] {
+    match self.program.attributes.iter().position(|a| a.name == name) {
+      Some(location) => self.attributes[location],
+      None => panic!("the vertex shader declares no attribute {}", name),
+    }
+  }
+
+  pub fn uniform(&self, name: &str) -> &[f32] {
+    self.program.uniform(name)
+  }
+
+  pub fn define(&self, name: &str) -> Option<&str> {
+    self.program.defines.get(name).map(String::as_str)
+  }
+}
+
+/// What a vertex shader closure returns: `gl_Position`, and the varyings to interpolate across
+/// the triangle for the fragment shader.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VertexOutput {
+  pub position: [f32; 4],
+  pub varyings: Vec<f32>,
+}
+
+/// What a fragment shader closure sees.
+pub struct Fragment<'a> {
+  /// `gl_FragCoord.xy`: the pixel's centre, from the bottom left of the surface.
+  pub frag_coord: [f32; 2],
+  /// The vertex shader's varyings, interpolated.
+  pub varyings: &'a [f32],
+  program: &'a Linked,
+  texture: Option<&'a Image>,
+}
+
+impl Fragment<'_> {
+  pub fn uniform(&self, name: &str) -> &[f32] {
+    self.program.uniform(name)
+  }
+
+  pub fn define(&self, name: &str) -> Option<&str> {
+    self.program.defines.get(name).map(String::as_str)
+  }
+
+  /// `texture2D(sampler, coord)`. Whatever unit `sampler` is set to, this reads the texture
+  /// bound to `GL_TEXTURE_2D`, or `(0, 0, 0, 1)` without one.
+  pub fn texture_2d(&self, sampler: &str, coord: [f32; 2]) -> [f32; 4] {
+    self.program.uniform(sampler);
+    match self.texture {
+      Some(texture) if !texture.pixels.is_empty() => texture.sample(coord),
+      _ => [0.0, 0.0, 0.0, 1.0],
+    }
+  }
+}
+
+// ----------------------------------------------------------------------------
+
+#[derive(Clone)]
+enum Closure {
+  Vertex(Rc<VertexFn>),
+  Fragment(Rc<FragmentFn>),
+}
+
+impl Closure {
+  fn stage(&self) -> gl::GLenum {
+    match self {
+      Closure::Vertex(_) => gl::GL_VERTEX_SHADER,
+      Closure::Fragment(_) => gl::GL_FRAGMENT_SHADER,
+    }
+  }
+}
+
+/// An `attribute` or `uniform` declaration.
+#[derive(Clone, Debug, PartialEq)]
+struct Declaration {
+  name: String,
+  type_: gl::GLenum,
+  size: usize,
+}
+
+#[derive(Default)]
+struct Declarations {
+  attributes: Vec<Declaration>,
+  uniforms: Vec<Declaration>,
+  defines: HashMap<String, String>,
+}
+
+struct ShaderObject {
+  stage: gl::GLenum,
+  source: String,
+  closure: Option<Closure>,
+  log: String,
+}
+
+#[derive(Default)]
+struct ProgramObject {
+  shaders: Vec<gl::GLuint>,
+  linked: Option<Linked>,
+  log: String,
+}
+
+struct Linked {
+  vertex: Rc<VertexFn>,
+  fragment: Rc<FragmentFn>,
+  /// Indexed by location, as are `uniforms` and `values`.
+  attributes: Vec<Declaration>,
+  uniforms: Vec<Declaration>,
+  values: Vec<Vec<f32>>,
+  defines: HashMap<String, String>,
+}
+
+impl Linked {
+  fn uniform(&self, name: &str) -> &[f32] {
+    match self.uniforms.iter().position(|u| u.name == name) {
+      Some(location) => &self.values[location],
+      None => panic!("the program declares no uniform {}", name),
+    }
+  }
+}
+
+#[derive(Clone, Copy)]
+struct AttributeArray {
+  enabled: bool,
+  buffer: gl::GLuint,
+  size: usize,
+  stride: usize,
+  offset: usize,
+}
+
+/// RGBA8 pixels, bottom row first. `GL_UNSIGNED_SHORT_5_6_5` images keep that precision.
+#[derive(Clone, Default)]
+struct Image {
+  width: usize,
+  height: usize,
+  rgb565: bool,
+  pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+  fn new(width: usize, height: usize, rgb565: bool) -> Image {
+    Image {
+      width,
+      height,
+      rgb565,
+      pixels: vec![[0, 0, 0, 255]; width * height],
+    }
+  }
+
+  fn store(&mut self, x: usize, y: usize, color: [f32; 4]) {
+    let channel = |value: f32, bits: u32| {
+      let max = ((1 << bits) - 1) as f32;
+      let value = (value.clamp(0.0, 1.0) * max).round() as u32;
+      // Widen back to 8 bits the way GL does, repeating the high bits
+      ((value << (8 - bits)) | (value >> (2 * bits).saturating_sub(8))) as u8
+    };
+    self.pixels[y * self.width + x] = if self.rgb565 {
+      [
+        channel(color[0], 5),
+        channel(color[1], 6),
+        channel(color[2], 5),
+        255,
+      ]
+    } else {
+      [
+        channel(color[0], 8),
+        channel(color[1], 8),
+        channel(color[2], 8),
+        channel(color[3], 8),
+      ]
+    };
+  }
+
+  fn sample(&self, coord: [f32; 2]) -> [f32; 4] {
+    let texel = |coord: f32, size: usize| {
+      let texel = (coord - coord.floor()) * size as f32;
+      (texel as usize).min(size - 1)
+    };
+    let pixel =
+      self.pixels[texel(coord[1], self.height) * self.width + texel(coord[0], self.width)];
+    let mut color = [0.0; 4];
+    for (value, &byte) in color.iter_mut().zip(pixel.iter()) {
+      *value = byte as f32 / 255.0;
+    }
+    color
+  }
+}
+
+/// Where draws go.
+#[derive(Clone, Copy, PartialEq)]
+enum Target {
+  Surface,
+  Texture(gl::GLuint),
+}
+
+/// The GL state of a [`Soft`] target. Its methods are the [`gl`](crate::gl) functions.
+pub(crate) struct State {
+  error: gl::GLenum,
+  clear_color: [f32; 4],
+  viewport: [gl::GLint; 4],
+  next_name: gl::GLuint,
+  surface: Image,
+  buffers: HashMap<gl::GLuint, Vec<u8>>,
+  textures: HashMap<gl::GLuint, Image>,
+  /// The texture attached to each framebuffer, 0 for none.
+  framebuffers: HashMap<gl::GLuint, gl::GLuint>,
+  shaders: HashMap<gl::GLuint, ShaderObject>,
+  programs: HashMap<gl::GLuint, ProgramObject>,
+  array_buffer: gl::GLuint,
+  texture: gl::GLuint,
+  framebuffer: gl::GLuint,
+  program: gl::GLuint,
+  arrays: Vec<AttributeArray>,
+  closures: Vec<(Vec<String>, Closure)>,
+}
+
+impl State {
+  fn new(width: u32, height: u32) -> State {
+    State {
+      error: gl::GL_NO_ERROR,
+      clear_color: [0.0; 4],
+      viewport: [0, 0, width as gl::GLint, height as gl::GLint],
+      next_name: 1,
+      surface: Image::new(width as usize, height as usize, false),
+      buffers: HashMap::new(),
+      textures: HashMap::new(),
+      framebuffers: HashMap::new(),
+      shaders: HashMap::new(),
+      programs: HashMap::new(),
+      array_buffer: 0,
+      texture: 0,
+      framebuffer: 0,
+      program: 0,
+      arrays: Vec::new(),
+      closures: Vec::new(),
+    }
+  }
+
+  /// Records `error` for `get_error`, unless an earlier one is still pending.
+  fn fail(&mut self, error: gl::GLenum) {
+    if self.error == gl::GL_NO_ERROR {
+      self.error = error;
+    }
+  }
+
+  fn names(&mut self, n: gl::GLsizei) -> Vec<gl::GLuint> {
+    let first = self.next_name;
+    self.next_name += n.max(0) as gl::GLuint;
+    (first..self.next_name).collect()
+  }
+
+  pub fn get_error(&mut self) -> gl::GLenum {
+    mem::replace(&mut self.error, gl::GL_NO_ERROR)
+  }
+
+  pub fn get_string(&mut self, name: gl::GLenum) -> Option<String> {
+    match name {
+      gl::GL_VENDOR => Some("gr-support".to_string()),
+      gl::GL_RENDERER => Some("gr-support software rasterizer".to_string()),
+      gl::GL_VERSION => Some("OpenGL ES 2.0 gr-support".to_string()),
+      _ => {
+        self.fail(gl::GL_INVALID_ENUM);
+        None
+      }
+    }
+  }
+
+  pub fn get_integerv(&mut self, pname: gl::GLenum, params: &mut [gl::GLint]) {
+    match pname {
+      gl::GL_VIEWPORT => params[..4].copy_from_slice(&self.viewport),
+      _ => self.fail(gl::GL_INVALID_ENUM),
+    }
+  }
+
+  pub fn flush(&mut self) {}
+
+  pub fn finish(&mut self) {}
+
+  pub fn viewport(&mut self, x: gl::GLint, y: gl::GLint, width: gl::GLsizei, height: gl::GLsizei) {
+    if width < 0 || height < 0 {
+      return self.fail(gl::GL_INVALID_VALUE);
+    }
+    self.viewport = [x, y, width, height];
+  }
+
+  pub fn clear_color(
+    &mut self,
+    red: gl::GLclampf,
+    green: gl::GLclampf,
+    blue: gl::GLclampf,
+    alpha: gl::GLclampf,
+  ) {
+    self.clear_color = [red, green, blue, alpha];
+  }
+
+  pub fn clear(&mut self, mask: gl::GLbitfield) {
+    if mask & gl::GL_COLOR_BUFFER_BIT == 0 {
+      return;
+    }
+    let color = self.clear_color;
+    if let Some(image) = self.target().and_then(|target| self.image_mut(target)) {
+      for y in 0..image.height {
+        for x in 0..image.width {
+          image.store(x, y, color);
+        }
+      }
+    }
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  pub fn read_pixels(
+    &mut self,
+    x: gl::GLint,
+    y: gl::GLint,
+    width: gl::GLsizei,
+    height: gl::GLsizei,
+    format: gl::GLenum,
+    type_: gl::GLenum,
+    pixels: &mut [u8],
+  ) {
+    if format != gl::GL_RGBA || type_ != gl::GL_UNSIGNED_BYTE {
+      return self.fail(gl::GL_INVALID_ENUM);
+    }
+    if width < 0 || height < 0 {
+      return self.fail(gl::GL_INVALID_VALUE);
+    }
+    let image = match self.target() {
+      Some(Target::Surface) => &self.surface,
+      Some(Target::Texture(texture)) => &self.textures[&texture],
+      None => return,
+    };
+
+    for row in 0..height {
+      for column in 0..width {
+        let (source_x, source_y) = (x + column, y + row);
+        let start = ((row * width + column) * 4) as usize;
+        let inside = source_x >= 0
+          && source_y >= 0
+          && (source_x as usize) < image.width
+          && (source_y as usize) < image.height;
+        // Pixels outside the surface are undefined, they are left alone
+        if inside && start + 4 <= pixels.len() {
+          let pixel = image.pixels[source_y as usize * image.width + source_x as usize];
+          pixels[start..start + 4].copy_from_slice(&pixel);
+        }
+      }
+    }
+  }
+
+  // Buffers ------------------------------------------------------------------
+
+  pub fn gen_buffers(&mut self, n: gl::GLsizei) -> Vec<gl::GLuint> {
+    self.names(n)
+  }
+
+  pub fn bind_buffer(&mut self, target: gl::GLenum, buffer: gl::GLuint) {
+    if target != gl::GL_ARRAY_BUFFER {
+      return self.fail(gl::GL_INVALID_ENUM);
+    }
+    if buffer != 0 {
+      self.buffers.entry(buffer).or_default();
+    }
+    self.array_buffer = buffer;
+  }
+
+  pub fn buffer_data(&mut self, target: gl::GLenum, data: &[u8], _usage: gl::GLenum) {
+    if target != gl::GL_ARRAY_BUFFER {
+      return self.fail(gl::GL_INVALID_ENUM);
+    }
+    if self.array_buffer == 0 {
+      return self.fail(gl::GL_INVALID_OPERATION);
+    }
+    self.buffers.insert(self.array_buffer, data.to_vec());
+  }
+
+  pub fn delete_buffers(&mut self, buffers: &[gl::GLuint]) {
+    for buffer in buffers {
+      self.buffers.remove(buffer);
+      if self.array_buffer == *buffer {
+        self.array_buffer = 0;
+      }
+    }
+  }
+
+  pub fn is_buffer(&mut self, buffer: gl::GLuint) -> bool {
+    self.buffers.contains_key(&buffer)
+  }
+
+  // Textures and framebuffers ------------------------------------------------
+
+  pub fn gen_textures(&mut self, n: gl::GLsizei) -> Vec<gl::GLuint> {
+    self.names(n)
+  }
+
+  pub fn bind_texture(&mut self, target: gl::GLenum, texture: gl::GLuint) {
+    if target != gl::GL_TEXTURE_2D {
+      return self.fail(gl::GL_INVALID_ENUM);
+    }
+    self.texture = texture;
+  }
+
+  /// Only level 0, from `GL_RGBA` or `GL_RGB` bytes or `GL_RGB` 5-6-5 shorts.
+  #[allow(clippy::too_many_arguments)]
+  pub fn tex_image_2d(
+    &mut self,
+    target: gl::GLenum,
+    level: gl::GLint,
+    _internal_format: gl::GLint,
+    width: gl::GLsizei,
+    height: gl::GLsizei,
+    _border: gl::GLint,
+    format: gl::GLenum,
+    type_: gl::GLenum,
+    data: &[u8],
+  ) {
+    if target != gl::GL_TEXTURE_2D {
+      return self.fail(gl::GL_INVALID_ENUM);
+    }
+    if level != 0 || width < 0 || height < 0 {
+      return self.fail(gl::GL_INVALID_VALUE);
+    }
+    if self.texture == 0 {
+      return self.fail(gl::GL_INVALID_OPERATION);
+    }
+    let bytes_per_pixel = match (format, type_) {
+      (gl::GL_RGBA, gl::GL_UNSIGNED_BYTE) => 4,
+      (gl::GL_RGB, gl::GL_UNSIGNED_BYTE) => 3,
+      (gl::GL_RGB, gl::GL_UNSIGNED_SHORT_5_6_5) => 2,
+      _ => return self.fail(gl::GL_INVALID_ENUM),
+    };
+
+    let (width, height) = (width as usize, height as usize);
+    let mut image = Image::new(width, height, type_ == gl::GL_UNSIGNED_SHORT_5_6_5);
+    if !data.is_empty() {
+      // Rows start on 4 byte boundaries, GL_UNPACK_ALIGNMENT's default
+      let stride = (width * bytes_per_pixel).div_ceil(4) * 4;
+      if data.len() < stride * height.saturating_sub(1) + width * bytes_per_pixel {
+        return self.fail(gl::GL_INVALID_VALUE);
+      }
+      for y in 0..height {
+        for x in 0..width {
+          let bytes = &data[y * stride + x * bytes_per_pixel..][..bytes_per_pixel];
+          image.pixels[y * width + x] = match bytes_per_pixel {
+            4 => [bytes[0], bytes[1], bytes[2], bytes[3]],
+            3 => [bytes[0], bytes[1], bytes[2], 255],
+            _ => {
+              let rgb = u16::from_ne_bytes([bytes[0], bytes[1]]);
+              let widen =
+                |value: u16, bits: u32| ((value << (8 - bits)) | (value >> (2 * bits - 8))) as u8;
+              [
+                widen(rgb >> 11, 5),
+                widen((rgb >> 5) & 0x3f, 6),
+                widen(rgb & 0x1f, 5),
+                255,
+              ]
+            }
+          };
+        }
+      }
+    }
+    self.textures.insert(self.texture, image);
+  }
+
+  /// Accepted and ignored, textures are always sampled nearest.
+  pub fn tex_parameterf(&mut self, target: gl::GLenum, _pname: gl::GLenum, _param: gl::GLfloat) {
+    if target != gl::GL_TEXTURE_2D {
+      self.fail(gl::GL_INVALID_ENUM);
+    }
+  }
+
+  pub fn delete_textures(&mut self, textures: &[gl::GLuint]) {
+    for texture in textures {
+      self.textures.remove(texture);
+      if self.texture == *texture {
+        self.texture = 0;
+      }
+      for attached in self.framebuffers.values_mut() {
+        if attached == texture {
+          *attached = 0;
+        }
+      }
+    }
+  }
+
+  /// Only once the texture has an image, where GL makes it one as soon as it is bound.
+  pub fn is_texture(&mut self, texture: gl::GLuint) -> bool {
+    self.textures.contains_key(&texture)
+  }
+
+  pub fn gen_framebuffers(&mut self, n: gl::GLsizei) -> Vec<gl::GLuint> {
+    self.names(n)
+  }
+
+  pub fn bind_framebuffer(&mut self, target: gl::GLenum, framebuffer: gl::GLuint) {
+    if target != gl::GL_FRAMEBUFFER {
+      return self.fail(gl::GL_INVALID_ENUM);
+    }
+    if framebuffer != 0 {
+      self.framebuffers.entry(framebuffer).or_insert(0);
+    }
+    self.framebuffer = framebuffer;
+  }
+
+  pub fn framebuffer_texture_2d(
+    &mut self,
+    target: gl::GLenum,
+    attachment: gl::GLenum,
+    textarget: gl::GLenum,
+    texture: gl::GLuint,
+    level: gl::GLint,
+  ) {
+    if target != gl::GL_FRAMEBUFFER
+      || attachment != gl::GL_COLOR_ATTACHMENT0
+      || textarget != gl::GL_TEXTURE_2D
+    {
+      return self.fail(gl::GL_INVALID_ENUM);
+    }
+    if level != 0 {
+      return self.fail(gl::GL_INVALID_VALUE);
+    }
+    match self.framebuffers.get_mut(&self.framebuffer) {
+      Some(attached) => *attached = texture,
+      None => self.fail(gl::GL_INVALID_OPERATION),
+    }
+  }
+
+  pub fn delete_framebuffers(&mut self, framebuffers: &[gl::GLuint]) {
+    for framebuffer in framebuffers {
+      self.framebuffers.remove(framebuffer);
+      if self.framebuffer == *framebuffer {
+        self.framebuffer = 0;
+      }
+    }
+  }
+
+  pub fn is_framebuffer(&mut self, framebuffer: gl::GLuint) -> bool {
+    self.framebuffers.contains_key(&framebuffer)
+  }
+
+  /// Where draws, clears and reads go, or `None` (and `GL_INVALID_FRAMEBUFFER_OPERATION`) when
+  /// the bound framebuffer has no texture to draw to.
+  fn target(&mut self) -> Option<Target> {
+    if self.framebuffer == 0 {
+      return Some(Target::Surface);
+    }
+    match self.framebuffers.get(&self.framebuffer) {
+      Some(texture) if self.textures.contains_key(texture) => Some(Target::Texture(*texture)),
+      _ => {
+        self.fail(gl::GL_INVALID_FRAMEBUFFER_OPERATION);
+        None
+      }
+    }
+  }
+
+  fn image_mut(&mut self, target: Target) -> Option<&mut Image> {
+    match target {
+      Target::Surface => Some(&mut self.surface),
+      Target::Texture(texture) => self.textures.get_mut(&texture),
+    }
+  }
+
+  // Shaders and programs -----------------------------------------------------
+
+  pub fn create_shader(&mut self, type_: gl::GLenum) -> gl::GLuint {
+    if type_ != gl::GL_VERTEX_SHADER && type_ != gl::GL_FRAGMENT_SHADER {
+      self.fail(gl::GL_INVALID_ENUM);
+      return 0;
+    }
+    let id = self.names(1)[0];
+    let shader = ShaderObject {
+      stage: type_,
+      source: String::new(),
+      closure: None,
+      log: String::new(),
+    };
+    self.shaders.insert(id, shader);
+    id
+  }
+
+  pub fn shader_source(&mut self, shader: gl::GLuint, source: &[u8]) {
+    match self.shaders.get_mut(&shader) {
+      Some(shader) => shader.source = String::from_utf8_lossy(source).into_owned(),
+      None => self.fail(gl::GL_INVALID_VALUE),
+    }
+  }
+
+  /// Compiling finds the closure registered for the source.
+  pub fn compile_shader(&mut self, shader: gl::GLuint) {
+    let object = match self.shaders.get(&shader) {
+      Some(object) => object,
+      None => return self.fail(gl::GL_INVALID_VALUE),
+    };
+
+    let lines = code_lines(&object.source);
+    let closure = self
+      .closures
+      .iter()
+      .filter(|(registered, closure)| {
+        closure.stage() == object.stage && is_subsequence(registered, &lines)
+      })
+      .max_by_key(|(registered, _)| registered.len())
+      .map(|(_, closure)| closure.clone());
+
+    let object = self.shaders.get_mut(&shader).unwrap();
+    object.log = match closure {
+      Some(_) => String::new(),
+      None => "no software shader is registered for this source".to_string(),
+    };
+    object.closure = closure;
+  }
+
+  pub fn get_shaderiv(&mut self, shader: gl::GLuint, pname: gl::GLenum) -> gl::GLint {
+    let object = match self.shaders.get(&shader) {
+      Some(object) => object,
+      None => {
+        self.fail(gl::GL_INVALID_VALUE);
+        return 0;
+      }
+    };
+    match pname {
+      gl::GL_COMPILE_STATUS => object.closure.is_some() as gl::GLint,
+      gl::GL_INFO_LOG_LENGTH => log_length(&object.log),
+      _ => {
+        self.fail(gl::GL_INVALID_ENUM);
+        0
+      }
+    }
+  }
+
+  pub fn get_shader_info_log(
+    &mut self,
+    shader: gl::GLuint,
+    max_length: gl::GLsizei,
+  ) -> Option<String> {
+    match self.shaders.get(&shader) {
+      Some(object) => Some(truncate(&object.log, max_length)),
+      None => {
+        self.fail(gl::GL_INVALID_VALUE);
+        None
+      }
+    }
+  }
+
+  pub fn delete_shader(&mut self, shader: gl::GLuint) {
+    // Attached shaders only go away with their program
+    let attached = self
+      .programs
+      .values()
+      .any(|program| program.shaders.contains(&shader));
+    if !attached {
+      self.shaders.remove(&shader);
+    }
+  }
+
+  pub fn is_shader(&mut self, shader: gl::GLuint) -> bool {
+    self.shaders.contains_key(&shader)
+  }
+
+  pub fn create_program(&mut self) -> gl::GLuint {
+    let id = self.names(1)[0];
+    self.programs.insert(id, ProgramObject::default());
+    id
+  }
+
+  pub fn attach_shader(&mut self, program: gl::GLuint, shader: gl::GLuint) {
+    if !self.shaders.contains_key(&shader) {
+      return self.fail(gl::GL_INVALID_VALUE);
+    }
+    match self.programs.get_mut(&program) {
+      Some(program) => program.shaders.push(shader),
+      None => self.fail(gl::GL_INVALID_VALUE),
+    }
+  }
+
+  /// Linking takes the attributes, uniforms and `#define`s from the shaders' declarations, in
+  /// order. All of them count as active, even those a GLSL compiler would drop.
+  pub fn link_program(&mut self, program: gl::GLuint) {
+    let shaders = match self.programs.get(&program) {
+      Some(object) => object.shaders.clone(),
+      None => return self.fail(gl::GL_INVALID_VALUE),
+    };
+
+    let mut vertex = None;
+    let mut fragment = None;
+    let mut declarations = Declarations::default();
+    for shader in shaders.iter().filter_map(|shader| self.shaders.get(shader)) {
+      match &shader.closure {
+        Some(Closure::Vertex(closure)) => vertex = Some(closure.clone()),
+        Some(Closure::Fragment(closure)) => fragment = Some(closure.clone()),
+        None => {}
+      }
+      declarations.merge(declare(&shader.source));
+    }
+
+    let object = self.programs.get_mut(&program).unwrap();
+    match (vertex, fragment) {
+      (Some(vertex), Some(fragment)) => {
+        let values = declarations
+          .uniforms
+          .iter()
+          .map(|uniform| vec![0.0; components(uniform.type_) * uniform.size])
+          .collect();
+        object.linked = Some(Linked {
+          vertex,
+          fragment,
+          attributes: declarations.attributes,
+          uniforms: declarations.uniforms,
+          values,
+          defines: declarations.defines,
+        });
+        object.log = String::new();
+      }
+      _ => {
+        object.linked = None;
+        object.log = "a compiled vertex and fragment shader are needed".to_string();
+      }
+    }
+  }
+
+  pub fn get_programiv(&mut self, program: gl::GLuint, pname: gl::GLenum) -> gl::GLint {
+    let object = match self.programs.get(&program) {
+      Some(object) => object,
+      None => {
+        self.fail(gl::GL_INVALID_VALUE);
+        return 0;
+      }
+    };
+    let attributes = object
+      .linked
+      .as_ref()
+      .map_or(&[][..], |l| &l.attributes[..]);
+    let uniforms = object.linked.as_ref().map_or(&[][..], |l| &l.uniforms[..]);
+    let max_length = |declarations: &[Declaration]| {
+      declarations
+        .iter()
+        .map(|declaration| active_name(declaration).len() as gl::GLint + 1)
+        .max()
+        .unwrap_or(0)
+    };
+    match pname {
+      gl::GL_LINK_STATUS => object.linked.is_some() as gl::GLint,
+      gl::GL_INFO_LOG_LENGTH => log_length(&object.log),
+      gl::GL_ACTIVE_ATTRIBUTES => attributes.len() as gl::GLint,
+      gl::GL_ACTIVE_ATTRIBUTE_MAX_LENGTH => max_length(attributes),
+      gl::GL_ACTIVE_UNIFORMS => uniforms.len() as gl::GLint,
+      gl::GL_ACTIVE_UNIFORM_MAX_LENGTH => max_length(uniforms),
+      _ => {
+        self.fail(gl::GL_INVALID_ENUM);
+        0
+      }
+    }
+  }
+
+  pub fn get_program_info_log(
+    &mut self,
+    program: gl::GLuint,
+    max_length: gl::GLsizei,
+  ) -> Option<String> {
+    match self.programs.get(&program) {
+      Some(object) => Some(truncate(&object.log, max_length)),
+      None => {
+        self.fail(gl::GL_INVALID_VALUE);
+        None
+      }
+    }
+  }
+
+  pub fn get_active_attrib(
+    &mut self,
+    program: gl::GLuint,
+    index: gl::GLuint,
+  ) -> Option<(String, gl::GLint, gl::GLenum)> {
+    self.active(program, index, |linked| &linked.attributes)
+  }
+
+  pub fn get_active_uniform(
+    &mut self,
+    program: gl::GLuint,
+    index: gl::GLuint,
+  ) -> Option<(String, gl::GLint, gl::GLenum)> {
+    self.active(program, index, |linked| &linked.uniforms)
+  }
+
+  fn active(
+    &mut self,
+    program: gl::GLuint,
+    index: gl::GLuint,
+    declarations: fn(&Linked) -> &Vec<Declaration>,
+  ) -> Option<(String, gl::GLint, gl::GLenum)> {
+    let linked = self.programs.get(&program).and_then(|p| p.linked.as_ref());
+    let declaration = linked.and_then(|linked| declarations(linked).get(index as usize));
+    match declaration {
+      Some(declaration) => Some((
+        active_name(declaration),
+        declaration.size as gl::GLint,
+        declaration.type_,
+      )),
+      None => {
+        self.fail(gl::GL_INVALID_VALUE);
+        None
+      }
+    }
+  }
+
+  pub fn get_attrib_location(&mut self, program: gl::GLuint, name: &str) -> gl::GLint {
+    self.location(program, name, |linked| &linked.attributes)
+  }
+
+  pub fn get_uniform_location(&mut self, program: gl::GLuint, name: &str) -> gl::GLint {
+    self.location(program, name, |linked| &linked.uniforms)
+  }
+
+  fn location(
+    &mut self,
+    program: gl::GLuint,
+    name: &str,
+    declarations: fn(&Linked) -> &Vec<Declaration>,
+  ) -> gl::GLint {
+    match self.programs.get(&program).map(|p| p.linked.as_ref()) {
+      Some(Some(linked)) => declarations(linked)
+        .iter()
+        .position(|declaration| declaration.name == name)
+        .map_or(-1, |location| location as gl::GLint),
+      Some(None) => {
+        self.fail(gl::GL_INVALID_OPERATION);
+        -1
+      }
+      None => {
+        self.fail(gl::GL_INVALID_VALUE);
+        -1
+      }
+    }
+  }
+
+  pub fn use_program(&mut self, program: gl::GLuint) {
+    match self.programs.get(&program) {
+      _ if program == 0 => self.program = 0,
+      Some(object) if object.linked.is_some() => self.program = program,
+      Some(_) => self.fail(gl::GL_INVALID_OPERATION),
+      None => self.fail(gl::GL_INVALID_VALUE),
+    }
+  }
+
+  pub fn delete_program(&mut self, program: gl::GLuint) {
+    if let Some(object) = self.programs.remove(&program) {
+      for shader in object.shaders {
+        self.shaders.remove(&shader);
+      }
+    }
+    if self.program == program {
+      self.program = 0;
+    }
+  }
+
+  pub fn is_program(&mut self, program: gl::GLuint) -> bool {
+    self.programs.contains_key(&program)
+  }
+
+  // Uniforms -----------------------------------------------------------------
+
+  /// Sets the uniform at `location` of the current program, which must have `values.len()`
+  /// components. Location -1 is ignored, as in GL.
+  fn set_uniform(&mut self, location: gl::GLint, values: &[f32]) {
+    if location == -1 {
+      return;
+    }
+    let linked = self
+      .programs
+      .get_mut(&self.program)
+      .and_then(|program| program.linked.as_mut());
+    let uniform = linked.and_then(|linked| linked.values.get_mut(location as usize));
+    match uniform {
+      Some(uniform) if uniform.len() == values.len() => uniform.copy_from_slice(values),
+      _ => self.fail(gl::GL_INVALID_OPERATION),
+    }
+  }
+
+  pub fn uniform1i(&mut self, location: gl::GLint, x: gl::GLint) {
+    self.set_uniform(location, &[x as f32]);
+  }
+
+  pub fn uniform2f(&mut self, location: gl::GLint, x: gl::GLfloat, y: gl::GLfloat) {
+    self.set_uniform(location, &[x, y]);
+  }
+
+  pub fn uniform4f(
+    &mut self,
+    location: gl::GLint,
+    x: gl::GLfloat,
+    y: gl::GLfloat,
+    z: gl::GLfloat,
+    w: gl::GLfloat,
+  ) {
+    self.set_uniform(location, &[x, y, z, w]);
+  }
+
+  pub fn uniform_matrix4fv(&mut self, location: gl::GLint, transpose: bool, value: &[gl::GLfloat]) {
+    // GLES 2 has no transposing
+    if transpose {
+      return self.fail(gl::GL_INVALID_VALUE);
+    }
+    self.set_uniform(location, value);
+  }
+
+  // Drawing ------------------------------------------------------------------
+
+  fn array(&mut self, index: gl::GLuint) -> &mut AttributeArray {
+    let index = index as usize;
+    if self.arrays.len() <= index {
+      let disabled = AttributeArray {
+        enabled: false,
+        buffer: 0,
+        size: 4,
+        stride: 0,
+        offset: 0,
+      };
+      self.arrays.resize(index + 1, disabled);
+    }
+    &mut self.arrays[index]
+  }
+
+  pub fn enable_vertex_attrib_array(&mut self, index: gl::GLuint) {
+    self.array(index).enabled = true;
+  }
+
+  /// Only `GL_FLOAT` arrays.
+  pub fn vertex_attrib_pointer_offset(
+    &mut self,
+    index: gl::GLuint,
+    size: gl::GLint,
+    type_: gl::GLenum,
+    _normalized: bool,
+    stride: gl::GLsizei,
+    offset: gl::GLuint,
+  ) {
+    if type_ != gl::GL_FLOAT {
+      return self.fail(gl::GL_INVALID_ENUM);
+    }
+    if !(1..=4).contains(&size) || stride < 0 {
+      return self.fail(gl::GL_INVALID_VALUE);
+    }
+    let buffer = self.array_buffer;
+    let array = self.array(index);
+    array.buffer = buffer;
+    array.size = size as usize;
+    array.stride = stride as usize;
+    array.offset = offset as usize;
+  }
+
+  /// The attributes of vertex `vertex`, by location.
+  fn fetch(&self, attributes: usize, vertex: usize) -> Vec<[f32; 4]> {
+    (0..attributes)
+      .map(|location| {
+        let mut value = [0.0, 0.0, 0.0, 1.0];
+        let array = match self.arrays.get(location) {
+          Some(array) if array.enabled => array,
+          _ => return value,
+        };
+        let stride = if array.stride == 0 {
+          array.size * 4
+        } else {
+          array.stride
+        };
+        let start = array.offset + vertex * stride;
+        if let Some(bytes) = self
+          .buffers
+          .get(&array.buffer)
+          .and_then(|b| b.get(start..start + array.size * 4))
+        {
+          for (component, bytes) in value.iter_mut().zip(bytes.chunks(4)) {
+            *component = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+          }
+        }
+        value
+      })
+      .collect()
+  }
+
+  pub fn draw_arrays(&mut self, mode: gl::GLenum, first: gl::GLint, count: gl::GLsizei) {
+    if first < 0 || count < 0 {
+      return self.fail(gl::GL_INVALID_VALUE);
+    }
+    let count = count as usize;
+    let triangles: Vec<[usize; 3]> = match mode {
+      gl::GL_TRIANGLES => (0..count / 3)
+        .map(|t| [3 * t, 3 * t + 1, 3 * t + 2])
+        .collect(),
+      gl::GL_TRIANGLE_FAN => (1..count.saturating_sub(1))
+        .map(|i| [0, i, i + 1])
+        .collect(),
+      _ => return self.fail(gl::GL_INVALID_ENUM),
+    };
+    let has_program = matches!(self.programs.get(&self.program), Some(p) if p.linked.is_some());
+    if !has_program {
+      return self.fail(gl::GL_INVALID_OPERATION);
+    }
+    let target = match self.target() {
+      Some(target) => target,
+      None => return,
+    };
+
+    // Out of the way while drawing, so the rest of the state can be read
+    let mut image = mem::take(self.image_mut(target).unwrap());
+    {
+      let linked = self.programs[&self.program].linked.as_ref().unwrap();
+      let vertices: Vec<VertexOutput> = (first as usize..first as usize + count)
+        .map(|vertex| {
+          let attributes = self.fetch(linked.attributes.len(), vertex);
+          (linked.vertex)(&Vertex {
+            attributes: &attributes,
+            program: linked,
+          })
+        })
+        .collect();
+      let texture = self.textures.get(&self.texture);
+      for triangle in triangles {
+        let triangle = [
+          &vertices[triangle[0]],
+          &vertices[triangle[1]],
+          &vertices[triangle[2]],
+        ];
+        self.rasterize(&mut image, linked, texture, triangle);
+      }
+    }
+    *self.image_mut(target).unwrap() = image;
+  }
+
+  fn rasterize(
+    &self,
+    image: &mut Image,
+    linked: &Linked,
+    texture: Option<&Image>,
+    triangle: [&VertexOutput; 3],
+  ) {
+    // Without clipping, triangles reaching behind the eye are dropped whole
+    if triangle.iter().any(|vertex| vertex.position[3] <= 0.0) {
+      return;
+    }
+    let [x, y, width, height] = self.viewport;
+    let window: Vec<[f32; 2]> = triangle
+      .iter()
+      .map(|vertex| {
+        let [px, py, _, w] = vertex.position;
+        [
+          x as f32 + (px / w + 1.0) * width as f32 / 2.0,
+          y as f32 + (py / w + 1.0) * height as f32 / 2.0,
+        ]
+      })
+      .collect();
+    let edge = |a: [f32; 2], b: [f32; 2], p: [f32; 2]| {
+      (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+    };
+    let area = edge(window[0], window[1], window[2]);
+    if area == 0.0 {
+      return;
+    }
+
+    // The triangle's bounds, within the viewport and the image
+    let bound = |axis: usize, low: i32, size: i32, pixels: usize| {
+      let min = window.iter().map(|p| p[axis]).fold(f32::INFINITY, f32::min);
+      let max = window
+        .iter()
+        .map(|p| p[axis])
+        .fold(f32::NEG_INFINITY, f32::max);
+      let start = (min.floor() as i32).max(low).max(0);
+      let end = (max.ceil() as i32).min(low + size).min(pixels as i32);
+      start..end.max(start)
+    };
+    let rows = bound(1, y, height, image.height);
+    let columns = bound(0, x, width, image.width);
+
+    let varyings = triangle.iter().map(|v| v.varyings.len()).min().unwrap_or(0);
+    let mut interpolated = vec![0.0; varyings];
+    for row in rows {
+      for column in columns.clone() {
+        let p = [column as f32 + 0.5, row as f32 + 0.5];
+        let weights = [
+          edge(window[1], window[2], p) / area,
+          edge(window[2], window[0], p) / area,
+          edge(window[0], window[1], p) / area,
+        ];
+        if weights.iter().any(|&weight| weight < 0.0) {
+          continue;
+        }
+
+        // Perspective correct, through 1/w
+        let weights: Vec<f32> = (0..3)
+          .map(|i| weights[i] / triangle[i].position[3])
+          .collect();
+        let total: f32 = weights.iter().sum();
+        for (i, value) in interpolated.iter_mut().enumerate() {
+          *value = (0..3)
+            .map(|v| weights[v] * triangle[v].varyings[i])
+            .sum::<f32>()
+            / total;
+        }
+
+        let color = (linked.fragment)(&Fragment {
+          frag_coord: p,
+          varyings: &interpolated,
+          program: linked,
+          texture,
+        });
+        image.store(column as usize, row as usize, color);
+      }
+    }
+  }
+}
+
+// ----------------------------------------------------------------------------
+
+/// The lines of `source` that are GLSL rather than preprocessor directives, trimmed.
+fn code_lines(source: &str) -> Vec<String> {
+  source
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .map(str::to_string)
+    .collect()
+}
+
+fn is_subsequence(needle: &[String], haystack: &[String]) -> bool {
+  let mut haystack = haystack.iter();
+  needle
+    .iter()
+    .all(|line| haystack.any(|other| other == line))
+}
+
+impl Declarations {
+  fn merge(&mut self, other: Declarations) {
+    for attribute in other.attributes {
+      if !self.attributes.iter().any(|a| a.name == attribute.name) {
+        self.attributes.push(attribute);
+      }
+    }
+    for uniform in other.uniforms {
+      if !self.uniforms.iter().any(|u| u.name == uniform.name) {
+        self.uniforms.push(uniform);
+      }
+    }
+    self.defines.extend(other.defines);
+  }
+}
+
+/// The `attribute` and `uniform` declarations and `#define`s of a shader.
+fn declare(source: &str) -> Declarations {
+  let mut declarations = Declarations::default();
+  let mut code = String::new();
+  for line in strip_comments(source).lines() {
+    let line = line.trim();
+    match line.strip_prefix("#define") {
+      Some(define) => {
+        let define = define.trim();
+        let (name, value) = define
+          .split_once(char::is_whitespace)
+          .unwrap_or((define, ""));
+        declarations
+          .defines
+          .insert(name.to_string(), value.trim().to_string());
+      }
+      None if line.starts_with('#') => {}
+      None => {
+        code.push_str(line);
+        code.push('\n');
+      }
+    }
+  }
+
+  for statement in code.split(&[';', '{', '}'][..]) {
+    let mut words = statement.split_whitespace();
+    let list = match words.next() {
+      Some("attribute") => &mut declarations.attributes,
+      Some("uniform") => &mut declarations.uniforms,
+      _ => continue,
+    };
+    let mut words = words.skip_while(|word| matches!(*word, "lowp" | "mediump" | "highp"));
+    let type_ = match words.next().and_then(glsl_type) {
+      Some(type_) => type_,
+      None => continue,
+    };
+    for name in words.collect::<Vec<_>>().join("").split(',') {
+      let (name, size) = match name.split_once('[') {
+        Some((name, size)) => (name, size.trim_end_matches(']').parse().unwrap_or(1)),
+        None => (name, 1),
+      };
+      list.push(Declaration {
+        name: name.to_string(),
+        type_,
+        size,
+      });
+    }
+  }
+  declarations
+}
+
+fn strip_comments(source: &str) -> String {
+  let mut stripped = String::with_capacity(source.len());
+  let mut rest = source;
+  while !rest.is_empty() {
+    if let Some(after) = rest.strip_prefix("//") {
+      rest = after.find('\n').map_or("", |end| &after[end..]);
+    } else if let Some(after) = rest.strip_prefix("/*") {
+      let end = after.find("*/").map_or(after.len(), |end| end + 2);
+      // Keep the line breaks, for the directives that follow
+      stripped.extend(after[..end].chars().filter(|&c| c == '\n'));
+      rest = &after[end..];
+    } else {
+      let c = rest.chars().next().unwrap();
+      stripped.push(c);
+      rest = &rest[c.len_utf8()..];
+    }
+  }
+  stripped
+}
+
+/// How many floats a uniform of this type holds.
+fn components(type_: gl::GLenum) -> usize {
+  match type_ {
+    gl::GL_FLOAT_VEC2 | gl::GL_INT_VEC2 => 2,
+    gl::GL_FLOAT_VEC3 | gl::GL_INT_VEC3 => 3,
+    gl::GL_FLOAT_VEC4 | gl::GL_INT_VEC4 | gl::GL_FLOAT_MAT2 => 4,
+    gl::GL_FLOAT_MAT3 => 9,
+    gl::GL_FLOAT_MAT4 => 16,
+    _ => 1,
+  }
+}
+
+/// Arrays are reported as `name[0]`, as GL does.
+fn active_name(declaration: &Declaration) -> String {
+  if declaration.size > 1 {
+    format!("{}[0]", declaration.name)
+  } else {
+    declaration.name.clone()
+  }
+}
+
+fn log_length(log: &str) -> gl::GLint {
+  if log.is_empty() {
+    0
+  } else {
+    log.len() as gl::GLint + 1
+  }
+}
+
+fn truncate(log: &str, max_length: gl::GLsizei) -> String {
+  log.chars().take((max_length - 1).max(0) as usize).collect()
+}
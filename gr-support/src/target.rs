@@ -0,0 +1,31 @@
+use gr_context::Context;
+
+/// Something to draw to: the Pi's display, or an offscreen surface.
+///
+/// Creating one makes its GL context current, so it has to outlive every GL object created
+/// through the [`GlContext`](crate::GlContext) wrapping it.
+pub trait RenderTarget {
+  fn width(&self) -> u32;
+  fn height(&self) -> u32;
+  /// The `(major, minor)` version of the EGL implementation.
+  fn egl_version(&self) -> (i32, i32);
+  fn swap_buffers(&mut self);
+}
+
+impl RenderTarget for Context {
+  fn width(&self) -> u32 {
+    Context::width(self)
+  }
+
+  fn height(&self) -> u32 {
+    Context::height(self)
+  }
+
+  fn egl_version(&self) -> (i32, i32) {
+    Context::egl_version(self)
+  }
+
+  fn swap_buffers(&mut self) {
+    Context::swap_buffers(self)
+  }
+}
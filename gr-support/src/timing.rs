@@ -0,0 +1,262 @@
+//! Where the time of each frame goes.
+//!
+//! ```ignore
+//! let mut timings = Timings::new(60);
+//! loop {
+//!   timings.start_frame();
+//!   timings.time("draw", || draw(&context))?;
+//!   timings.time("swap", || context.swap_buffers());
+//! }
+//! println!("{}", timings.report());
+//! ```
+//!
+//! GL calls mostly queue work for the GPU, so a stage drawing measures how long the CPU took to
+//! submit it. The GPU catches up in `swap_buffers`, which also waits for vsync.
+//!
+//! Timings run for as long as the program does, so only the last [`WINDOW`] durations of each
+//! row are kept for its percentiles. The counts, averages and extremes cover the whole run.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+use std::time::{Duration, Instant};
+
+/// How many of the latest durations percentiles are taken over, a minute at 60 fps.
+pub const WINDOW: usize = 3600;
+
+/// Collects the time each frame and each stage of it takes.
+pub struct Timings {
+  fps: u32,
+  frame_start: Option<Instant>,
+  missed: u64,
+  frames: Series,
+  stages: Vec<(&'static str, Series)>,
+}
+
+impl Timings {
+  /// Frames are meant to take `1 / fps` seconds; longer ones count as missed.
+  pub fn new(fps: u32) -> Timings {
+    assert!(fps > 0, "frame timings need a rate");
+    Timings {
+      fps,
+      frame_start: None,
+      missed: 0,
+      frames: Series::default(),
+      stages: Vec::new(),
+    }
+  }
+
+  /// Starts a frame, ending the previous one. Frames run from one start to the next.
+  pub fn start_frame(&mut self) {
+    let now = Instant::now();
+    if let Some(start) = self.frame_start {
+      self.add_frame(now - start);
+    }
+    self.frame_start = Some(now);
+  }
+
+  /// Runs `f` and adds the time it took to `stage`.
+  pub fn time<T, F: FnOnce() -> T>(&mut self, stage: &'static str, f: F) -> T {
+    let start = Instant::now();
+    let result = f();
+    self.add(stage, start.elapsed());
+    result
+  }
+
+  /// Adds `duration` to `stage`. Stages are reported in the order they were first seen.
+  pub fn add(&mut self, stage: &'static str, duration: Duration) {
+    match self.stages.iter_mut().find(|(name, _)| *name == stage) {
+      Some((_, series)) => series.push(duration),
+      None => {
+        let mut series = Series::default();
+        series.push(duration);
+        self.stages.push((stage, series));
+      }
+    }
+  }
+
+  /// Adds a whole frame of `duration`, for frames timed elsewhere.
+  pub fn add_frame(&mut self, duration: Duration) {
+    let period = Duration::from_secs(1) / self.fps;
+    // A frame taking 2.5 periods kept two from being shown
+    let periods = (duration.as_secs_f64() / period.as_secs_f64()).round() as u64;
+    self.missed += periods.saturating_sub(1);
+    self.frames.push(duration);
+  }
+
+  /// Summarizes the frames so far.
+  pub fn report(&self) -> Report {
+    Report {
+      fps: self.fps,
+      frames: self.frames.count,
+      missed: self.missed,
+      frame: self.frames.summary(),
+      stages: self
+        .stages
+        .iter()
+        .map(|(name, series)| (name.to_string(), series.summary()))
+        .collect(),
+    }
+  }
+}
+
+/// Running totals of a row of durations, and the last [`WINDOW`] of them.
+#[derive(Default)]
+struct Series {
+  count: u64,
+  total: Duration,
+  min: Duration,
+  max: Duration,
+  recent: VecDeque<Duration>,
+}
+
+impl Series {
+  fn push(&mut self, duration: Duration) {
+    self.min = if self.count == 0 {
+      duration
+    } else {
+      self.min.min(duration)
+    };
+    self.max = self.max.max(duration);
+    self.count += 1;
+    self.total += duration;
+    if self.recent.len() == WINDOW {
+      self.recent.pop_front();
+    }
+    self.recent.push_back(duration);
+  }
+
+  fn summary(&self) -> Summary {
+    if self.count == 0 {
+      return Summary::default();
+    }
+    let recent = Summary::new(&self.recent.iter().copied().collect::<Vec<_>>());
+    Summary {
+      min: self.min,
+      avg: Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64),
+      p95: recent.p95,
+      p99: recent.p99,
+      max: self.max,
+    }
+  }
+}
+
+/// The spread of a set of durations. In a [`Report`], the percentiles are of the last [`WINDOW`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Summary {
+  pub min: Duration,
+  pub avg: Duration,
+  pub p95: Duration,
+  pub p99: Duration,
+  pub max: Duration,
+}
+
+impl Summary {
+  /// All zero for no durations.
+  pub fn new(durations: &[Duration]) -> Summary {
+    if durations.is_empty() {
+      return Summary::default();
+    }
+    let mut sorted = durations.to_vec();
+    sorted.sort();
+    // Nearest rank
+    let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+    Summary {
+      min: sorted[0],
+      avg: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+      p95: percentile(95),
+      p99: percentile(99),
+      max: sorted[sorted.len() - 1],
+    }
+  }
+
+  fn values(&self) -> [(&'static str, Duration); 5] {
+    [
+      ("min", self.min),
+      ("avg", self.avg),
+      ("p95", self.p95),
+      ("p99", self.p99),
+      ("max", self.max),
+    ]
+  }
+}
+
+/// What [`Timings::report`] found. `Display` prints a table in milliseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+  pub fps: u32,
+  pub frames: u64,
+  /// Frames not shown at the target rate because others took too long.
+  pub missed: u64,
+  pub frame: Summary,
+  pub stages: Vec<(String, Summary)>,
+}
+
+fn millis(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1000.0
+}
+
+impl Report {
+  /// The report as a single line of JSON, with durations in milliseconds:
+  ///
+  /// ```text
+  /// {"fps":60,"frames":600,"missed":2,
+  ///  "frame":{"min":16.1,"avg":16.7,"p95":17.2,"p99":33.4,"max":34.0},
+  ///  "stages":{"draw":{...},"swap":{...}}}
+  /// ```
+  pub fn to_json(&self) -> String {
+    fn summary(json: &mut String, summary: &Summary) {
+      json.push('{');
+      for (i, (name, value)) in summary.values().iter().enumerate() {
+        if i > 0 {
+          json.push(',');
+        }
+        write!(json, "\"{}\":{:.3}", name, millis(*value)).unwrap();
+      }
+      json.push('}');
+    }
+
+    let mut json = format!(
+      "{{\"fps\":{},\"frames\":{},\"missed\":{},\"frame\":",
+      self.fps, self.frames, self.missed
+    );
+    summary(&mut json, &self.frame);
+    json.push_str(",\"stages\":{");
+    for (i, (name, stage)) in self.stages.iter().enumerate() {
+      if i > 0 {
+        json.push(',');
+      }
+      // Stage names are identifiers in the code, nothing to escape
+      write!(json, "\"{}\":", name).unwrap();
+      summary(&mut json, stage);
+    }
+    json.push_str("}}");
+    json
+  }
+}
+
+impl fmt::Display for Report {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(
+      f,
+      "{} frames, {} missed at {} fps",
+      self.frames, self.missed, self.fps
+    )?;
+    write!(f, "{:8}", "ms")?;
+    for (name, _) in self.frame.values().iter() {
+      write!(f, "{:>9}", name)?;
+    }
+    let rows = Some(("frame", &self.frame)).into_iter().chain(
+      self
+        .stages
+        .iter()
+        .map(|(name, stage)| (name.as_str(), stage)),
+    );
+    for (name, summary) in rows {
+      write!(f, "\n{:8}", name)?;
+      for (_, value) in summary.values().iter() {
+        write!(f, "{:>9.2}", millis(*value))?;
+      }
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,409 @@
+//! Offline checks of GLSL ES 1.00 shaders, so a broken shader fails `cargo test` on any Linux
+//! box instead of at startup on a Pi.
+//!
+//! Sources are parsed with the `glsl` crate, then checked against the parts of the ES 1.00 spec
+//! that desktop GLSL is relaxed about: storage qualifiers, types, fragment shader float
+//! precision, and varyings and uniforms agreeing across both stages. It does not type check
+//! expressions; the driver still has the last word.
+
+use crate::ShaderStage;
+use glsl::parser::Parse;
+use glsl::syntax::{
+  Declaration, ExternalDeclaration, FunctionParameterDeclaration, FunctionPrototype,
+  InitDeclaratorList, Preprocessor, StorageQualifier, TranslationUnit, TypeQualifier,
+  TypeQualifierSpec, TypeSpecifier, TypeSpecifierNonArray,
+};
+use glsl::transpiler::glsl::{show_interpolation_qualifier, show_storage_qualifier};
+use glsl::visitor::{Host, Visit, Visitor};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+  /// The source is not valid GLSL at all.
+  Parse { stage: ShaderStage, message: String },
+  /// `#version` is not 100.
+  Version { stage: ShaderStage, version: u16 },
+  /// A qualifier, type or declaration that GLSL ES 1.00 does not have, e.g. `in`, `layout`,
+  /// `uint` or an interface block.
+  Unsupported {
+    stage: ShaderStage,
+    name: String,
+    feature: String,
+  },
+  /// `attribute` in a fragment shader.
+  FragmentAttribute { name: String },
+  /// A float type in a fragment shader with neither a precision qualifier nor a
+  /// `precision ... float;` default, which ES 1.00 fragment shaders do not have.
+  MissingPrecision { name: String },
+  /// The fragment shader reads a varying that the vertex shader never declares.
+  UndeclaredVarying { name: String },
+  /// A varying or uniform declared with a different type in each stage.
+  Mismatch {
+    name: String,
+    vertex: String,
+    fragment: String,
+  },
+}
+
+impl fmt::Display for ValidationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ValidationError::Parse { stage, message } => {
+        write!(f, "{} does not parse: {}", stage, message.trim_end())
+      }
+      ValidationError::Version { stage, version } => write!(
+        f,
+        "{} asks for #version {}, GLES 2 only has 100",
+        stage, version
+      ),
+      ValidationError::Unsupported {
+        stage,
+        name,
+        feature,
+      } => write!(
+        f,
+        "{} declares {} with `{}`, which GLSL ES 1.00 does not have",
+        stage, name, feature
+      ),
+      ValidationError::FragmentAttribute { name } => {
+        write!(f, "fragment shader declares attribute {}", name)
+      }
+      ValidationError::MissingPrecision { name } => write!(
+        f,
+        "fragment shader declares {} without a precision and has no default float precision",
+        name
+      ),
+      ValidationError::UndeclaredVarying { name } => write!(
+        f,
+        "fragment shader reads varying {}, which the vertex shader does not declare",
+        name
+      ),
+      ValidationError::Mismatch {
+        name,
+        vertex,
+        fragment,
+      } => write!(
+        f,
+        "{} is a {} in the vertex shader but a {} in the fragment shader",
+        name, vertex, fragment
+      ),
+    }
+  }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Checks one shader on its own.
+pub fn validate_shader(stage: ShaderStage, source: &str) -> Result<(), Vec<ValidationError>> {
+  let mut errors = Vec::new();
+  check_shader(stage, source, &mut errors);
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(errors)
+  }
+}
+
+/// Checks both shaders, and that they would link: every varying the fragment shader declares
+/// must come out of the vertex shader with the same type, and shared uniforms must agree.
+pub fn validate_program(vertex: &str, fragment: &str) -> Result<(), Vec<ValidationError>> {
+  let mut errors = Vec::new();
+  let vertex = check_shader(ShaderStage::Vertex, vertex, &mut errors);
+  let fragment = check_shader(ShaderStage::Fragment, fragment, &mut errors);
+
+  if let (Some(vertex), Some(fragment)) = (vertex, fragment) {
+    for varying in &fragment.varyings {
+      match vertex.varyings.iter().find(|v| v.name == varying.name) {
+        Some(declared) if declared.type_ != varying.type_ => {
+          errors.push(mismatch(declared, varying))
+        }
+        Some(_) => {}
+        None => errors.push(ValidationError::UndeclaredVarying {
+          name: varying.name.clone(),
+        }),
+      }
+    }
+
+    for uniform in &fragment.uniforms {
+      if let Some(declared) = vertex.uniforms.iter().find(|u| u.name == uniform.name) {
+        if declared.type_ != uniform.type_ {
+          errors.push(mismatch(declared, uniform));
+        }
+      }
+    }
+  }
+
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(errors)
+  }
+}
+
+// ----------------------------------------------------------------------------
+
+struct Variable {
+  name: String,
+  type_: TypeSpecifier,
+}
+
+#[derive(Default)]
+struct Interface {
+  uniforms: Vec<Variable>,
+  varyings: Vec<Variable>,
+}
+
+fn mismatch(vertex: &Variable, fragment: &Variable) -> ValidationError {
+  ValidationError::Mismatch {
+    name: vertex.name.clone(),
+    vertex: type_name(&vertex.type_),
+    fragment: type_name(&fragment.type_),
+  }
+}
+
+fn type_name(type_: &TypeSpecifier) -> String {
+  let mut name = String::new();
+  glsl::transpiler::glsl::show_type_specifier(&mut name, type_);
+  name
+}
+
+/// Returns the stage's uniforms and varyings, or `None` when it does not parse.
+fn check_shader(
+  stage: ShaderStage,
+  source: &str,
+  errors: &mut Vec<ValidationError>,
+) -> Option<Interface> {
+  let unit = match TranslationUnit::parse(source) {
+    Ok(unit) => unit,
+    Err(err) => {
+      errors.push(ValidationError::Parse {
+        stage,
+        message: err.info,
+      });
+      return None;
+    }
+  };
+
+  let mut interface = Interface::default();
+  let mut default_float_precision = false;
+
+  for declaration in &unit.0 {
+    match declaration {
+      ExternalDeclaration::Preprocessor(Preprocessor::Version(version))
+        if version.version != 100 =>
+      {
+        errors.push(ValidationError::Version {
+          stage,
+          version: version.version,
+        });
+      }
+      ExternalDeclaration::Declaration(Declaration::Precision(_, type_))
+        if type_.ty == TypeSpecifierNonArray::Float =>
+      {
+        default_float_precision = true;
+      }
+      ExternalDeclaration::Declaration(Declaration::Block(block)) => {
+        errors.push(ValidationError::Unsupported {
+          stage,
+          name: block.name.as_str().to_string(),
+          feature: "interface block".to_string(),
+        });
+      }
+      ExternalDeclaration::Declaration(Declaration::InitDeclaratorList(list)) => {
+        let head = &list.head;
+        let names = head
+          .name
+          .iter()
+          .chain(list.tail.iter().map(|tail| &tail.ident.ident));
+        for name in names {
+          check_global(
+            stage,
+            name.as_str(),
+            head.ty.qualifier.as_ref(),
+            &head.ty.ty,
+            &mut interface,
+            errors,
+          );
+        }
+      }
+      _ => {}
+    }
+  }
+
+  let mut checker = Checker {
+    stage,
+    default_float_precision,
+    errors,
+  };
+  unit.visit(&mut checker);
+
+  Some(interface)
+}
+
+/// Checks the qualifiers of a global declaration and records it if it crosses stages.
+fn check_global(
+  stage: ShaderStage,
+  name: &str,
+  qualifier: Option<&TypeQualifier>,
+  type_: &TypeSpecifier,
+  interface: &mut Interface,
+  errors: &mut Vec<ValidationError>,
+) {
+  let qualifier = match qualifier {
+    Some(qualifier) => qualifier,
+    None => return,
+  };
+
+  let unsupported = |feature: String| ValidationError::Unsupported {
+    stage,
+    name: name.to_string(),
+    feature,
+  };
+
+  for spec in &qualifier.qualifiers {
+    match spec {
+      TypeQualifierSpec::Storage(StorageQualifier::Const) => {}
+      TypeQualifierSpec::Storage(StorageQualifier::Uniform) => interface.uniforms.push(Variable {
+        name: name.to_string(),
+        type_: type_.clone(),
+      }),
+      TypeQualifierSpec::Storage(StorageQualifier::Varying) => interface.varyings.push(Variable {
+        name: name.to_string(),
+        type_: type_.clone(),
+      }),
+      TypeQualifierSpec::Storage(StorageQualifier::Attribute) => {
+        if stage == ShaderStage::Fragment {
+          errors.push(ValidationError::FragmentAttribute {
+            name: name.to_string(),
+          });
+        }
+      }
+      TypeQualifierSpec::Storage(storage) => {
+        let mut feature = String::new();
+        show_storage_qualifier(&mut feature, storage);
+        errors.push(unsupported(feature));
+      }
+      TypeQualifierSpec::Interpolation(interpolation) => {
+        let mut feature = String::new();
+        show_interpolation_qualifier(&mut feature, interpolation);
+        errors.push(unsupported(feature));
+      }
+      TypeQualifierSpec::Layout(_) => errors.push(unsupported("layout".to_string())),
+      TypeQualifierSpec::Precise => errors.push(unsupported("precise".to_string())),
+      TypeQualifierSpec::Precision(_) | TypeQualifierSpec::Invariant => {}
+    }
+  }
+}
+
+fn has_precision(qualifier: Option<&TypeQualifier>) -> bool {
+  qualifier.is_some_and(|qualifier| {
+    qualifier
+      .qualifiers
+      .0
+      .iter()
+      .any(|spec| matches!(spec, TypeQualifierSpec::Precision(_)))
+  })
+}
+
+fn is_float(type_: &TypeSpecifierNonArray) -> bool {
+  use TypeSpecifierNonArray::*;
+  matches!(type_, Float | Vec2 | Vec3 | Vec4 | Mat2 | Mat3 | Mat4)
+}
+
+/// Types in the GLSL ES 1.00 spec, section 4.1.
+fn is_es100(type_: &TypeSpecifierNonArray) -> bool {
+  use TypeSpecifierNonArray::*;
+  matches!(
+    type_,
+    Void
+      | Bool
+      | Int
+      | Float
+      | Vec2
+      | Vec3
+      | Vec4
+      | BVec2
+      | BVec3
+      | BVec4
+      | IVec2
+      | IVec3
+      | IVec4
+      | Mat2
+      | Mat3
+      | Mat4
+      | Sampler2D
+      | SamplerCube
+      | Struct(_)
+      | TypeName(_)
+  )
+}
+
+/// Checks the type of every declaration, global or local, function parameters included.
+struct Checker<'a> {
+  stage: ShaderStage,
+  default_float_precision: bool,
+  errors: &'a mut Vec<ValidationError>,
+}
+
+impl Checker<'_> {
+  fn check(&mut self, name: &str, qualifier: Option<&TypeQualifier>, type_: &TypeSpecifier) {
+    if !is_es100(&type_.ty) {
+      let mut feature = String::new();
+      glsl::transpiler::glsl::show_type_specifier_non_array(&mut feature, &type_.ty);
+      self.errors.push(ValidationError::Unsupported {
+        stage: self.stage,
+        name: name.to_string(),
+        feature,
+      });
+    }
+
+    if self.stage == ShaderStage::Fragment
+      && !self.default_float_precision
+      && is_float(&type_.ty)
+      && !has_precision(qualifier)
+    {
+      self.errors.push(ValidationError::MissingPrecision {
+        name: name.to_string(),
+      });
+    }
+  }
+}
+
+impl Visitor for Checker<'_> {
+  fn visit_init_declarator_list(&mut self, list: &InitDeclaratorList) -> Visit {
+    // `uniform vec2 a, b;` declares `b` in the tail, with the head's type
+    let head = &list.head;
+    let names = head
+      .name
+      .iter()
+      .chain(list.tail.iter().map(|tail| &tail.ident.ident));
+    for name in names {
+      self.check(name.as_str(), head.ty.qualifier.as_ref(), &head.ty.ty);
+    }
+    Visit::Children
+  }
+
+  fn visit_function_prototype(&mut self, prototype: &FunctionPrototype) -> Visit {
+    self.check(
+      prototype.name.as_str(),
+      prototype.ty.qualifier.as_ref(),
+      &prototype.ty.ty,
+    );
+
+    for parameter in &prototype.parameters {
+      match parameter {
+        FunctionParameterDeclaration::Named(qualifier, declarator) => self.check(
+          declarator.ident.ident.as_str(),
+          qualifier.as_ref(),
+          &declarator.ty,
+        ),
+        // `void main(void)`
+        FunctionParameterDeclaration::Unnamed(qualifier, type_) => {
+          self.check(prototype.name.as_str(), qualifier.as_ref(), type_)
+        }
+      }
+    }
+
+    Visit::Children
+  }
+}
@@ -0,0 +1,39 @@
+//! Command line value parsers.
+
+use gr_support::args::{point, seconds, size};
+use std::time::Duration;
+
+#[test]
+fn parses_seconds() {
+  assert_eq!(seconds("10"), Ok(Duration::from_secs(10)));
+  assert_eq!(seconds("2.5"), Ok(Duration::from_millis(2500)));
+  assert_eq!(seconds("0"), Ok(Duration::ZERO));
+  for bad in &["-1", "inf", "NaN", "ten", ""] {
+    assert!(seconds(bad).is_err(), "{}", bad);
+  }
+}
+
+#[test]
+fn parses_points() {
+  assert_eq!(point("800,400"), Ok((800.0, 400.0)));
+  assert_eq!(point("-0.5, 1e2"), Ok((-0.5, 100.0)));
+  assert_eq!(
+    point("800x400"),
+    Err("expected X,Y, not \"800x400\"".to_string())
+  );
+  for bad in &["800", "800,", ",400", "1,2,3", "inf,0"] {
+    assert!(point(bad).is_err(), "{}", bad);
+  }
+}
+
+#[test]
+fn parses_sizes() {
+  assert_eq!(size("640x480"), Ok((640, 480)));
+  assert_eq!(
+    size("640,480"),
+    Err("expected WIDTHxHEIGHT, not \"640,480\"".to_string())
+  );
+  for bad in &["640", "0x480", "640x", "x480", "-640x480", "640x480x2", ""] {
+    assert!(size(bad).is_err(), "{}", bad);
+  }
+}
@@ -0,0 +1,135 @@
+use gr_support::bindings::{
+  Attribute, BindingError, BindingKind, Mat4, Sampler2D, Uniform, Vec2, Vec3, Vec4,
+};
+use gr_support::soft::{Soft, VertexOutput};
+use gr_support::{Error, GlContext, ShaderBindings, ShaderProgram};
+use opengles::glesv2 as gl;
+
+const VERTEX: &str = "
+attribute vec3 vertexPosition;
+attribute vec4 vertexColor;
+uniform mat4 projectionMatrix;
+uniform vec2 centre;
+varying vec4 vColor;
+
+void main() {
+  gl_Position = projectionMatrix * vec4(vertexPosition.xy - centre, vertexPosition.z, 1.0);
+  vColor = vertexColor;
+}
+";
+
+const FRAGMENT: &str = "
+uniform sampler2D tex;
+varying mediump vec4 vColor;
+
+void main() {
+  gl_FragColor = texture2D(tex, gl_FragCoord.xy) * vColor;
+}
+";
+
+fn context() -> GlContext {
+  GlContext::with_target(
+    Soft::new(1, 1)
+      .vertex_shader(VERTEX, |_| VertexOutput {
+        position: [0.0, 0.0, 0.0, 1.0],
+        varyings: vec![0.0; 4],
+      })
+      .fragment_shader(FRAGMENT, |_| [0.0; 4]),
+  )
+}
+
+fn program(context: &GlContext) -> ShaderProgram<'_> {
+  ShaderProgram::builder()
+    .vertex(VERTEX)
+    .fragment(FRAGMENT)
+    .build(context)
+    .expect("linked")
+}
+
+#[derive(ShaderBindings)]
+#[shader(rename_all = "camelCase")]
+struct Triangle {
+  vertex_position: Attribute<Vec3>,
+  vertex_color: Attribute<Vec4>,
+  projection_matrix: Uniform<Mat4>,
+  #[shader(name = "tex")]
+  texture: Uniform<Sampler2D>,
+  centre: Option<Uniform<Vec2>>,
+  scale: Option<Uniform<Vec2>>,
+}
+
+#[test]
+fn binds_every_field() {
+  let context = context();
+  let program = program(&context);
+  let attribute = |name| program.attribute(name).expect(name).location as gl::GLuint;
+  let uniform = |name| program.uniform(name).expect(name).location;
+
+  let triangle: Triangle = program.bind().expect("bound");
+  assert_eq!(
+    triangle.vertex_position.location,
+    attribute("vertexPosition")
+  );
+  assert_eq!(triangle.vertex_color.location, attribute("vertexColor"));
+  assert_eq!(
+    triangle.projection_matrix.location,
+    uniform("projectionMatrix")
+  );
+  assert_eq!(triangle.texture.location, uniform("tex"));
+  assert_eq!(
+    triangle.centre.map(|centre| centre.location),
+    Some(uniform("centre"))
+  );
+  assert!(triangle.scale.is_none());
+}
+
+// Never built, binding it must fail
+#[allow(dead_code)]
+#[derive(ShaderBindings)]
+struct Broken {
+  #[shader(name = "vertexPosition")]
+  position: Attribute<Vec4>,
+  offset: Uniform<Vec2>,
+  #[shader(name = "tex")]
+  texture: Option<Uniform<Vec2>>,
+}
+
+#[test]
+fn reports_every_missing_or_mistyped_field() {
+  let context = context();
+  let errors = match program(&context).bind::<Broken>() {
+    Err(Error::Bindings(errors)) => errors,
+    Err(err) => panic!("unexpected error {}", err),
+    Ok(_) => panic!("bound a broken struct"),
+  };
+
+  assert_eq!(
+    errors,
+    vec![
+      BindingError::Mistyped {
+        kind: BindingKind::Attribute,
+        name: "vertexPosition".to_string(),
+        expected: gl::GL_FLOAT_VEC4,
+        found: gl::GL_FLOAT_VEC3,
+      },
+      BindingError::Missing {
+        kind: BindingKind::Uniform,
+        name: "offset".to_string(),
+      },
+      BindingError::Mistyped {
+        kind: BindingKind::Uniform,
+        name: "tex".to_string(),
+        expected: gl::GL_FLOAT_VEC2,
+        found: gl::GL_SAMPLER_2D,
+      },
+    ]
+  );
+
+  assert_eq!(
+    Error::Bindings(errors).to_string(),
+    "shader bindings failed:\n  \
+     attribute vertexPosition is a vec3, expected a vec4\n  \
+     uniform offset is not active\n  \
+     uniform tex is a sampler2D, expected a vec2"
+  );
+}
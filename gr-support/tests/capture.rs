@@ -0,0 +1,73 @@
+//! Screenshots of the software rasterizer's frames.
+
+mod common;
+
+use common::scratch_dir;
+use gr_support::capture::Capture;
+use gr_support::soft::Soft;
+use gr_support::{gl, GlContext};
+use std::fs;
+
+fn clear(red: f32) {
+  gl::clear_color(red, 0.0, 0.0, 1.0);
+  gl::clear(gl::GL_COLOR_BUFFER_BIT);
+}
+
+#[test]
+fn saves_every_nth_frame_as_ppm() {
+  let dir = scratch_dir("capture-every");
+  let context = GlContext::with_target(Soft::new(2, 1));
+  let mut capture = Capture::new(dir.join("frame-{frame}.ppm").to_str().unwrap()).every(2);
+
+  let mut saved = Vec::new();
+  for frame in 0..5 {
+    clear(frame as f32 / 4.0);
+    saved.push(capture.frame(&context).unwrap());
+  }
+
+  assert_eq!(
+    saved,
+    vec![
+      None,
+      Some(dir.join("frame-000002.ppm")),
+      None,
+      Some(dir.join("frame-000004.ppm")),
+      None
+    ]
+  );
+  let mut expected = b"P6\n2 1\n255\n".to_vec();
+  expected.extend_from_slice(&[64, 0, 0, 64, 0, 0]);
+  assert_eq!(fs::read(dir.join("frame-000002.ppm")).unwrap(), expected);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn saves_the_next_frame_on_sigusr1() {
+  let dir = scratch_dir("capture-signal");
+  let context = GlContext::with_target(Soft::new(1, 1));
+  let mut capture = Capture::new(dir.join("{frame}.ppm").to_str().unwrap()).on_request();
+
+  clear(1.0);
+  assert_eq!(capture.frame(&context).unwrap(), None);
+  unsafe { libc::raise(libc::SIGUSR1) };
+  assert_eq!(
+    capture.frame(&context).unwrap(),
+    Some(dir.join("000002.ppm"))
+  );
+  assert_eq!(capture.frame(&context).unwrap(), None);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn refuses_unknown_formats() {
+  let dir = scratch_dir("capture-format");
+  let context = GlContext::with_target(Soft::new(1, 1));
+  let mut capture = Capture::new(dir.join("{frame}.bmp").to_str().unwrap()).every(1);
+
+  clear(1.0);
+  assert!(capture.frame(&context).is_err());
+
+  fs::remove_dir_all(&dir).unwrap();
+}
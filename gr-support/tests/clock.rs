@@ -0,0 +1,110 @@
+//! Frame pacing, on a virtual clock.
+
+use gr_support::clock::{FrameClock, VirtualClock};
+use std::time::Duration;
+
+fn millis(millis: f64) -> Duration {
+  Duration::from_secs_f64(millis / 1000.0)
+}
+
+#[test]
+fn frames_are_due_at_exact_multiples_of_the_period() {
+  let time = VirtualClock::new();
+  let mut clock = FrameClock::with_clock(60, time.clone());
+
+  for frame in 0..600 {
+    let tick = clock.tick();
+    assert_eq!(tick.frame, frame);
+    assert_eq!(tick.time, Duration::from_nanos(frame * 1_000_000_000 / 60));
+    // Work taking some of the frame does not push the next one back
+    time.advance(Duration::from_millis(5));
+    clock.wait();
+  }
+  assert_eq!(clock.tick().time, Duration::from_secs(10));
+  assert_eq!(clock.missed(), 0);
+}
+
+#[test]
+fn late_frames_skip_to_the_next_deadline() {
+  let time = VirtualClock::new();
+  let mut clock = FrameClock::with_clock(50, time.clone());
+
+  clock.tick();
+  time.advance(Duration::from_millis(45));
+  clock.wait();
+  assert_eq!(clock.missed(), 1);
+  let tick = clock.tick();
+  assert_eq!(tick.frame, 3);
+  assert_eq!(tick.time, Duration::from_millis(60));
+  assert_eq!(tick.delta, Duration::from_millis(60));
+
+  clock.wait();
+  assert_eq!(clock.tick().time, Duration::from_millis(80));
+  assert_eq!(clock.missed(), 1);
+}
+
+#[test]
+fn tracks_frame_times() {
+  let time = VirtualClock::new();
+  let mut clock = FrameClock::with_clock(100, time.clone());
+  assert_eq!(clock.average_frame_time(), Duration::ZERO);
+
+  for _ in 0..200 {
+    clock.tick();
+    clock.wait();
+  }
+  clock.tick();
+  time.advance(Duration::from_millis(25));
+  clock.wait();
+  clock.tick();
+
+  let frame_times: Vec<Duration> = clock.frame_times().collect();
+  assert_eq!(frame_times.len(), 120);
+  assert_eq!(frame_times[0], Duration::from_millis(10));
+  assert_eq!(frame_times[119], Duration::from_millis(30));
+  assert_eq!(
+    clock.average_frame_time(),
+    (Duration::from_millis(10) * 119 + Duration::from_millis(30)) / 120
+  );
+}
+
+#[test]
+fn fixed_timesteps_carry_the_remainder_over() {
+  let time = VirtualClock::new();
+  let mut clock = FrameClock::with_clock(40, time).fixed_timestep(Duration::from_millis(10));
+
+  let tick = clock.tick();
+  assert_eq!((tick.updates, tick.alpha), (0, 0.0));
+
+  // 25 ms a frame: 2 updates and a half, then 3 once the halves add up
+  let mut updates = Vec::new();
+  let mut alphas = Vec::new();
+  for _ in 0..4 {
+    clock.wait();
+    let tick = clock.tick();
+    updates.push(tick.updates);
+    alphas.push(tick.alpha);
+  }
+  assert_eq!(updates, [2, 3, 2, 3]);
+  for (alpha, expected) in alphas.iter().zip(&[0.5, 0.0, 0.5, 0.0]) {
+    assert!((alpha - expected).abs() < 1e-6, "{:?}", alphas);
+  }
+}
+
+#[test]
+fn fixed_timesteps_do_not_snowball() {
+  let time = VirtualClock::new();
+  let mut clock =
+    FrameClock::with_clock(60, time.clone()).fixed_timestep(Duration::from_millis(10));
+
+  clock.tick();
+  time.advance(Duration::from_secs(1));
+  clock.wait();
+  let tick = clock.tick();
+  assert_eq!((tick.updates, tick.alpha), (5, 1.0));
+
+  clock.wait();
+  let tick = clock.tick();
+  assert!(tick.delta < millis(17.0));
+  assert!(tick.updates <= 2, "{:?}", tick);
+}
@@ -0,0 +1,20 @@
+//! Helpers shared by the tests.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+/// An empty directory named after the crate, `name` and the process, for a test to write in.
+/// Tests running at the same time need different names. The path is canonical, as a symlink
+/// resolved in it would be.
+pub fn scratch_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!(
+    "{}-{}-{}",
+    env!("CARGO_PKG_NAME"),
+    name,
+    process::id()
+  ));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).expect("scratch dir");
+  fs::canonicalize(&dir).expect("scratch dir")
+}
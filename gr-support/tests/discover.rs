@@ -0,0 +1,94 @@
+//! Finding device nodes, in a directory laid out as `/dev/input`.
+
+mod common;
+
+use common::scratch_dir;
+use gr_support::input::discover::{self, Found};
+use gr_support::input::Class;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn numbers_event_nodes() {
+  assert_eq!(
+    discover::event_number(Path::new("/dev/input/event12")),
+    Some(12)
+  );
+  assert_eq!(discover::event_number(Path::new("/dev/input/mouse0")), None);
+  assert_eq!(discover::event_number(Path::new("/dev/input/events")), None);
+}
+
+#[test]
+fn lists_event_nodes_in_order_then_mice() {
+  let dir = scratch_dir("discover");
+  for name in &["event10", "event2", "mouse0", "js0", "mice"] {
+    fs::write(dir.join(name), "").unwrap();
+  }
+  fs::create_dir(dir.join("by-id")).unwrap();
+  symlink(
+    "../event10",
+    dir.join("by-id/usb-Logitech_USB_Optical_Mouse-event-mouse"),
+  )
+  .unwrap();
+
+  assert_eq!(
+    discover::nodes(&dir).unwrap(),
+    [dir.join("event2"), dir.join("event10"), dir.join("mice")]
+  );
+  assert_eq!(
+    discover::links(&dir),
+    [(
+      dir.join("by-id/usb-Logitech_USB_Optical_Mouse-event-mouse"),
+      dir.join("event10")
+    )]
+  );
+
+  // Plain files are not evdev nodes, and are left out, but `mice` needs no checking
+  let found = discover::scan(&dir).unwrap();
+  assert_eq!(
+    found,
+    [Found {
+      path: dir.join("mice"),
+      link: None,
+      name: discover::PS2_NAME.to_string(),
+      classes: vec![Class::Pointer],
+    }]
+  );
+  assert!(found[0].is(Class::Pointer) && !found[0].is(Class::Keyboard));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn finds_nothing_without_links() {
+  let dir = scratch_dir("discover-empty");
+  assert_eq!(discover::nodes(&dir).unwrap(), Vec::<PathBuf>::new());
+  assert_eq!(discover::links(&dir), []);
+  assert!(discover::nodes(dir.join("missing")).is_err());
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+fn found(name: &str, classes: &[Class]) -> Found {
+  Found {
+    path: Path::new(discover::INPUT_DIR).join(name),
+    link: None,
+    name: name.to_string(),
+    classes: classes.to_vec(),
+  }
+}
+
+#[test]
+fn reads_mice_without_an_evdev_pointer_or_touchscreen() {
+  let mice = found("mice", &[Class::Pointer]);
+  let keyboard = found("event0", &[Class::Keyboard]);
+  assert!(discover::reads_mice(&[]));
+  assert!(discover::reads_mice(&[keyboard.clone(), mice.clone()]));
+
+  let mouse = found("event1", &[Class::Pointer]);
+  assert!(!discover::reads_mice(&[keyboard, mouse, mice.clone()]));
+
+  // A kiosk with nothing but a touchscreen, whose touches `mice` would report as clicks
+  let touchscreen = found("event2", &[Class::Touchscreen]);
+  assert!(!discover::reads_mice(&[touchscreen, mice]));
+}
@@ -0,0 +1,41 @@
+use gr_support::GlError;
+use opengles::glesv2 as gl;
+
+const ERRORS: [gl::GLenum; 5] = [
+  gl::GL_INVALID_ENUM,
+  gl::GL_INVALID_VALUE,
+  gl::GL_INVALID_OPERATION,
+  gl::GL_INVALID_FRAMEBUFFER_OPERATION,
+  gl::GL_OUT_OF_MEMORY,
+];
+
+#[test]
+fn every_gles2_error_round_trips() {
+  for &code in ERRORS.iter() {
+    let error = GlError::from_code(code).expect("an error");
+    assert_eq!(error.code(), code);
+    assert!(error.name().starts_with("GL_"), "{}", error.name());
+    assert!(!error.description().is_empty());
+  }
+}
+
+#[test]
+fn no_error_is_not_an_error() {
+  assert_eq!(GlError::from_code(gl::GL_NO_ERROR), None);
+}
+
+#[test]
+fn unknown_codes_are_kept() {
+  let error = GlError::from_code(0x1234).expect("an error");
+  assert_eq!(error, GlError::Unknown(0x1234));
+  assert_eq!(error.code(), 0x1234);
+}
+
+#[test]
+fn display_shows_name_and_code() {
+  assert_eq!(
+    GlError::InvalidOperation.to_string(),
+    "GL_INVALID_OPERATION (0x0502)"
+  );
+  assert_eq!(GlError::OutOfMemory.to_string(), "GL_OUT_OF_MEMORY (0x0505)");
+}
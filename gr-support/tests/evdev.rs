@@ -0,0 +1,295 @@
+//! Decoding evdev byte streams, laid out as a 64-bit and a 32-bit kernel write them, and telling
+//! devices apart by what they report.
+
+use gr_support::input::evdev::{Capabilities, Decoder, Layout, RawEvent};
+use gr_support::input::{Axis, Button, Class, Event, Key, KeyState, TouchPhase};
+use std::time::Duration;
+
+const EV_SYN: u16 = 0;
+const EV_KEY: u16 = 1;
+const EV_REL: u16 = 2;
+const EV_ABS: u16 = 3;
+const EV_MSC: u16 = 4;
+
+/// `struct input_event`s, all at 12.5 s.
+fn stream(layout: Layout, events: &[(u16, u16, i32)]) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  for &(kind, code, value) in events {
+    match layout {
+      Layout::Long32 => {
+        bytes.extend_from_slice(&12u32.to_ne_bytes());
+        bytes.extend_from_slice(&500_000u32.to_ne_bytes());
+      }
+      Layout::Long64 => {
+        bytes.extend_from_slice(&12u64.to_ne_bytes());
+        bytes.extend_from_slice(&500_000u64.to_ne_bytes());
+      }
+    }
+    bytes.extend_from_slice(&kind.to_ne_bytes());
+    bytes.extend_from_slice(&code.to_ne_bytes());
+    bytes.extend_from_slice(&value.to_ne_bytes());
+  }
+  bytes
+}
+
+fn decode(layout: Layout, events: &[(u16, u16, i32)]) -> Vec<Event> {
+  let mut decoded = Vec::new();
+  Decoder::with_layout(layout).feed(&stream(layout, events), &mut decoded);
+  decoded
+}
+
+#[test]
+fn parses_both_layouts() {
+  for &layout in &[Layout::Long32, Layout::Long64] {
+    let bytes = stream(layout, &[(EV_REL, 1, -3)]);
+    assert_eq!(bytes.len(), layout.size());
+    assert_eq!(
+      layout.parse(&bytes),
+      RawEvent {
+        time: Duration::from_millis(12_500),
+        kind: EV_REL,
+        code: 1,
+        value: -3,
+      }
+    );
+  }
+}
+
+#[test]
+fn decodes_a_mouse_report() {
+  // A USB mouse moving, clicking and scrolling in one report, with the scan code and the high
+  // resolution wheel it also sends
+  let events = decode(
+    Layout::Long64,
+    &[
+      (EV_MSC, 4, 0x90001),
+      (EV_KEY, 0x110, 1),
+      (EV_REL, 0, 5),
+      (EV_REL, 1, -2),
+      (EV_REL, 8, 1),
+      (EV_REL, 0x0b, 120),
+      (EV_SYN, 0, 0),
+      (EV_KEY, 0x110, 0),
+      (EV_SYN, 0, 0),
+    ],
+  );
+  assert_eq!(
+    events,
+    [
+      Event::Button {
+        button: Button::Left,
+        pressed: true
+      },
+      Event::Motion { dx: 5, dy: -2 },
+      Event::Wheel {
+        vertical: 1,
+        horizontal: 0
+      },
+      Event::Button {
+        button: Button::Left,
+        pressed: false
+      },
+    ]
+  );
+}
+
+#[test]
+fn decodes_keys_and_absolute_axes() {
+  let events = decode(
+    Layout::Long32,
+    &[
+      (EV_KEY, 1, 1),
+      (EV_SYN, 0, 0),
+      (EV_KEY, 1, 2),
+      (EV_SYN, 0, 0),
+      (EV_KEY, 1, 0),
+      (EV_KEY, 0x14a, 1),
+      (EV_ABS, 0, 400),
+      (EV_ABS, 1, 240),
+      (EV_SYN, 0, 0),
+    ],
+  );
+  assert_eq!(
+    events,
+    [
+      Event::Key {
+        key: Key::ESC,
+        state: KeyState::Pressed
+      },
+      Event::Key {
+        key: Key::ESC,
+        state: KeyState::Repeated
+      },
+      Event::Key {
+        key: Key::ESC,
+        state: KeyState::Released
+      },
+      Event::Button {
+        button: Button::Touch,
+        pressed: true
+      },
+      Event::Absolute {
+        axis: Axis::X,
+        value: 400
+      },
+      Event::Absolute {
+        axis: Axis::Y,
+        value: 240
+      },
+    ]
+  );
+}
+
+#[test]
+fn waits_for_the_end_of_a_report() {
+  let bytes = stream(
+    Layout::Long64,
+    &[(EV_REL, 0, 1), (EV_REL, 0, 2), (EV_SYN, 0, 0)],
+  );
+  let mut decoder = Decoder::with_layout(Layout::Long64);
+  let mut events = Vec::new();
+  // Reads that stop anywhere, even inside an event
+  for chunk in bytes.chunks(7) {
+    assert_eq!(events, []);
+    decoder.feed(chunk, &mut events);
+  }
+  assert_eq!(events, [Event::Motion { dx: 3, dy: 0 }]);
+}
+
+#[test]
+fn drops_reports_the_kernel_dropped_events_from() {
+  let events = decode(
+    Layout::Long64,
+    &[
+      (EV_REL, 0, 1),
+      (EV_SYN, 3, 0),
+      (EV_REL, 0, 100),
+      (EV_SYN, 0, 0),
+      (EV_REL, 1, 4),
+      (EV_SYN, 0, 0),
+    ],
+  );
+  assert_eq!(events, [Event::Motion { dx: 0, dy: 4 }]);
+}
+
+#[test]
+fn tracks_multi_touch_slots() {
+  const ABS_MT_SLOT: u16 = 0x2f;
+  const ABS_MT_TOUCH_MAJOR: u16 = 0x30;
+  const ABS_MT_POSITION_X: u16 = 0x35;
+  const ABS_MT_POSITION_Y: u16 = 0x36;
+  const ABS_MT_TRACKING_ID: u16 = 0x39;
+  let touch = |slot, phase, x, y| Event::Touch { slot, phase, x, y };
+
+  let events = decode(
+    Layout::Long64,
+    &[
+      // A finger in slot 0, as the Pi touchscreen reports it
+      (EV_ABS, ABS_MT_TRACKING_ID, 7),
+      (EV_ABS, ABS_MT_POSITION_X, 100),
+      (EV_ABS, ABS_MT_POSITION_Y, 200),
+      (EV_ABS, ABS_MT_TOUCH_MAJOR, 5),
+      (EV_KEY, 0x14a, 1),
+      (EV_ABS, 0, 100),
+      (EV_ABS, 1, 200),
+      (EV_SYN, 0, 0),
+      // A second one in slot 1, and the first moving along X only
+      (EV_ABS, ABS_MT_POSITION_X, 110),
+      (EV_ABS, ABS_MT_SLOT, 1),
+      (EV_ABS, ABS_MT_TRACKING_ID, 8),
+      (EV_ABS, ABS_MT_POSITION_X, 300),
+      (EV_ABS, ABS_MT_POSITION_Y, 400),
+      (EV_SYN, 0, 0),
+      // Nothing for the touches
+      (EV_ABS, 0, 110),
+      (EV_SYN, 0, 0),
+      // The first lifted, and put down again at once
+      (EV_ABS, ABS_MT_SLOT, 0),
+      (EV_ABS, ABS_MT_TRACKING_ID, -1),
+      (EV_SYN, 0, 0),
+      (EV_ABS, ABS_MT_TRACKING_ID, 9),
+      (EV_SYN, 0, 0),
+      (EV_ABS, ABS_MT_TRACKING_ID, 10),
+      (EV_ABS, ABS_MT_POSITION_Y, 250),
+      (EV_SYN, 0, 0),
+    ],
+  );
+  assert_eq!(
+    events,
+    [
+      Event::Button {
+        button: Button::Touch,
+        pressed: true
+      },
+      Event::Absolute {
+        axis: Axis::X,
+        value: 100
+      },
+      Event::Absolute {
+        axis: Axis::Y,
+        value: 200
+      },
+      touch(0, TouchPhase::Down, 100, 200),
+      touch(0, TouchPhase::Move, 110, 200),
+      touch(1, TouchPhase::Down, 300, 400),
+      Event::Absolute {
+        axis: Axis::X,
+        value: 110
+      },
+      touch(0, TouchPhase::Up, 110, 200),
+      touch(0, TouchPhase::Down, 110, 200),
+      touch(0, TouchPhase::Up, 110, 250),
+      touch(0, TouchPhase::Down, 110, 250),
+    ]
+  );
+}
+
+/// An `EVIOCGBIT` bitmap with `codes` set.
+fn bitmap(codes: &[u16]) -> Vec<u8> {
+  let mut bitmap = vec![0u8; 96];
+  for &code in codes {
+    bitmap[code as usize / 8] |= 1 << (code % 8);
+  }
+  bitmap
+}
+
+fn classes(capabilities: &Capabilities) -> Vec<Class> {
+  Class::ALL
+    .iter()
+    .copied()
+    .filter(|&class| capabilities.is(class))
+    .collect()
+}
+
+#[test]
+fn classifies_devices_by_capabilities() {
+  // BTN_LEFT, BTN_RIGHT, BTN_MIDDLE; REL_X, REL_Y, REL_WHEEL
+  let mouse = Capabilities::from_bitmaps(&bitmap(&[0x110, 0x111, 0x112]), &[0x03, 0x01], &[]);
+  assert_eq!(classes(&mouse), [Class::Pointer]);
+  assert!(mouse.relative(0x08) && !mouse.relative(0x06));
+
+  let keys: Vec<u16> = (1..128).collect();
+  let keyboard = Capabilities::from_bitmaps(&bitmap(&keys), &[], &[]);
+  assert_eq!(classes(&keyboard), [Class::Keyboard]);
+
+  // KEY_KP0 to KEY_KP9, KEY_KPMINUS, KEY_KPPLUS, KEY_KPDOT
+  let keypad = Capabilities::from_bitmaps(&bitmap(&(71..84).collect::<Vec<u16>>()), &[], &[]);
+  assert_eq!(classes(&keypad), [Class::Keyboard]);
+
+  // KEY_POWER only
+  let power = Capabilities::from_bitmaps(&bitmap(&[116]), &[], &[]);
+  assert_eq!(classes(&power), []);
+
+  // ABS_X, ABS_Y, ABS_MT_SLOT, ABS_MT_POSITION_X, ABS_MT_POSITION_Y
+  let absolute = bitmap(&[0x00, 0x01, 0x2f, 0x35, 0x36]);
+  let touchscreen = Capabilities::from_bitmaps(&bitmap(&[0x14a]), &[], &absolute);
+  assert_eq!(classes(&touchscreen), [Class::Touchscreen]);
+
+  // BTN_LEFT, BTN_TOOL_FINGER, BTN_TOUCH
+  let touchpad = Capabilities::from_bitmaps(&bitmap(&[0x110, 0x145, 0x14a]), &[], &absolute);
+  assert_eq!(classes(&touchpad), [Class::Touchpad]);
+
+  // BTN_TOOL_PEN, BTN_TOOL_FINGER, BTN_TOUCH: a tablet, none of them
+  let tablet = Capabilities::from_bitmaps(&bitmap(&[0x140, 0x145, 0x14a]), &[], &absolute);
+  assert_eq!(classes(&tablet), []);
+}
@@ -0,0 +1,176 @@
+//! Recognising gestures in touch events.
+
+use gr_support::input::gesture::{Gesture, Recogniser};
+use gr_support::input::{Event, TouchPhase};
+use std::f32::consts::FRAC_PI_2;
+use std::time::Duration;
+
+fn touch(slot: u16, phase: TouchPhase, x: i32, y: i32) -> Event {
+  Event::Touch { slot, phase, x, y }
+}
+
+fn ms(ms: u64) -> Duration {
+  Duration::from_millis(ms)
+}
+
+#[test]
+fn taps_when_lifted_soon_without_moving() {
+  let mut gestures = Recogniser::new();
+  assert_eq!(
+    gestures.feed(&[touch(0, TouchPhase::Down, 100, 100)], ms(0)),
+    []
+  );
+  // Within the slop
+  assert_eq!(
+    gestures.feed(&[touch(0, TouchPhase::Move, 105, 103)], ms(50)),
+    []
+  );
+  assert_eq!(
+    gestures.feed(&[touch(0, TouchPhase::Up, 105, 103)], ms(100)),
+    [Gesture::Tap { x: 105.0, y: 103.0 }]
+  );
+
+  // Held too long
+  gestures.feed(&[touch(0, TouchPhase::Down, 100, 100)], ms(1000));
+  assert_eq!(
+    gestures.feed(&[touch(0, TouchPhase::Up, 100, 100)], ms(1500)),
+    []
+  );
+
+  // With another finger down meanwhile
+  gestures.feed(&[touch(0, TouchPhase::Down, 100, 100)], ms(2000));
+  gestures.feed(&[touch(1, TouchPhase::Down, 200, 100)], ms(2010));
+  gestures.feed(&[touch(1, TouchPhase::Up, 200, 100)], ms(2020));
+  assert_eq!(
+    gestures.feed(&[touch(0, TouchPhase::Up, 100, 100)], ms(2030)),
+    []
+  );
+  assert_eq!(gestures.fingers(), 0);
+}
+
+#[test]
+fn drags_past_the_slop() {
+  let mut gestures = Recogniser::new().slop(5.0);
+  gestures.feed(&[touch(0, TouchPhase::Down, 100, 100)], ms(0));
+  assert_eq!(
+    gestures.feed(&[touch(0, TouchPhase::Move, 103, 100)], ms(10)),
+    []
+  );
+  assert_eq!(
+    gestures.feed(&[touch(0, TouchPhase::Move, 110, 100)], ms(20)),
+    [Gesture::Drag {
+      fingers: 1,
+      x: 110.0,
+      y: 100.0,
+      dx: 10.0,
+      dy: 0.0
+    }]
+  );
+  assert_eq!(
+    gestures.feed(&[touch(0, TouchPhase::Move, 110, 90)], ms(30)),
+    [Gesture::Drag {
+      fingers: 1,
+      x: 110.0,
+      y: 90.0,
+      dx: 0.0,
+      dy: -10.0
+    }]
+  );
+  // Not a tap, however soon it is lifted
+  assert_eq!(
+    gestures.feed(&[touch(0, TouchPhase::Up, 110, 90)], ms(40)),
+    []
+  );
+}
+
+#[test]
+fn pinches_and_rotates_with_two_fingers() {
+  let mut gestures = Recogniser::new();
+  gestures.feed(
+    &[
+      touch(0, TouchPhase::Down, 100, 100),
+      touch(1, TouchPhase::Down, 200, 100),
+    ],
+    ms(0),
+  );
+
+  // Apart, about the middle
+  assert_eq!(
+    gestures.feed(&[touch(1, TouchPhase::Move, 300, 100)], ms(10)),
+    [
+      Gesture::Drag {
+        fingers: 2,
+        x: 200.0,
+        y: 100.0,
+        dx: 50.0,
+        dy: 0.0
+      },
+      Gesture::Pinch {
+        x: 200.0,
+        y: 100.0,
+        scale: 2.0
+      },
+    ]
+  );
+
+  // A quarter turn about the first finger, clockwise on screen
+  let turned = gestures.feed(&[touch(1, TouchPhase::Move, 100, 300)], ms(20));
+  match turned[..] {
+    [Gesture::Drag { .. }, Gesture::Rotate { angle, .. }] => {
+      assert!((angle - FRAC_PI_2).abs() < 1e-6, "{}", angle)
+    }
+    _ => panic!("{:?}", turned),
+  }
+
+  // The finger left drags on from where it is, without jumping
+  gestures.feed(&[touch(0, TouchPhase::Up, 100, 100)], ms(30));
+  assert_eq!(
+    gestures.feed(&[touch(1, TouchPhase::Move, 101, 300)], ms(40)),
+    [Gesture::Drag {
+      fingers: 1,
+      x: 101.0,
+      y: 300.0,
+      dx: 1.0,
+      dy: 0.0
+    }]
+  );
+}
+
+#[test]
+fn moves_reported_together_are_one_gesture() {
+  let mut gestures = Recogniser::new();
+  gestures.feed(
+    &[
+      touch(0, TouchPhase::Down, 100, 100),
+      touch(1, TouchPhase::Down, 200, 100),
+    ],
+    ms(0),
+  );
+
+  // Side by side, reported one finger at a time
+  assert_eq!(
+    gestures.feed(
+      &[
+        touch(0, TouchPhase::Move, 110, 100),
+        touch(1, TouchPhase::Move, 210, 100),
+      ],
+      ms(10),
+    ),
+    [Gesture::Drag {
+      fingers: 2,
+      x: 160.0,
+      y: 100.0,
+      dx: 10.0,
+      dy: 0.0
+    }]
+  );
+}
+
+#[test]
+fn skips_other_events() {
+  let mut gestures = Recogniser::new();
+  assert_eq!(gestures.feed(&[Event::Motion { dx: 1, dy: 1 }], ms(0)), []);
+  // A move for a finger it missed going down counts as going down
+  gestures.feed(&[touch(3, TouchPhase::Move, 10, 10)], ms(0));
+  assert_eq!(gestures.fingers(), 1);
+}
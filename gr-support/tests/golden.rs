@@ -0,0 +1,85 @@
+//! Reference images, without a GPU.
+
+#![cfg(feature = "golden")]
+
+mod common;
+
+use common::scratch_dir;
+use gr_support::golden::{Golden, Mismatch};
+use gr_support::image::Image;
+use gr_support::soft::Soft;
+use gr_support::{gl, GlContext};
+use std::env;
+use std::fs;
+
+/// A 2x2 image, `[r, g]` in each pixel.
+fn image(colors: [[u8; 2]; 4]) -> Image {
+  let pixels = colors.iter().flat_map(|[r, g]| [*r, *g, 0, 255]).collect();
+  Image::new(2, 2, pixels)
+}
+
+#[test]
+fn reads_the_framebuffer() {
+  let context = GlContext::with_target(Soft::new(2, 2));
+  gl::clear_color(1.0, 0.0, 0.0, 1.0);
+  gl::clear(gl::GL_COLOR_BUFFER_BIT);
+
+  assert_eq!(
+    Image::read(&context).unwrap(),
+    image([[255, 0], [255, 0], [255, 0], [255, 0]])
+  );
+}
+
+#[test]
+fn flips_rows() {
+  let mut flipped = image([[1, 2], [3, 4], [5, 6], [7, 8]]);
+  flipped.flip();
+  assert_eq!(flipped, image([[5, 6], [7, 8], [1, 2], [3, 4]]));
+}
+
+#[test]
+fn diffs_beyond_the_tolerance() {
+  let expected = image([[0, 0], [100, 0], [0, 40], [0, 0]]);
+  let actual = image([[2, 0], [100, 0], [0, 50], [0, 0]]);
+
+  assert!(expected.diff(&actual, 10).is_none());
+  let diff = expected.diff(&actual, 2).unwrap();
+  assert_eq!(diff.differing, 1);
+  assert_eq!(diff.max_difference, 10);
+  assert_eq!(diff.image.pixel(0, 1), [255, 0, 0, 255]);
+  assert_eq!(diff.image.pixel(1, 0), [7, 7, 7, 255]);
+}
+
+#[test]
+fn checks_against_saved_references() {
+  // Comparing is what this tests
+  env::remove_var("GR_UPDATE_GOLDEN");
+  let dir = scratch_dir("golden");
+  let golden = Golden::new(dir.join("references"), dir.join("output"));
+  let expected = image([[0, 0], [10, 20], [30, 40], [255, 255]]);
+
+  match golden.check("frame", &expected) {
+    Err(Mismatch::Missing { actual, .. }) => {
+      assert_eq!(Image::load_png(actual).unwrap(), expected)
+    }
+    result => panic!("{:?}", result),
+  }
+
+  fs::create_dir_all(dir.join("references")).unwrap();
+  expected.save_png(golden.reference("frame")).unwrap();
+  golden.check("frame", &expected).unwrap();
+
+  let actual = image([[0, 0], [10, 20], [30, 48], [255, 255]]);
+  match golden.check("frame", &actual) {
+    Err(Mismatch::Pixels {
+      differing: 1,
+      max_difference: 8,
+      diff,
+      ..
+    }) => assert_eq!(Image::load_png(diff).unwrap().pixel(0, 1), [255, 0, 0, 255]),
+    result => panic!("{:?}", result),
+  }
+  golden.tolerance(8).check("frame", &actual).unwrap();
+
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,121 @@
+use gr_support::gpu::{Driver, Gpu, VideoCore};
+use std::path::{Path, PathBuf};
+
+fn fixture(board: &str) -> Gpu {
+  let root: PathBuf = [
+    env!("CARGO_MANIFEST_DIR"),
+    "tests",
+    "fixtures",
+    "gpu",
+    board,
+  ]
+  .iter()
+  .collect();
+  Gpu::detect_in(&root)
+}
+
+#[test]
+fn detects_boards_from_the_device_tree() {
+  let cases = [
+    ("pi3b-firmware", VideoCore::Vc4, Driver::Firmware),
+    ("pi3b-kms", VideoCore::Vc4, Driver::Mesa),
+    ("zero2w", VideoCore::Vc4, Driver::Firmware),
+    ("pi4b", VideoCore::Vc6, Driver::Mesa),
+    ("cm4", VideoCore::Vc6, Driver::Mesa),
+  ];
+  for &(board, videocore, driver) in &cases {
+    let gpu = fixture(board);
+    assert_eq!(gpu.videocore, Some(videocore), "{}", board);
+    assert_eq!(gpu.driver, driver, "{}", board);
+  }
+
+  assert_eq!(
+    fixture("pi4b").model.as_deref(),
+    Some("Raspberry Pi 4 Model B Rev 1.4")
+  );
+}
+
+#[test]
+fn falls_back_to_the_renderer() {
+  let gpu = fixture("desktop");
+  assert_eq!(gpu.model, None);
+  assert_eq!(gpu.videocore, None);
+
+  assert_eq!(
+    fixture("desktop").or_renderer_str("V3D 4.2").videocore,
+    Some(VideoCore::Vc6)
+  );
+  assert_eq!(
+    gpu
+      .or_renderer_str("llvmpipe (LLVM 12.0.0, 128 bits)")
+      .videocore,
+    None
+  );
+
+  // The model wins when it is known
+  assert_eq!(
+    fixture("pi3b-kms").or_renderer_str("V3D 4.2").videocore,
+    Some(VideoCore::Vc4)
+  );
+}
+
+#[test]
+fn parses_models() {
+  let cases = [
+    ("Raspberry Pi Model B Rev 2", Some(VideoCore::Vc4)),
+    ("Raspberry Pi 2 Model B Rev 1.1", Some(VideoCore::Vc4)),
+    ("Raspberry Pi Zero W Rev 1.1", Some(VideoCore::Vc4)),
+    (
+      "Raspberry Pi Compute Module 3 Plus Rev 1.0",
+      Some(VideoCore::Vc4),
+    ),
+    ("Raspberry Pi 400 Rev 1.0", Some(VideoCore::Vc6)),
+    ("Raspberry Pi 5 Model B Rev 1.0", Some(VideoCore::Vc6)),
+    ("Pine64 RockPro64 v2.1", None),
+  ];
+  for &(model, videocore) in &cases {
+    assert_eq!(VideoCore::from_model(model), videocore, "{}", model);
+  }
+}
+
+#[test]
+fn parses_renderers() {
+  let cases = [
+    ("VideoCore IV HW", Some(VideoCore::Vc4)),
+    ("VC4 V3D 2.1", Some(VideoCore::Vc4)),
+    ("V3D 4.2", Some(VideoCore::Vc6)),
+    ("V3D 7.1.7.0", Some(VideoCore::Vc6)),
+    ("Mesa DRI Intel(R) HD Graphics 620", None),
+  ];
+  for &(renderer, videocore) in &cases {
+    assert_eq!(
+      VideoCore::from_renderer(renderer),
+      videocore,
+      "{}",
+      renderer
+    );
+  }
+}
+
+#[test]
+fn explains_driver_mismatches() {
+  let pi3 = Gpu::detect_in(Path::new("/nonexistent"));
+  assert_eq!(pi3.driver, Driver::Firmware);
+  assert_eq!(pi3.mismatch_with(Driver::Firmware), None);
+  assert_eq!(
+    pi3.mismatch_with(Driver::Mesa).as_deref(),
+    Some(
+      "built for the Mesa KMS driver but this machine runs the Broadcom firmware driver, \
+       rebuild with `RUSTFLAGS='-L /opt/vc/lib' cargo build --no-default-features --features=vc4`"
+    )
+  );
+
+  let pi4 = fixture("pi4b");
+  assert_eq!(
+    pi4.mismatch_with(Driver::Firmware).as_deref(),
+    Some(
+      "built for the Broadcom firmware driver but this machine runs the Mesa KMS driver, \
+       rebuild with `cargo build --no-default-features --features=vc6`"
+    )
+  );
+}
@@ -0,0 +1,45 @@
+use gr_support::{EglError, Error, GlContext, Headless, RenderTarget};
+use opengles::glesv2 as gl;
+use std::env;
+
+#[test]
+fn rejects_bad_headless_sizes() {
+  for &size in &["640", "640x", "x480", "0x480", "big"] {
+    env::set_var("GR_HEADLESS", size);
+    match GlContext::from_env() {
+      Err(Error::HeadlessSize(found)) => assert_eq!(found, size),
+      Err(err) => panic!("{}: {}", size, err),
+      Ok(_) => panic!("{}: accepted", size),
+    }
+  }
+  env::remove_var("GR_HEADLESS");
+}
+
+#[test]
+fn egl_errors_are_named() {
+  let err = EglError {
+    function: "eglCreateContext",
+    code: 0x3009,
+  };
+  assert_eq!(
+    err.to_string(),
+    "eglCreateContext failed: EGL_BAD_MATCH (0x3009)"
+  );
+}
+
+/// Needs EGL, e.g. Mesa with `LIBGL_ALWAYS_SOFTWARE=1`.
+#[test]
+#[ignore]
+fn draws_offscreen() {
+  let headless = Headless::new(4, 2).expect("a headless context");
+  assert_eq!((headless.width(), headless.height()), (4, 2));
+  let context = GlContext::with_target(headless);
+
+  gl::clear_color(1.0, 0.0, 0.0, 1.0);
+  gl::clear(gl::GL_COLOR_BUFFER_BIT);
+  context.swap_buffers();
+
+  let mut pixels: Vec<u8> = vec![0; 4 * 2 * 4];
+  gl::read_pixels(0, 0, 4, 2, gl::GL_RGBA, gl::GL_UNSIGNED_BYTE, &mut pixels);
+  assert!(pixels.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+}
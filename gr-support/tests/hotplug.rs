@@ -0,0 +1,39 @@
+#![cfg(feature = "hotplug")]
+
+mod common;
+
+use common::scratch_dir;
+use gr_support::input::hotplug::{Change, Watcher};
+use std::fs;
+use std::os::unix::fs::{symlink, PermissionsExt};
+
+#[test]
+fn sees_nodes_and_links_come_and_go() {
+  let dir = scratch_dir("hotplug");
+  let mut watcher = Watcher::new(&dir).expect("watcher");
+  assert_eq!(watcher.changes().unwrap(), []);
+
+  // Made by the kernel, then given to the input group by udev
+  let node = dir.join("event3");
+  fs::write(&node, "").unwrap();
+  fs::set_permissions(&node, fs::Permissions::from_mode(0o660)).unwrap();
+  assert_eq!(
+    watcher.changes().unwrap(),
+    [Change::Added(node.clone()), Change::Added(node.clone())]
+  );
+
+  // The first link udev makes also makes the directory
+  let link = dir.join("by-id/usb-Logitech_USB_Optical_Mouse-event-mouse");
+  fs::create_dir(dir.join("by-id")).unwrap();
+  symlink("../event3", &link).unwrap();
+  assert_eq!(watcher.changes().unwrap(), [Change::Added(link.clone())]);
+
+  fs::remove_file(&link).unwrap();
+  fs::remove_file(&node).unwrap();
+  assert_eq!(
+    watcher.changes().unwrap(),
+    [Change::Removed(link), Change::Removed(node)]
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
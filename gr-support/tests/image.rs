@@ -0,0 +1,80 @@
+//! Raw dumps, as `glReadPixels` gives them.
+
+mod common;
+
+use common::scratch_dir;
+use gr_support::image::{Image, RawFormat};
+use gr_support::Error;
+use std::fs;
+use std::io;
+
+#[test]
+fn reads_raw_rgba_bottom_row_first() {
+  let raw = [1, 2, 3, 4, 5, 6, 7, 8];
+  let image = Image::from_raw(1, 2, RawFormat::Rgba8, &raw).unwrap();
+  assert_eq!(image.pixel(0, 0), [5, 6, 7, 8]);
+  assert_eq!(image.pixel(0, 1), [1, 2, 3, 4]);
+}
+
+#[test]
+fn widens_rgb565() {
+  // Full red, half green (32 of 63) and no blue, little endian
+  let rgb: u16 = (31 << 11) | (32 << 5);
+  let image = Image::from_raw(1, 1, RawFormat::Rgb565, &rgb.to_le_bytes()).unwrap();
+  assert_eq!(image.pixel(0, 0), [255, 130, 0, 255]);
+}
+
+#[test]
+fn checks_the_size_of_raw_dumps() {
+  let err = Image::from_raw(2, 2, RawFormat::Rgb565, &[0; 16]).unwrap_err();
+  assert_eq!(err.to_string(), "a 2x2 rgb565 dump is 8 bytes, not 16");
+}
+
+#[test]
+fn refuses_sizes_that_overflow() {
+  match Image::from_raw(u32::MAX, u32::MAX, RawFormat::Rgba8, &[]) {
+    Err(Error::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::InvalidInput),
+    result => panic!("{:?}", result),
+  }
+}
+
+#[test]
+#[should_panic(expected = "65536x65536 RGBA pixels")]
+fn sizes_are_not_computed_in_32_bits() {
+  // 65536 * 65536 * 4 is 0 in a u32
+  Image::new(65536, 65536, Vec::new());
+}
+
+#[test]
+fn saves_raw_dumps_as_gl_reads_them() {
+  let dir = scratch_dir("image");
+  let path = dir.join("frame.raw");
+  let image = Image::new(1, 2, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+  image.save(&path).unwrap();
+
+  let raw = fs::read(&path).unwrap();
+  assert_eq!(raw, [5, 6, 7, 8, 1, 2, 3, 4]);
+  assert_eq!(
+    Image::load(&path, Some((1, 2, RawFormat::Rgba8))).unwrap(),
+    image
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn saves_empty_raw_dumps() {
+  let dir = scratch_dir("image-empty");
+  let path = dir.join("empty.raw");
+  Image::new(0, 2, Vec::new()).save(&path).unwrap();
+  assert!(fs::read(&path).unwrap().is_empty());
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn parses_raw_formats() {
+  assert_eq!("rgba".parse(), Ok(RawFormat::Rgba8));
+  assert_eq!("rgb565".parse(), Ok(RawFormat::Rgb565));
+  assert!("bgra".parse::<RawFormat>().is_err());
+}
@@ -0,0 +1,101 @@
+//! Binding keys to actions, and changing the bindings from a file.
+
+use gr_support::input::keymap::{Keymap, KeymapError};
+use gr_support::input::{Event, Key, KeyState};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Action {
+  Quit,
+  Zoom,
+}
+
+impl FromStr for Action {
+  type Err = ();
+
+  fn from_str(name: &str) -> Result<Action, ()> {
+    match name {
+      "quit" => Ok(Action::Quit),
+      "zoom" => Ok(Action::Zoom),
+      _ => Err(()),
+    }
+  }
+}
+
+fn defaults() -> Keymap<Action> {
+  Keymap::new()
+    .bind(Action::Quit, &[Key::ESC, Key::Q])
+    .bind(Action::Zoom, &[Key::KP_PLUS])
+}
+
+#[test]
+fn names_keys() {
+  assert_eq!(Key::from_name("q"), Some(Key::Q));
+  assert_eq!(Key::from_name("Q"), Some(Key::Q));
+  assert_eq!(Key::from_name("z"), Some(Key(44)));
+  assert_eq!(Key::from_name("0"), Some(Key(11)));
+  assert_eq!(Key::from_name("kp0"), Some(Key::KP0));
+  assert_eq!(Key::from_name("KP7"), Some(Key::KP7));
+  assert_eq!(Key::from_name("kpenter"), Some(Key::KP_ENTER));
+  assert_eq!(Key::from_name("f1"), Some(Key(59)));
+  assert_eq!(Key::from_name("f12"), Some(Key(88)));
+  assert_eq!(Key::from_name("f13"), None);
+  assert_eq!(Key::from_name("+"), None);
+  for &(name, key) in Key::NAMES {
+    assert_eq!(Key::from_name(name), Some(key));
+  }
+}
+
+#[test]
+fn maps_presses_and_repeats() {
+  let keymap = defaults();
+  let key = |key, state| Event::Key { key, state };
+  let events = [
+    key(Key::KP_PLUS, KeyState::Pressed),
+    key(Key::KP_PLUS, KeyState::Repeated),
+    key(Key::KP_PLUS, KeyState::Released),
+    key(Key::SPACE, KeyState::Pressed),
+    Event::Motion { dx: 1, dy: 1 },
+    key(Key::Q, KeyState::Pressed),
+  ];
+  assert_eq!(
+    keymap.actions(&events),
+    [Action::Zoom, Action::Zoom, Action::Quit]
+  );
+}
+
+#[test]
+fn rebinding_a_key_takes_it_from_its_action() {
+  let keymap = defaults().bind(Action::Zoom, &[Key::Q]);
+  assert_eq!(keymap.action(Key::Q), Some(Action::Zoom));
+  assert_eq!(keymap.keys(Action::Quit), [Key::ESC]);
+  assert_eq!(keymap.keys(Action::Zoom), [Key::KP_PLUS, Key::Q]);
+}
+
+#[test]
+fn loads_bindings_from_a_file() {
+  let keymap = defaults()
+    .load("# The kiosk's keypad\n\nquit =\nzoom = kp9 KPPLUS # or Enter\n")
+    .unwrap();
+  assert_eq!(keymap.keys(Action::Quit), []);
+  assert_eq!(keymap.keys(Action::Zoom), [Key::KP9, Key::KP_PLUS]);
+  assert_eq!(keymap.action(Key::ESC), None);
+}
+
+#[test]
+fn reports_the_line_of_errors() {
+  let error = |text| defaults().load(text).unwrap_err();
+  assert_eq!(
+    error("quit = esc\nzoom kp9"),
+    KeymapError {
+      line: 2,
+      message: "expected ACTION = KEYS, not \"zoom kp9\"".to_string(),
+    }
+  );
+  assert_eq!(error("jump = space").message, "no action called \"jump\"");
+  assert_eq!(error("quit = escape").message, "no key called \"escape\"");
+  assert_eq!(
+    error("\n\nquit = escape").to_string(),
+    "line 3: no key called \"escape\""
+  );
+}
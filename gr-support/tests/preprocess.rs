@@ -0,0 +1,181 @@
+mod common;
+
+use common::scratch_dir;
+use gr_support::preprocess::{Origin, PreprocessError, Preprocessor};
+use gr_support::{Error, ShaderError, ShaderStage, SourceLine};
+use std::fs;
+
+fn origin(file: &str, line: usize) -> Origin {
+  Origin {
+    file: file.to_string(),
+    line,
+  }
+}
+
+#[test]
+fn defines_go_after_version() {
+  let source = Preprocessor::new()
+    .define("MAX_ITERATIONS", 18)
+    .define("COLOR(i)", "vec4(float(i), 0, 0, 1)")
+    .process("main.glsl", "// Mandelbrot\n#version 100\nvoid main() {}\n")
+    .unwrap();
+
+  assert_eq!(
+    source.text(),
+    "// Mandelbrot\n#version 100\n#define MAX_ITERATIONS 18\n\
+     #define COLOR(i) vec4(float(i), 0, 0, 1)\nvoid main() {}\n"
+  );
+  assert_eq!(source.origin(2), Some(&origin("main.glsl", 2)));
+  assert_eq!(source.origin(4), Some(&origin("<define>", 2)));
+  assert_eq!(source.origin(5), Some(&origin("main.glsl", 3)));
+}
+
+#[test]
+fn defines_go_first_without_version() {
+  let source = Preprocessor::new()
+    .define("MAX_ITERATIONS", 512)
+    .process("main.glsl", "void main() {}\n")
+    .unwrap();
+
+  assert_eq!(
+    source.text(),
+    "#define MAX_ITERATIONS 512\nvoid main() {}\n"
+  );
+  assert_eq!(source.origin(2), Some(&origin("main.glsl", 1)));
+}
+
+#[test]
+fn includes_snippets_and_maps_their_lines() {
+  let source = Preprocessor::new()
+    .snippet(
+      "hsl2rgb.glsl",
+      "vec4 hsl2rgb(vec3 c) {\n  return vec4(c, 1);\n}\n",
+    )
+    .process(
+      "main.glsl",
+      "uniform vec4 color;\n#include \"hsl2rgb.glsl\"\nvoid main() {}\n",
+    )
+    .unwrap();
+
+  assert_eq!(
+    source.text(),
+    "uniform vec4 color;\nvec4 hsl2rgb(vec3 c) {\n  return vec4(c, 1);\n}\nvoid main() {}\n"
+  );
+  assert_eq!(source.origin(3), Some(&origin("hsl2rgb.glsl", 2)));
+  assert_eq!(source.origin(5), Some(&origin("main.glsl", 3)));
+  assert_eq!(source.origin(6), None);
+  assert!(source.files().is_empty());
+}
+
+#[test]
+fn files_next_to_the_shader_win_over_snippets() {
+  let dir = scratch_dir("preprocess-files");
+  let main = dir.join("mandelbrot.glsl");
+  let common = dir.join("common").join("hsl2rgb.glsl");
+  fs::create_dir_all(common.parent().unwrap()).unwrap();
+  fs::write(&main, "#include \"common/hsl2rgb.glsl\"\nvoid main() {}\n").unwrap();
+  fs::write(&common, "// from disk\n").unwrap();
+
+  let source = Preprocessor::new()
+    .snippet("common/hsl2rgb.glsl", "// built in\n")
+    .process_file(&main)
+    .unwrap();
+
+  assert_eq!(source.text(), "// from disk\nvoid main() {}\n");
+  assert_eq!(source.files(), &[main.clone(), common.clone()][..]);
+  assert_eq!(
+    source.origin(1),
+    Some(&origin(&common.display().to_string(), 1))
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn include_dirs_are_searched() {
+  let dir = scratch_dir("preprocess-include-dir");
+  fs::write(dir.join("noise.glsl"), "float noise(vec2 p);\n").unwrap();
+
+  let source = Preprocessor::new()
+    .include_dir(&dir)
+    .process("main.glsl", "#include \"noise.glsl\" // shared\n")
+    .unwrap();
+
+  assert_eq!(source.text(), "float noise(vec2 p);\n");
+  assert_eq!(source.files(), &[dir.join("noise.glsl")][..]);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn missing_and_malformed_includes_are_errors() {
+  let err = Preprocessor::new()
+    .process("main.glsl", "\n#include \"nowhere.glsl\"\n")
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    Error::Preprocess(PreprocessError::IncludeNotFound { ref file, line: 2, ref name })
+      if file == "main.glsl" && name == "nowhere.glsl"
+  ));
+  assert_eq!(
+    err.to_string(),
+    "main.glsl:2: cannot find included file nowhere.glsl"
+  );
+
+  let err = Preprocessor::new()
+    .process("main.glsl", "#include <hsl2rgb.glsl>\n")
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    Error::Preprocess(PreprocessError::BadInclude { line: 1, .. })
+  ));
+}
+
+#[test]
+fn recursive_includes_are_errors() {
+  let err = Preprocessor::new()
+    .snippet("a.glsl", "#include \"b.glsl\"\n")
+    .snippet("b.glsl", "\n#include \"a.glsl\"\n")
+    .process("main.glsl", "#include \"a.glsl\"\n")
+    .unwrap_err();
+
+  assert!(matches!(
+    err,
+    Error::Preprocess(PreprocessError::RecursiveInclude { ref file, line: 2, ref name })
+      if file == "b.glsl" && name == "a.glsl"
+  ));
+}
+
+#[test]
+fn compile_errors_point_at_the_original_files() {
+  let source = Preprocessor::new()
+    .define("MAX_ITERATIONS", 18)
+    .snippet(
+      "hsl2rgb.glsl",
+      "vec4 hsl2rgb(vec3 c) {\n  return colour;\n}\n",
+    )
+    .process(
+      "mandelbrot.glsl",
+      "#include \"hsl2rgb.glsl\"\nvoid main() {}\n",
+    )
+    .unwrap();
+
+  let log = "ERROR: 0:3: 'colour' : undeclared identifier\n".to_string();
+  let err = Error::Shader(ShaderError::Compile {
+    stage: ShaderStage::Fragment,
+    lines: SourceLine::from_log(source.text(), &log),
+    log,
+  });
+
+  match source.locate(err) {
+    Error::Shader(ShaderError::Compile { lines, .. }) => assert_eq!(
+      lines,
+      vec![SourceLine {
+        number: 2,
+        text: "  return colour;".to_string(),
+        file: Some("hsl2rgb.glsl".to_string()),
+      }]
+    ),
+    err => panic!("{:?}", err),
+  }
+}
@@ -0,0 +1,188 @@
+//! Decoding PS/2 and IntelliMouse packets.
+
+use gr_support::input::ps2::{self, Decoder, OutOfSync, Packet, Protocol};
+use gr_support::input::{Button, Event};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+#[test]
+fn decodes_standard_packets() {
+  assert_eq!(
+    ps2::decode(Protocol::Standard, &[0x09, 5, 3]),
+    Ok(Packet {
+      left: true,
+      dx: 5,
+      dy: 3,
+      ..Packet::default()
+    })
+  );
+  // 0xff with the sign bit is -1, not -1 - 256
+  assert_eq!(
+    ps2::decode(Protocol::Standard, &[0x3e, 0xff, 0x80]),
+    Ok(Packet {
+      right: true,
+      middle: true,
+      dx: -1,
+      dy: -128,
+      ..Packet::default()
+    })
+  );
+}
+
+#[test]
+fn saturates_overflowed_motion() {
+  let packet = ps2::decode(Protocol::Standard, &[0xd8, 0x10, 0x10]).unwrap();
+  assert_eq!((packet.dx, packet.dy), (-256, 255));
+  assert!(packet.x_overflow && packet.y_overflow);
+}
+
+#[test]
+fn decodes_the_intellimouse_wheel() {
+  let packet = |wheel: u8| ps2::decode(Protocol::IntelliMouse, &[0x08, 0, 0, wheel]).unwrap();
+  assert_eq!(packet(0xff).wheel, 1);
+  assert_eq!(packet(0x01).wheel, -1);
+  assert_eq!(packet(0x00).wheel, 0);
+}
+
+#[test]
+fn rejects_packets_without_bit_3() {
+  assert_eq!(
+    ps2::decode(Protocol::Standard, &[0x01, 0, 0]),
+    Err(OutOfSync(0x01))
+  );
+}
+
+#[test]
+fn reports_changes_and_finds_packets_again() {
+  let mut decoder = Decoder::new(Protocol::Standard);
+  let mut events = Vec::new();
+  // A stray byte, a click with motion, a packet split across reads, and the release
+  decoder.feed(&[0x00, 0x09, 0x02], &mut events);
+  decoder.feed(&[0x02, 0x09], &mut events);
+  decoder.feed(&[0, 0, 0x08, 0, 0], &mut events);
+
+  assert_eq!(decoder.skipped(), 1);
+  assert_eq!(
+    events,
+    [
+      Event::Button {
+        button: Button::Left,
+        pressed: true
+      },
+      Event::Motion { dx: 2, dy: -2 },
+      Event::Button {
+        button: Button::Left,
+        pressed: false
+      },
+    ]
+  );
+}
+
+/// A mouse answering commands as the kernel's `mousedev` does.
+struct Port {
+  id: u8,
+  written: Vec<u8>,
+  replies: VecDeque<u8>,
+}
+
+impl Read for Port {
+  fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+    if self.replies.is_empty() {
+      return Err(io::ErrorKind::WouldBlock.into());
+    }
+    let count = buffer.len().min(self.replies.len());
+    for byte in buffer.iter_mut().take(count) {
+      *byte = self.replies.pop_front().unwrap();
+    }
+    Ok(count)
+  }
+}
+
+impl Write for Port {
+  fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+    for &byte in bytes {
+      self.written.push(byte);
+      self.replies.push_back(0xfa);
+      if byte == 0xf2 {
+        self.replies.push_back(self.id);
+      }
+    }
+    Ok(bytes.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+#[test]
+fn switches_to_the_wheel_protocol() {
+  let mut port = Port {
+    id: 3,
+    written: Vec::new(),
+    // A motion packet that was waiting
+    replies: vec![0x08, 1, 1].into(),
+  };
+  assert_eq!(
+    ps2::enable_wheel(&mut port).unwrap(),
+    Protocol::IntelliMouse
+  );
+  assert_eq!(port.written, [0xf3, 200, 0xf3, 100, 0xf3, 80, 0xf2]);
+
+  let mut port = Port {
+    id: 0,
+    written: Vec::new(),
+    replies: VecDeque::new(),
+  };
+  assert_eq!(ps2::enable_wheel(&mut port).unwrap(), Protocol::Standard);
+}
+
+/// xorshift, so the stream is the same on every run.
+fn random_bytes(seed: u64, count: usize) -> Vec<u8> {
+  let mut state = seed;
+  (0..count)
+    .map(|_| {
+      state ^= state << 13;
+      state ^= state >> 7;
+      state ^= state << 17;
+      state as u8
+    })
+    .collect()
+}
+
+/// What `fuzz/fuzz_targets/ps2.rs` checks on arbitrary input, on a fixed sample.
+#[test]
+fn random_streams_decode_within_range() {
+  for &protocol in &[Protocol::Standard, Protocol::IntelliMouse] {
+    for seed in 1..50 {
+      let bytes = random_bytes(seed, 512);
+
+      let mut whole = Vec::new();
+      let mut decoder = Decoder::new(protocol);
+      decoder.feed(&bytes, &mut whole);
+
+      // The same events however the stream is split
+      let mut split = Vec::new();
+      let mut chunked = Decoder::new(protocol);
+      for chunk in bytes.chunks(1 + seed as usize % 5) {
+        chunked.feed(chunk, &mut split);
+      }
+      assert_eq!(whole, split);
+      assert_eq!(decoder.skipped(), chunked.skipped());
+
+      for event in whole {
+        match event {
+          Event::Motion { dx, dy } => {
+            assert!((-256..=255).contains(&dx) && (-255..=256).contains(&dy));
+          }
+          Event::Wheel { vertical, .. } => {
+            assert_eq!(protocol, Protocol::IntelliMouse);
+            assert!((-127..=128).contains(&vertical));
+          }
+          Event::Button { .. } => {}
+          other => panic!("{:?}", other),
+        }
+      }
+    }
+  }
+}
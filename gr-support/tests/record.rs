@@ -0,0 +1,78 @@
+//! Recording the software rasterizer's frames.
+
+mod common;
+
+use common::scratch_dir;
+use gr_support::image::Image;
+use gr_support::record::Recorder;
+use gr_support::soft::Soft;
+use gr_support::{gl, GlContext};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn clear(red: f32, green: f32, blue: f32) {
+  gl::clear_color(red, green, blue, 1.0);
+  gl::clear(gl::GL_COLOR_BUFFER_BIT);
+}
+
+#[test]
+fn writes_y4m_frames() {
+  let dir = scratch_dir("record-y4m");
+  let path = dir.join("frames.y4m");
+  let context = GlContext::with_target(Soft::new(3, 2));
+  let mut recorder = Recorder::create(&path, 3, 2, 30).unwrap();
+
+  clear(1.0, 1.0, 1.0);
+  recorder.frame(&context).unwrap();
+  clear(1.0, 0.0, 0.0);
+  recorder.frame(&context).unwrap();
+  assert_eq!(recorder.time(), Duration::from_secs(2) / 30);
+  recorder.finish().unwrap();
+
+  let y4m = fs::read(&path).unwrap();
+  let header = b"YUV4MPEG2 W3 H2 F30:1 Ip A1:1 C420jpeg XCOLORRANGE=FULL\n";
+  assert_eq!(&y4m[..header.len()], header);
+
+  // 6 luma samples, then 2x1 of each chroma plane
+  let frames: Vec<&[u8]> = y4m[header.len()..].chunks(6 + 6 + 4).collect();
+  assert_eq!(frames.len(), 2);
+  assert_eq!(
+    frames[0],
+    b"FRAME\n\xff\xff\xff\xff\xff\xff\x80\x80\x80\x80"
+  );
+  assert_eq!(&frames[1][..6], b"FRAME\n");
+  assert_eq!(&frames[1][6..], [76, 76, 76, 76, 76, 76, 85, 85, 255, 255]);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn pipes_raw_frames_to_a_command() {
+  let dir = scratch_dir("record-raw");
+  let path = dir.join("frames.rgba");
+  let context = GlContext::with_target(Soft::new(2, 1));
+  let command = format!("cat > {}-{{width}}x{{height}}", path.display());
+  let mut recorder = Recorder::pipe(&command, 2, 1, 60).unwrap();
+
+  clear(0.0, 0.0, 1.0);
+  recorder.frame(&context).unwrap();
+  recorder.finish().unwrap();
+
+  let raw_path = PathBuf::from(format!("{}-2x1", path.display()));
+  assert_eq!(
+    fs::read(&raw_path).unwrap(),
+    [0, 0, 255, 255, 0, 0, 255, 255]
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn refuses_frames_of_another_size() {
+  let mut recorder = Recorder::new(Vec::new(), gr_support::record::Format::Raw, 2, 2, 60).unwrap();
+  assert!(recorder
+    .write(&Image::new(1, 1, vec![0, 0, 0, 255]))
+    .is_err());
+  assert_eq!(recorder.frames(), 0);
+}
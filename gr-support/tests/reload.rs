@@ -0,0 +1,32 @@
+#![cfg(feature = "reload")]
+
+mod common;
+
+use common::scratch_dir;
+use gr_support::reload::SourceWatcher;
+use std::fs;
+
+#[test]
+fn sees_writes_and_renames_of_watched_files_only() {
+  let dir = scratch_dir("reload");
+  let shader = dir.join("julia.glsl");
+  fs::write(&shader, "void main() {}").unwrap();
+
+  let mut watcher = SourceWatcher::new(&[&shader]).expect("watcher");
+  assert!(!watcher.changed().unwrap());
+
+  fs::write(&shader, "void main() { }").unwrap();
+  assert!(watcher.changed().unwrap());
+  assert!(!watcher.changed().unwrap());
+
+  // Saved the way most editors do it
+  let temporary = dir.join(".julia.glsl.swp");
+  fs::write(&temporary, "void main() {  }").unwrap();
+  fs::rename(&temporary, &shader).unwrap();
+  assert!(watcher.changed().unwrap());
+
+  fs::write(dir.join("mandelbrot.glsl"), "void main() {}").unwrap();
+  assert!(!watcher.changed().unwrap());
+
+  fs::remove_dir_all(&dir).unwrap();
+}
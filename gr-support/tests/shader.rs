@@ -0,0 +1,71 @@
+use gr_support::{ShaderError, ShaderStage, SourceLine};
+
+const SOURCE: &str = "varying mediump vec4 color;
+
+void main() {
+  gl_FragColor = colour;
+  gl_FragColor.w = 1;
+}
+";
+
+fn line(number: usize, text: &str) -> SourceLine {
+  SourceLine {
+    number,
+    text: text.to_string(),
+    file: None,
+  }
+}
+
+#[test]
+fn mesa_style_logs() {
+  let log = "0:4(18): error: `colour' undeclared\n0:5(3): error: type mismatch\n";
+  assert_eq!(
+    SourceLine::from_log(SOURCE, log),
+    vec![
+      line(4, "  gl_FragColor = colour;"),
+      line(5, "  gl_FragColor.w = 1;")
+    ]
+  );
+}
+
+#[test]
+fn broadcom_style_logs() {
+  let log = "ERROR: 0:4: 'colour' : undeclared identifier\nERROR: 1 compilation errors.";
+  assert_eq!(
+    SourceLine::from_log(SOURCE, log),
+    vec![line(4, "  gl_FragColor = colour;")]
+  );
+}
+
+#[test]
+fn parenthesised_logs() {
+  let log = "0(5) : error C7011: implicit cast from \"int\" to \"float\"";
+  assert_eq!(
+    SourceLine::from_log(SOURCE, log),
+    vec![line(5, "  gl_FragColor.w = 1;")]
+  );
+}
+
+#[test]
+fn repeated_and_out_of_range_lines_are_dropped() {
+  let log = "0:4: first\n0:4: second\n0:99: past the end\nno line here";
+  assert_eq!(
+    SourceLine::from_log(SOURCE, log),
+    vec![line(4, "  gl_FragColor = colour;")]
+  );
+}
+
+#[test]
+fn compile_errors_show_log_and_lines() {
+  let err = ShaderError::Compile {
+    stage: ShaderStage::Fragment,
+    log: "0:4(18): error: `colour' undeclared\n".to_string(),
+    lines: vec![line(4, "  gl_FragColor = colour;")],
+  };
+  assert_eq!(
+    err.to_string(),
+    "fragment shader failed to compile:\n\
+     0:4(18): error: `colour' undeclared\n   \
+     4 |   gl_FragColor = colour;"
+  );
+}
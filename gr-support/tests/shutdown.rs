@@ -0,0 +1,15 @@
+//! Stopping on a signal. A binary of its own, as the handlers stay installed.
+
+use gr_support::shutdown;
+
+#[test]
+fn sigterm_asks_to_stop() {
+  shutdown::install();
+  assert!(!shutdown::requested());
+  assert_eq!(shutdown::exit_code(), None);
+
+  unsafe { libc::raise(libc::SIGTERM) };
+  assert!(shutdown::requested());
+  assert_eq!(shutdown::signal(), Some(libc::SIGTERM));
+  assert_eq!(shutdown::exit_code(), Some(143));
+}
@@ -0,0 +1,297 @@
+use gr_support::gl;
+use gr_support::preprocess::Preprocessor;
+use gr_support::soft::{Soft, VertexOutput};
+use gr_support::{
+  gl_check, Buffer, Error, Framebuffer, GlContext, Program, Shader, ShaderError, ShaderProgram,
+  ShaderStage, Texture, Variable,
+};
+
+const VERTEX: &str = "
+attribute vec2 position;
+attribute float shade;
+varying float vShade;
+
+void main() {
+  gl_Position = vec4(position, 0.0, 1.0);
+  vShade = shade;
+}
+";
+
+const FRAGMENT: &str = "
+// SHADE_CHANNEL is defined by the test
+uniform mediump vec4 tint;
+varying mediump float vShade;
+
+void main() {
+  gl_FragColor = tint;
+  gl_FragColor[SHADE_CHANNEL] = vShade;
+}
+";
+
+const COPY: &str = "
+uniform sampler2D tex;
+uniform mediump vec2 size;
+
+void main() {
+  gl_FragColor = texture2D(tex, gl_FragCoord.xy / size);
+}
+";
+
+/// `(x, y, shade)` for a quad over the whole surface, dark on the left and bright on the right.
+#[rustfmt::skip]
+const QUAD: [f32; 12] = [
+  -1.0, -1.0, 0.0,
+   1.0, -1.0, 1.0,
+   1.0,  1.0, 1.0,
+  -1.0,  1.0, 0.0,
+];
+
+fn soft(width: u32, height: u32) -> Soft {
+  Soft::new(width, height)
+    .vertex_shader(VERTEX, |vertex| {
+      let [x, y, _, _] = vertex.attribute("position");
+      VertexOutput {
+        position: [x, y, 0.0, 1.0],
+        varyings: vec![vertex.attribute("shade")[0]],
+      }
+    })
+    .fragment_shader(FRAGMENT, |fragment| {
+      let channel: usize = fragment.define("SHADE_CHANNEL").unwrap().parse().unwrap();
+      let mut color = [0.0; 4];
+      color.copy_from_slice(fragment.uniform("tint"));
+      color[channel] = fragment.varyings[0];
+      color
+    })
+    .fragment_shader(COPY, |fragment| {
+      let size = fragment.uniform("size");
+      let coord = [
+        fragment.frag_coord[0] / size[0],
+        fragment.frag_coord[1] / size[1],
+      ];
+      fragment.texture_2d("tex", coord)
+    })
+}
+
+fn read(context: &GlContext) -> Vec<[u8; 4]> {
+  let (width, height) = (context.width() as i32, context.height() as i32);
+  let mut pixels = vec![0; (width * height * 4) as usize];
+  gl::read_pixels(
+    0,
+    0,
+    width,
+    height,
+    gl::GL_RGBA,
+    gl::GL_UNSIGNED_BYTE,
+    &mut pixels,
+  );
+  pixels.chunks(4).map(|p| [p[0], p[1], p[2], p[3]]).collect()
+}
+
+fn draw_quad(program: &ShaderProgram) -> gr_support::Result<()> {
+  program.use_program()?;
+  let position = program.attrib_location("position")?;
+  let shade = program.attrib_location("shade")?;
+  gl::vertex_attrib_pointer_offset(position, 2, gl::GL_FLOAT, false, 12, 0);
+  gl::vertex_attrib_pointer_offset(shade, 1, gl::GL_FLOAT, false, 12, 8);
+  gl::enable_vertex_attrib_array(position);
+  gl::enable_vertex_attrib_array(shade);
+  gl::uniform4f(program.uniform_location("tint"), 0.0, 0.0, 1.0, 1.0);
+  gl::draw_arrays(gl::GL_TRIANGLE_FAN, 0, 4);
+  gl_check()
+}
+
+#[test]
+fn clears_and_reads_back() {
+  let context = GlContext::with_target(Soft::new(4, 2));
+  gl::clear_color(1.0, 0.0, 0.0, 1.0);
+  gl::clear(gl::GL_COLOR_BUFFER_BIT);
+  gl_check().unwrap();
+
+  assert_eq!(read(&context), vec![[255, 0, 0, 255]; 8]);
+  assert_eq!(
+    gl::get_string(gl::GL_RENDERER).as_deref(),
+    Some("gr-support software rasterizer")
+  );
+}
+
+#[test]
+fn interpolates_varyings_with_defines_and_uniforms() {
+  let context = GlContext::with_target(soft(8, 2));
+  let fragment = Preprocessor::new()
+    .define("SHADE_CHANNEL", 0)
+    .process("fragment.glsl", FRAGMENT)
+    .unwrap();
+  let program = ShaderProgram::builder()
+    .vertex(VERTEX)
+    .fragment(&fragment)
+    .build(&context)
+    .unwrap();
+  let buffer = Buffer::new(&context).unwrap();
+  buffer
+    .upload(gl::GL_ARRAY_BUFFER, &QUAD, gl::GL_STATIC_DRAW)
+    .unwrap();
+
+  draw_quad(&program).unwrap();
+
+  let pixels = read(&context);
+  // Red follows the shade, from 1/16 to 15/16 across the pixel centres; blue is the tint
+  let reds: Vec<u8> = pixels[..8].iter().map(|p| p[0]).collect();
+  assert_eq!(reds, vec![16, 48, 80, 112, 143, 175, 207, 239]);
+  assert!(pixels.iter().all(|p| p[2] == 255 && p[3] == 255));
+  assert_eq!(pixels[..8], pixels[8..]);
+}
+
+#[test]
+fn renders_to_a_texture_and_samples_it() {
+  let context = GlContext::with_target(soft(8, 2));
+  let fragment = Preprocessor::new()
+    .define("SHADE_CHANNEL", 1)
+    .process("fragment.glsl", FRAGMENT)
+    .unwrap();
+  let shaded = ShaderProgram::builder()
+    .vertex(VERTEX)
+    .fragment(&fragment)
+    .build(&context)
+    .unwrap();
+  let copy = ShaderProgram::builder()
+    .vertex(VERTEX)
+    .fragment(COPY)
+    .build(&context)
+    .unwrap();
+  let buffer = Buffer::new(&context).unwrap();
+  buffer
+    .upload(gl::GL_ARRAY_BUFFER, &QUAD, gl::GL_STATIC_DRAW)
+    .unwrap();
+
+  let texture = Texture::new(&context).unwrap();
+  texture
+    .image_2d(
+      gl::GL_RGB,
+      8,
+      2,
+      gl::GL_RGB,
+      gl::GL_UNSIGNED_SHORT_5_6_5,
+      &[] as &[u16],
+    )
+    .unwrap();
+  let framebuffer = Framebuffer::new(&context).unwrap();
+  framebuffer
+    .attach_texture(gl::GL_COLOR_ATTACHMENT0, &texture)
+    .unwrap();
+  draw_quad(&shaded).unwrap();
+
+  Framebuffer::bind_default().unwrap();
+  gl::clear(gl::GL_COLOR_BUFFER_BIT);
+  copy.use_program().unwrap();
+  gl::uniform2f(copy.uniform_location("size"), 8.0, 2.0);
+  texture.bind().unwrap();
+  draw_quad(&copy).unwrap();
+
+  // Green went through 6 bits, blue through 5
+  let greens: Vec<u8> = read(&context)[..8].iter().map(|p| p[1]).collect();
+  assert_eq!(greens, vec![16, 48, 81, 113, 142, 174, 207, 239]);
+  assert!(read(&context).iter().all(|p| p[0] == 0 && p[2] == 255));
+}
+
+#[test]
+fn sources_without_a_closure_do_not_compile() {
+  let context = GlContext::with_target(soft(1, 1));
+  let built = ShaderProgram::builder()
+    .vertex(VERTEX)
+    .fragment("void main() { gl_FragColor = vec4(1.0); }")
+    .build(&context);
+
+  match built {
+    Err(Error::Shader(ShaderError::Compile { log, .. })) => {
+      assert_eq!(log, "no software shader is registered for this source")
+    }
+    Err(err) => panic!("{}", err),
+    Ok(_) => panic!("compiled"),
+  }
+}
+
+#[test]
+fn declarations_are_active_variables() {
+  let context = GlContext::with_target(soft(1, 1));
+  let program = ShaderProgram::builder()
+    .vertex(VERTEX)
+    .fragment(COPY)
+    .build(&context)
+    .unwrap();
+
+  let type_ = |variable: Option<&Variable>| variable.map(|variable| variable.type_);
+  assert_eq!(
+    type_(program.attribute("position")),
+    Some(gl::GL_FLOAT_VEC2)
+  );
+  assert_eq!(type_(program.attribute("shade")), Some(gl::GL_FLOAT));
+  assert_eq!(type_(program.uniform("tex")), Some(gl::GL_SAMPLER_2D));
+  assert_eq!(type_(program.uniform("size")), Some(gl::GL_FLOAT_VEC2));
+  assert_eq!(program.uniform("tint"), None);
+}
+
+#[test]
+fn dropping_resources_deletes_them() {
+  let context = GlContext::with_target(Soft::new(2, 2));
+  let buffer = Buffer::new(&context).unwrap();
+  buffer
+    .upload(gl::GL_ARRAY_BUFFER, &QUAD, gl::GL_STATIC_DRAW)
+    .unwrap();
+  let texture = Texture::new(&context).unwrap();
+  texture
+    .image_2d(
+      gl::GL_RGBA,
+      2,
+      2,
+      gl::GL_RGBA,
+      gl::GL_UNSIGNED_BYTE,
+      &[0u8; 16],
+    )
+    .unwrap();
+  let framebuffer = Framebuffer::new(&context).unwrap();
+  framebuffer
+    .attach_texture(gl::GL_COLOR_ATTACHMENT0, &texture)
+    .unwrap();
+  let ids = (buffer.id(), texture.id(), framebuffer.id());
+  assert!(gl::is_buffer(ids.0) && gl::is_texture(ids.1) && gl::is_framebuffer(ids.2));
+
+  drop(buffer);
+  drop(framebuffer);
+  drop(texture);
+
+  assert!(!gl::is_buffer(ids.0));
+  assert!(!gl::is_texture(ids.1));
+  assert!(!gl::is_framebuffer(ids.2));
+}
+
+#[test]
+fn dropping_a_program_deletes_it_and_its_dropped_shaders() {
+  let context = GlContext::with_target(soft(1, 1));
+  let vertex = Shader::compile(&context, ShaderStage::Vertex, VERTEX).unwrap();
+  let fragment = Shader::compile(&context, ShaderStage::Fragment, COPY).unwrap();
+  let program = Program::link(&[&vertex, &fragment]).unwrap();
+  let ids = (vertex.id(), fragment.id(), program.id());
+  assert!(gl::is_shader(ids.0) && gl::is_shader(ids.1) && gl::is_program(ids.2));
+
+  // Attached, so only flagged for deletion
+  drop(vertex);
+  drop(fragment);
+  assert!(gl::is_shader(ids.0) && gl::is_shader(ids.1));
+
+  drop(program);
+  assert!(!gl::is_program(ids.2));
+  assert!(!gl::is_shader(ids.0));
+  assert!(!gl::is_shader(ids.1));
+}
+
+#[test]
+fn dropping_an_unattached_shader_deletes_it() {
+  let context = GlContext::with_target(soft(1, 1));
+  let shader = Shader::compile(&context, ShaderStage::Vertex, VERTEX).unwrap();
+  let id = shader.id();
+  assert!(gl::is_shader(id));
+
+  drop(shader);
+
+  assert!(!gl::is_shader(id));
+}
@@ -0,0 +1,96 @@
+//! Frame timing reports.
+
+use gr_support::timing::{Summary, Timings, WINDOW};
+use std::time::Duration;
+
+fn millis(millis: u64) -> Duration {
+  Duration::from_millis(millis)
+}
+
+#[test]
+fn summarizes_with_nearest_rank_percentiles() {
+  let durations: Vec<Duration> = (1..=200).map(millis).collect();
+  let summary = Summary::new(&durations);
+  assert_eq!(summary.min, millis(1));
+  assert_eq!(summary.avg, Duration::from_micros(100_500));
+  assert_eq!(summary.p95, millis(190));
+  assert_eq!(summary.p99, millis(198));
+  assert_eq!(summary.max, millis(200));
+
+  assert_eq!(Summary::new(&[millis(7)]).p99, millis(7));
+  assert_eq!(Summary::new(&[]), Summary::default());
+}
+
+#[test]
+fn counts_missed_frames() {
+  let mut timings = Timings::new(50);
+  for frame in &[20, 21, 19, 40, 50, 29] {
+    timings.add_frame(millis(*frame));
+  }
+  let report = timings.report();
+  assert_eq!(report.frames, 6);
+  // 40 ms missed one, 50 ms two, 29 ms rounds down to being on time
+  assert_eq!(report.missed, 3);
+}
+
+#[test]
+fn keeps_percentiles_of_the_latest_frames() {
+  let mut timings = Timings::new(10);
+  for _ in 0..WINDOW {
+    timings.add_frame(millis(100));
+  }
+  for _ in 0..WINDOW {
+    timings.add_frame(millis(10));
+  }
+
+  let report = timings.report();
+  assert_eq!(report.frames, 2 * WINDOW as u64);
+  assert_eq!(report.frame.min, millis(10));
+  assert_eq!(report.frame.avg, millis(55));
+  assert_eq!(report.frame.p99, millis(10));
+  assert_eq!(report.frame.max, millis(100));
+}
+
+#[test]
+fn reports_stages_in_order() {
+  let mut timings = Timings::new(60);
+  for _ in 0..3 {
+    timings.start_frame();
+    timings.add("input", millis(1));
+    assert_eq!(timings.time("draw", || 42), 42);
+    timings.add("swap", millis(16));
+  }
+
+  let report = timings.report();
+  assert_eq!(report.frames, 2);
+  let stages: Vec<&str> = report
+    .stages
+    .iter()
+    .map(|(name, _)| name.as_str())
+    .collect();
+  assert_eq!(stages, ["input", "draw", "swap"]);
+  assert_eq!(report.stages[2].1.avg, millis(16));
+
+  let table = report.to_string();
+  assert!(
+    table.starts_with("2 frames, 0 missed at 60 fps\n"),
+    "{}",
+    table
+  );
+  assert!(table.contains("\nswap        16.00    16.00"), "{}", table);
+}
+
+#[test]
+fn reports_json() {
+  let mut timings = Timings::new(60);
+  timings.add_frame(millis(17));
+  timings.add_frame(millis(33));
+  timings.add("draw", Duration::from_micros(2500));
+
+  assert_eq!(
+    timings.report().to_json(),
+    "{\"fps\":60,\"frames\":2,\"missed\":1,\
+     \"frame\":{\"min\":17.000,\"avg\":25.000,\"p95\":33.000,\"p99\":33.000,\"max\":33.000},\
+     \"stages\":{\"draw\":{\"min\":2.500,\"avg\":2.500,\"p95\":2.500,\"p99\":2.500,\"max\":2.500}}}"
+  );
+}
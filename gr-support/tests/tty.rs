@@ -0,0 +1,122 @@
+//! Decoding what a terminal in raw mode sends, and putting the terminal back.
+
+use gr_support::input::tty::{Decoder, Terminal};
+use gr_support::input::{Event, Key, KeyState};
+use gr_support::shutdown;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+fn keys(events: &[Event]) -> Vec<Key> {
+  events
+    .iter()
+    .map(|event| match *event {
+      Event::Key {
+        key,
+        state: KeyState::Pressed,
+      } => key,
+      other => panic!("{:?}", other),
+    })
+    .collect()
+}
+
+fn decode(bytes: &[u8]) -> Vec<Key> {
+  let mut events = Vec::new();
+  Decoder::new().feed(bytes, &mut events);
+  keys(&events)
+}
+
+#[test]
+fn decodes_characters() {
+  assert_eq!(
+    decode(b"qQ5 \r\n+-*"),
+    [
+      Key::Q,
+      Key::Q,
+      Key::from_char('5').unwrap(),
+      Key::SPACE,
+      Key::ENTER,
+      Key::ENTER,
+      Key::KP_PLUS,
+      Key::MINUS,
+      Key::KP_ASTERISK,
+    ]
+  );
+  // Ctrl-A and friends are not keys of their own
+  assert_eq!(decode(b"\x01\x02"), []);
+}
+
+#[test]
+fn decodes_escape_sequences() {
+  assert_eq!(
+    decode(b"\x1b[A\x1b[B\x1bOC\x1b[D\x1b[5~\x1b[3~\x1bOM"),
+    [
+      Key::UP,
+      Key::DOWN,
+      Key::RIGHT,
+      Key::LEFT,
+      Key::PAGE_UP,
+      Key::DELETE,
+      Key::KP_ENTER
+    ]
+  );
+  // F5, with a modifier, and Alt-J
+  assert_eq!(decode(b"\x1b[15~\x1b[1;5A\x1bj"), [Key::UP, Key::J]);
+}
+
+#[test]
+fn waits_for_the_rest_of_a_sequence() {
+  let mut decoder = Decoder::new();
+  let mut events = Vec::new();
+  decoder.feed(b"r\x1b[", &mut events);
+  assert_eq!(keys(&events), [Key::R]);
+  decoder.feed(b"6", &mut events);
+  decoder.feed(b"~\x1b", &mut events);
+  assert_eq!(keys(&events), [Key::R, Key::PAGE_DOWN, Key::ESC]);
+}
+
+fn lflag(fd: libc::c_int) -> libc::tcflag_t {
+  let mut termios = MaybeUninit::<libc::termios>::uninit();
+  assert_eq!(unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) }, 0);
+  unsafe { termios.assume_init() }.c_lflag
+}
+
+#[test]
+fn a_second_signal_puts_the_terminal_back_before_killing() {
+  let (mut master, mut slave) = (0, 0);
+  let opened = unsafe {
+    libc::openpty(
+      &mut master,
+      &mut slave,
+      ptr::null_mut(),
+      ptr::null(),
+      ptr::null(),
+    )
+  };
+  assert_eq!(opened, 0);
+  let cooked = lflag(slave);
+  assert_ne!(cooked & libc::ECHO, 0);
+
+  // The child makes the terminal raw and gets killed, without a chance to drop it
+  let child = unsafe { libc::fork() };
+  if child == 0 {
+    unsafe { libc::dup2(slave, libc::STDIN_FILENO) };
+    let _terminal = Terminal::stdin();
+    shutdown::install();
+    unsafe {
+      libc::raise(libc::SIGTERM);
+      libc::raise(libc::SIGTERM);
+      libc::_exit(0);
+    }
+  }
+
+  let mut status = 0;
+  assert_eq!(unsafe { libc::waitpid(child, &mut status, 0) }, child);
+  assert!(libc::WIFSIGNALED(status), "status {}", status);
+  assert_eq!(libc::WTERMSIG(status), libc::SIGTERM);
+  assert_eq!(lflag(slave), cooked);
+
+  unsafe {
+    libc::close(master);
+    libc::close(slave);
+  }
+}
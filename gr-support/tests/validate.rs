@@ -0,0 +1,173 @@
+#![cfg(feature = "validate")]
+
+use gr_support::validate::{validate_program, validate_shader, ValidationError};
+use gr_support::ShaderStage;
+
+const VERTEX: &str = "
+attribute vec4 position;
+uniform mat4 transform;
+varying mediump vec2 uv;
+
+void main() {
+  gl_Position = transform * position;
+  uv = position.xy * 0.5 + 0.5;
+}
+";
+
+const FRAGMENT: &str = "
+uniform sampler2D tex;
+varying mediump vec2 uv;
+
+void main(void) {
+  mediump vec4 color = texture2D(tex, uv);
+  gl_FragColor = color;
+}
+";
+
+fn errors(result: Result<(), Vec<ValidationError>>) -> Vec<ValidationError> {
+  result.expect_err("shader passed validation")
+}
+
+#[test]
+fn accepts_es100_program() {
+  assert_eq!(validate_program(VERTEX, FRAGMENT), Ok(()));
+}
+
+#[test]
+fn reports_parse_errors() {
+  let errors = errors(validate_shader(
+    ShaderStage::Fragment,
+    "void main() { gl_FragColor = ; }",
+  ));
+  assert!(matches!(
+    errors.as_slice(),
+    [ValidationError::Parse {
+      stage: ShaderStage::Fragment,
+      ..
+    }]
+  ));
+}
+
+#[test]
+fn rejects_desktop_glsl() {
+  let source = "#version 330
+in vec4 position;
+
+void main() {
+  gl_Position = position;
+}
+";
+  assert_eq!(
+    errors(validate_shader(ShaderStage::Vertex, source)),
+    vec![
+      ValidationError::Version {
+        stage: ShaderStage::Vertex,
+        version: 330,
+      },
+      ValidationError::Unsupported {
+        stage: ShaderStage::Vertex,
+        name: "position".to_string(),
+        feature: "in".to_string(),
+      },
+    ]
+  );
+}
+
+#[test]
+fn fragment_floats_need_a_precision() {
+  let source = "
+varying vec2 uv;
+
+void main() {
+  gl_FragColor = vec4(uv, 0.0, 1.0);
+}
+";
+  assert_eq!(
+    errors(validate_shader(ShaderStage::Fragment, source)),
+    vec![ValidationError::MissingPrecision {
+      name: "uv".to_string()
+    }]
+  );
+
+  let with_default = format!("precision mediump float;\n{}", source);
+  assert_eq!(
+    validate_shader(ShaderStage::Fragment, &with_default),
+    Ok(())
+  );
+}
+
+#[test]
+fn varyings_must_match() {
+  let fragment = "
+varying mediump vec3 uv;
+varying mediump float fade;
+
+void main() {
+  gl_FragColor = vec4(uv, fade);
+}
+";
+  assert_eq!(
+    errors(validate_program(VERTEX, fragment)),
+    vec![
+      ValidationError::Mismatch {
+        name: "uv".to_string(),
+        vertex: "vec2".to_string(),
+        fragment: "vec3".to_string(),
+      },
+      ValidationError::UndeclaredVarying {
+        name: "fade".to_string()
+      },
+    ]
+  );
+}
+
+#[test]
+fn fragment_shaders_have_no_attributes() {
+  let source = "
+attribute mediump vec4 position;
+
+void main() {
+  gl_FragColor = position;
+}
+";
+  assert_eq!(
+    errors(validate_shader(ShaderStage::Fragment, source)),
+    vec![ValidationError::FragmentAttribute {
+      name: "position".to_string()
+    }]
+  );
+}
+
+#[test]
+fn checks_every_declarator_in_a_list() {
+  let source = "
+uniform mediump vec2 offset, scale;
+varying vec2 uv, shade;
+
+void main() {
+  uint first, second;
+  gl_FragColor = vec4(uv * scale + offset, shade);
+}
+";
+  assert_eq!(
+    errors(validate_shader(ShaderStage::Fragment, source)),
+    vec![
+      ValidationError::MissingPrecision {
+        name: "uv".to_string()
+      },
+      ValidationError::MissingPrecision {
+        name: "shade".to_string()
+      },
+      ValidationError::Unsupported {
+        stage: ShaderStage::Fragment,
+        name: "first".to_string(),
+        feature: "uint".to_string(),
+      },
+      ValidationError::Unsupported {
+        stage: ShaderStage::Fragment,
+        name: "second".to_string(),
+        feature: "uint".to_string(),
+      },
+    ]
+  );
+}
@@ -0,0 +1,7 @@
+mediump vec4 hsl2rgb(in mediump vec3 c, in mediump float a) {
+  mediump vec3 rgb = clamp(
+    abs(mod(c.x * 6.0 + vec3(0.0, 4.0, 2.0), 6.0) - 3.0) - 1.0, 0.0, 1.0
+  );
+
+  return vec4(c.z + c.y * (rgb - 0.5) * (1.0 - abs(2.0 * c.z - 1.0)), a);
+}
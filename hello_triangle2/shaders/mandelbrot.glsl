@@ -0,0 +1,35 @@
+// MAX_ITERATIONS and COLOR(i) are defined at runtime, see `Mandelbrot` in src/shaders.rs
+
+uniform mediump vec4 color;
+uniform mediump vec2 scale;
+uniform mediump vec2 centre;
+varying mediump vec2 tcoord;
+
+#include "hsl2rgb.glsl"
+
+void main(void) {
+  mediump float intensity;
+  mediump vec4 color2;
+  mediump float cr = (gl_FragCoord.x - centre.x) * scale.x;
+  mediump float ci = (gl_FragCoord.y - centre.y) * scale.y;
+  mediump float ar = cr;
+  mediump float ai = ci;
+  mediump float tr, ti;
+  mediump float col = 0.0;
+  mediump float p = 0.0;
+  mediump int i = 0;
+
+  for (mediump int i2 = 1; i2 < MAX_ITERATIONS; i2++) {
+    tr = ar * ar - ai * ai + cr;
+    ti = 2.0 * ar * ai + ci;
+    p = tr * tr + ti * ti;
+    ar = tr;
+    ai = ti;
+    if (p > 16.0) {
+      i = i2;
+      break;
+    }
+  }
+
+  gl_FragColor = COLOR(i);
+}
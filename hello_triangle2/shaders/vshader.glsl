@@ -0,0 +1,8 @@
+attribute mediump vec4 vertex;
+varying mediump vec2 tcoord;
+
+void main(void) {
+  mediump vec4 pos = vertex;
+  gl_Position = pos;
+  tcoord = vertex.xy * 0.5 + 0.5;
+}
@@ -0,0 +1,128 @@
+//! What the keys do: moving around the Mandelbrot set, and the rest.
+
+use gr_support::input::keymap::Keymap;
+use gr_support::input::Key;
+use std::str::FromStr;
+
+/// Something a key does, named as in a `--keys` file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+  Quit,
+  PanLeft,
+  PanRight,
+  PanUp,
+  PanDown,
+  ZoomIn,
+  ZoomOut,
+  /// Back to where the demo started.
+  Reset,
+  Screenshot,
+  /// Shows or hides the Julia set over the Mandelbrot set.
+  Julia,
+}
+
+impl Action {
+  pub const ALL: [Action; 10] = [
+    Action::Quit,
+    Action::PanLeft,
+    Action::PanRight,
+    Action::PanUp,
+    Action::PanDown,
+    Action::ZoomIn,
+    Action::ZoomOut,
+    Action::Reset,
+    Action::Screenshot,
+    Action::Julia,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Action::Quit => "quit",
+      Action::PanLeft => "pan-left",
+      Action::PanRight => "pan-right",
+      Action::PanUp => "pan-up",
+      Action::PanDown => "pan-down",
+      Action::ZoomIn => "zoom-in",
+      Action::ZoomOut => "zoom-out",
+      Action::Reset => "reset",
+      Action::Screenshot => "screenshot",
+      Action::Julia => "julia",
+    }
+  }
+}
+
+impl FromStr for Action {
+  type Err = ();
+
+  fn from_str(name: &str) -> Result<Action, ()> {
+    Action::ALL
+      .iter()
+      .copied()
+      .find(|action| action.name() == name)
+      .ok_or(())
+  }
+}
+
+/// The keys of a full keyboard, and of a numeric keypad, read through evdev or typed on a
+/// terminal, which sends the keypad's digits as the main ones.
+pub fn keymap() -> Keymap<Action> {
+  let digit = |c| Key::from_char(c).unwrap();
+  Keymap::new()
+    .bind(Action::Quit, &[Key::ESC, Key::Q])
+    .bind(Action::PanLeft, &[Key::LEFT, Key::KP4, digit('4')])
+    .bind(Action::PanRight, &[Key::RIGHT, Key::KP6, digit('6')])
+    .bind(Action::PanUp, &[Key::UP, Key::KP8, digit('8')])
+    .bind(Action::PanDown, &[Key::DOWN, Key::KP2, digit('2')])
+    .bind(Action::ZoomIn, &[Key::EQUAL, Key::KP_PLUS])
+    .bind(Action::ZoomOut, &[Key::MINUS, Key::KP_MINUS])
+    .bind(Action::Reset, &[Key::R, Key::KP5, digit('5')])
+    .bind(Action::Screenshot, &[Key::ENTER, Key::KP_ENTER])
+    .bind(Action::Julia, &[Key::J, Key::SPACE, Key::KP_ASTERISK])
+}
+
+/// How much of the screen a pan moves by.
+pub const PAN_STEP: f32 = 0.1;
+/// How much a zoom magnifies by.
+pub const ZOOM_STEP: f32 = 1.25;
+
+/// Which part of the complex plane is on screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+  /// Where 0 is, in pixels from the bottom left.
+  pub centre: (f32, f32),
+  /// The width of a pixel.
+  pub scale: f32,
+}
+
+impl View {
+  /// Moves the view by `(dx, dy)` pixels, up and right positive, so that what was there is in
+  /// the middle.
+  pub fn pan(&mut self, dx: f32, dy: f32) {
+    self.centre = (self.centre.0 - dx, self.centre.1 - dy);
+  }
+
+  /// Magnifies the view by `factor`, keeping the point at pixel `about` where it is.
+  pub fn zoom(&mut self, factor: f32, about: (f32, f32)) {
+    self.centre = (
+      about.0 - (about.0 - self.centre.0) * factor,
+      about.1 - (about.1 - self.centre.1) * factor,
+    );
+    self.scale /= factor;
+  }
+
+  /// Pans or zooms for `action` on a `width` by `height` screen, about its middle. Returns
+  /// whether the view changed.
+  pub fn apply(&mut self, action: Action, width: f32, height: f32) -> bool {
+    let (step_x, step_y) = (width * PAN_STEP, height * PAN_STEP);
+    match action {
+      Action::PanLeft => self.pan(-step_x, 0.0),
+      Action::PanRight => self.pan(step_x, 0.0),
+      Action::PanUp => self.pan(0.0, step_y),
+      Action::PanDown => self.pan(0.0, -step_y),
+      Action::ZoomIn => self.zoom(ZOOM_STEP, (width / 2.0, height / 2.0)),
+      Action::ZoomOut => self.zoom(1.0 / ZOOM_STEP, (width / 2.0, height / 2.0)),
+      _ => return false,
+    }
+    true
+  }
+}
@@ -0,0 +1,459 @@
+//! The hello_triangle2 example's drawing code, shared by the binary and the tests.
+
+#![allow(non_upper_case_globals)]
+
+use clap::{ArgAction, Parser};
+use gr_support::args::point;
+use gr_support::bindings::{Attribute, Sampler2D, Uniform, Vec2, Vec4};
+use gr_support::gpu::{Gpu, VideoCore};
+use gr_support::preprocess::Preprocessor;
+use gr_support::reload::{ReloadingProgram, ShaderSource};
+use gr_support::{
+  gl, gl_check, Buffer, Error, Framebuffer, GlContext, GlError, ShaderBindings, ShaderError,
+  ShaderProgram, ShaderStage, Texture,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub mod controls;
+pub mod probe;
+pub mod shaders;
+
+use shaders::{
+  Mandelbrot, HSL2RGB_SOURCE, JULIA_FSHADER_SOURCE, MANDELBROT_FSHADER_SOURCE, VSHADER_SOURCE,
+};
+
+#[rustfmt::skip]
+static VERTEX_DATA: [gl::GLfloat; 16] = [
+  -1.0, -1.0,  1.0,  1.0,
+   1.0, -1.0,  1.0,  1.0,
+   1.0,  1.0,  1.0,  1.0,
+  -1.0,  1.0,  1.0,  1.0,
+];
+
+// --------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct CubeState {
+  pub screen_width: u32,
+  pub screen_height: u32,
+
+  dispman_display: u32,
+  dispman_update: u32,
+  dispman_element: u32,
+}
+
+impl CubeState {
+  pub fn new() -> Self {
+    return CubeState {
+      screen_width: 0,
+      screen_height: 0,
+
+      dispman_display: 0,
+      dispman_update: 0,
+      dispman_element: 0,
+    };
+  }
+}
+
+/***********************************************************
+ * Name: init_ogl
+ *
+ * Arguments:
+ *       CUBE_STATE_T *state - holds OGLES model info
+ *
+ * Description: Sets the display, OpenGL|ES context and screen stuff
+ *
+ * Returns: void
+ *
+ ***********************************************************/
+pub fn init_ogl(context: &GlContext, state: &mut CubeState) -> gr_support::Result<()> {
+  // lmfr: This only runs if selecting "G1 Legacy - Original non-GL desktop driver"
+  // lmfr: in raspi-config
+
+  state.screen_width = context.width();
+  state.screen_height = context.height();
+
+  // Set background color and clear buffers
+  gl::clear_color(0.15f32, 0.25f32, 0.35f32, 1.0f32);
+  gl::clear(gl::GL_COLOR_BUFFER_BIT);
+
+  gl_check()
+}
+
+#[derive(ShaderBindings)]
+pub struct JuliaLocations {
+  vertex: Attribute<Vec4>,
+  // Declared but never read by the shader, so drivers may optimize it away
+  color: Option<Uniform<Vec4>>,
+  scale: Uniform<Vec2>,
+  offset: Uniform<Vec2>,
+  tex: Uniform<Sampler2D>,
+  centre: Uniform<Vec2>,
+}
+
+#[derive(ShaderBindings)]
+pub struct MandelbrotLocations {
+  vertex: Attribute<Vec4>,
+  scale: Uniform<Vec2>,
+  centre: Uniform<Vec2>,
+}
+
+pub struct Scene<'ctx> {
+  pub julia: ReloadingProgram<'ctx, JuliaLocations>,
+  pub mandelbrot: ReloadingProgram<'ctx, MandelbrotLocations>,
+  tex_fb: Framebuffer<'ctx>,
+  tex: Texture<'ctx>,
+  buf: Buffer<'ctx>,
+}
+
+/// The built-in source, or `dir/name` when loading shaders from disk. A missing file is first
+/// written with the built-in source, so there is something to edit.
+fn shader_source(
+  shader_dir: Option<&Path>,
+  name: &str,
+  builtin: &'static str,
+) -> io::Result<ShaderSource> {
+  let dir = match shader_dir {
+    Some(dir) => dir,
+    None => return Ok(ShaderSource::Static(builtin)),
+  };
+
+  let path = dir.join(name);
+  if !path.exists() {
+    fs::create_dir_all(dir)?;
+    fs::write(&path, builtin)?;
+  }
+  Ok(ShaderSource::File(path))
+}
+
+/// The Mandelbrot settings for this GPU, with the iteration count given by `--iterations`, or
+/// probed once per board and then read from the cache. Expects the quad's buffer and a
+/// framebuffer to draw to be bound.
+fn mandelbrot_settings(
+  context: &GlContext,
+  state: &CubeState,
+  gpu: &Gpu,
+  args: &Args,
+  vertex: &ShaderSource,
+  fragment: &ShaderSource,
+) -> gr_support::Result<Mandelbrot> {
+  let videocore = gpu.videocore.unwrap_or_else(|| {
+    eprintln!("warning: unknown GPU, sizing the shaders for a VideoCore IV");
+    VideoCore::Vc4
+  });
+  let settings = *Mandelbrot::for_videocore(videocore);
+  if let Some(count) = args.iterations {
+    return Ok(settings.with_max_iterations(count));
+  }
+
+  let board = gpu.to_string();
+  let cache = probe::Cache::user();
+  if let (false, Some(cache)) = (args.probe, &cache) {
+    if let Some(count) = cache.get(&board) {
+      println!(
+        "Mandelbrot: {} iterations, cached in {}",
+        count,
+        cache.path().display()
+      );
+      return Ok(settings.with_max_iterations(count));
+    }
+  }
+
+  let cx = state.screen_width as gl::GLfloat / 2.0;
+  let cy = state.screen_height as gl::GLfloat / 2.0;
+  let probed = probe::search(settings.max_iterations, |count| {
+    let candidate = settings.with_max_iterations(count);
+    mandelbrot_works(context, vertex, fragment, &candidate, cx, cy)
+  })?;
+
+  match probed {
+    Some(count) => {
+      println!("Mandelbrot: {} iterations, probed", count);
+      if let Some(cache) = &cache {
+        if let Err(err) = cache.set(&board, count) {
+          eprintln!("warning: {}: {}", cache.path().display(), err);
+        }
+      }
+      Ok(settings.with_max_iterations(count))
+    }
+    // Most likely a broken shader, building it for real reports why
+    None => Ok(settings),
+  }
+}
+
+/// Whether the Mandelbrot shader builds and renders with `mandelbrot`. Running out of memory or
+/// failing to compile or link means it does not, other errors stop the probe.
+fn mandelbrot_works(
+  context: &GlContext,
+  vertex: &ShaderSource,
+  fragment: &ShaderSource,
+  mandelbrot: &Mandelbrot,
+  cx: gl::GLfloat,
+  cy: gl::GLfloat,
+) -> gr_support::Result<bool> {
+  let preprocessor = mandelbrot.preprocessor();
+  let vertex = vertex.load(&preprocessor, ShaderStage::Vertex)?;
+  let fragment = fragment.load(&preprocessor, ShaderStage::Fragment)?;
+
+  let drawn = ShaderProgram::builder()
+    .vertex(&vertex)
+    .fragment(&fragment)
+    .build(context)
+    .and_then(|program| {
+      let locations: MandelbrotLocations = program.bind()?;
+      draw_mandelbrot(&program, &locations, cx, cy, 0.003)
+    });
+
+  match drawn {
+    Ok(()) => Ok(true),
+    Err(Error::Gl {
+      error: GlError::OutOfMemory,
+      ..
+    })
+    | Err(Error::Shader(ShaderError::Compile { .. }))
+    | Err(Error::Shader(ShaderError::Link { .. })) => Ok(false),
+    Err(err) => Err(err),
+  }
+}
+
+pub fn init_shaders<'ctx>(
+  context: &'ctx GlContext,
+  state: &mut CubeState,
+  gpu: &Gpu,
+  args: &Args,
+) -> gr_support::Result<Scene<'ctx>> {
+  let verbose = args.verbose > 0;
+  let shader_dir = args.shader_dir.as_deref();
+  let vshader = shader_source(shader_dir, "vshader.glsl", VSHADER_SOURCE)?;
+  // Included by mandelbrot.glsl, seeded so it can be edited too
+  shader_source(shader_dir, "hsl2rgb.glsl", HSL2RGB_SOURCE)?;
+  let mandelbrot_fshader = shader_source(shader_dir, "mandelbrot.glsl", MANDELBROT_FSHADER_SOURCE)?;
+
+  gl::clear_color(0.0, 1.0, 1.0, 1.0);
+
+  let buf = Buffer::new(context)?;
+
+  // Prepare a texture image
+  let tex = Texture::new(context)?;
+  tex.image_2d(
+    gl::GL_RGB,                         /* internal_format */
+    state.screen_width as gl::GLsizei,  /* width */
+    state.screen_height as gl::GLsizei, /* height */
+    gl::GL_RGB,                         /* src_format */
+    gl::GL_UNSIGNED_SHORT_5_6_5,        /* src_type */
+    &[] as &[gl::GLchar; 0],            /* buffer */
+  )?;
+
+  tex.parameter(gl::GL_TEXTURE_MIN_FILTER, gl::GL_NEAREST)?;
+  tex.parameter(gl::GL_TEXTURE_MAG_FILTER, gl::GL_NEAREST)?;
+
+  // Prepare a framebuffer for rendering
+  let tex_fb = Framebuffer::new(context)?;
+  tex_fb.attach_texture(gl::GL_COLOR_ATTACHMENT0, &tex)?;
+
+  // Prepare viewport
+  gl::viewport(0, 0, state.screen_width as i32, state.screen_height as i32);
+  gl_check()?;
+
+  // Upload vertex data to a buffer
+  buf.upload(gl::GL_ARRAY_BUFFER, &VERTEX_DATA, gl::GL_STATIC_DRAW)?;
+
+  // Test renders go to the texture, which is drawn over before it is shown
+  tex_fb.bind()?;
+  buf.bind(gl::GL_ARRAY_BUFFER)?;
+  let mandelbrot = mandelbrot_settings(context, state, gpu, args, &vshader, &mandelbrot_fshader)?;
+  Framebuffer::bind_default()?;
+
+  // julia
+  let julia = ReloadingProgram::new(
+    context,
+    vshader.clone(),
+    shader_source(shader_dir, "julia.glsl", JULIA_FSHADER_SOURCE)?,
+    Preprocessor::new(),
+    verbose,
+  )?;
+
+  // mandelbrot
+  let mandelbrot = ReloadingProgram::new(
+    context,
+    vshader,
+    mandelbrot_fshader,
+    mandelbrot.preprocessor(),
+    verbose,
+  )?;
+
+  Ok(Scene {
+    julia,
+    mandelbrot,
+    tex_fb,
+    tex,
+    buf,
+  })
+}
+
+/// Points `vertex` at the quad in the bound buffer. Done on every draw, as a reloaded program
+/// may have moved its attributes.
+fn set_vertex_pointer(vertex: Attribute<Vec4>) -> gr_support::Result<()> {
+  gl::vertex_attrib_pointer_offset(
+    vertex.location, /* index */
+    4,               /* size */
+    gl::GL_FLOAT,    /* type */
+    false,           /* normalized */
+    16,              /* stride */
+    0,               /* offset */
+  );
+  gl::enable_vertex_attrib_array(vertex.location);
+  gl_check()
+}
+
+pub fn draw_mandelbrot_to_texture(
+  scene: &Scene,
+  cx: gl::GLfloat,
+  cy: gl::GLfloat,
+  scale: gl::GLfloat,
+) -> gr_support::Result<()> {
+  // Draw the mandelbrot to a texture
+  scene.tex_fb.bind()?;
+  scene.buf.bind(gl::GL_ARRAY_BUFFER)?;
+
+  draw_mandelbrot(
+    scene.mandelbrot.program(),
+    scene.mandelbrot.bindings(),
+    cx,
+    cy,
+    scale,
+  )
+}
+
+/// Draws the Mandelbrot set straight to the screen, without the Julia set over it.
+pub fn draw_mandelbrot_to_screen(
+  scene: &Scene,
+  cx: gl::GLfloat,
+  cy: gl::GLfloat,
+  scale: gl::GLfloat,
+) -> gr_support::Result<()> {
+  Framebuffer::bind_default()?;
+  gl::clear(gl::GL_COLOR_BUFFER_BIT | gl::GL_DEPTH_BUFFER_BIT);
+  scene.buf.bind(gl::GL_ARRAY_BUFFER)?;
+
+  draw_mandelbrot(
+    scene.mandelbrot.program(),
+    scene.mandelbrot.bindings(),
+    cx,
+    cy,
+    scale,
+  )?;
+  gl::bind_buffer(gl::GL_ARRAY_BUFFER, 0);
+  gl_check()
+}
+
+/// Draws the quad in the bound buffer to the bound framebuffer, and waits for it.
+fn draw_mandelbrot(
+  program: &ShaderProgram,
+  locations: &MandelbrotLocations,
+  cx: gl::GLfloat,
+  cy: gl::GLfloat,
+  scale: gl::GLfloat,
+) -> gr_support::Result<()> {
+  program.use_program()?;
+
+  set_vertex_pointer(locations.vertex)?;
+  locations.scale.set(scale, scale);
+  locations.centre.set(cx, cy);
+  gl_check()?;
+  gl::draw_arrays(gl::GL_TRIANGLE_FAN, 0, 4);
+  gl_check()?;
+
+  gl::flush();
+  gl::finish();
+  gl_check()
+}
+
+pub fn draw_triangles(
+  scene: &Scene,
+  cx: gl::GLfloat,
+  cy: gl::GLfloat,
+  scale: gl::GLfloat,
+  x: i32,
+  y: i32,
+) -> gr_support::Result<()> {
+  // Now render to the main frame buffer
+  Framebuffer::bind_default()?;
+  // // Clear the background (not really necessary I suppose)
+  gl::clear(gl::GL_COLOR_BUFFER_BIT | gl::GL_DEPTH_BUFFER_BIT);
+  gl_check()?;
+
+  scene.buf.bind(gl::GL_ARRAY_BUFFER)?;
+  scene.julia.program().use_program()?;
+  scene.tex.bind()?;
+  let locations = scene.julia.bindings();
+  set_vertex_pointer(locations.vertex)?;
+  if let Some(color) = &locations.color {
+    color.set(0.5, 0.5, 0.8, 1.0);
+  }
+  locations.scale.set(scale, scale);
+  locations.offset.set(x as gl::GLfloat, y as gl::GLfloat);
+  locations.centre.set(cx, cy);
+  locations.tex.set(0); // I don't really understand this part, perhaps it relates to active texture?
+  gl_check()?;
+
+  gl::draw_arrays(gl::GL_TRIANGLE_FAN, 0, 4);
+  gl_check()?;
+
+  gl::bind_buffer(gl::GL_ARRAY_BUFFER, 0);
+
+  gl::flush();
+  gl::finish();
+  gl_check()
+}
+
+/// Draws the Mandelbrot set, with the Julia set for the point under the mouse over it.
+#[derive(Parser)]
+#[clap(after_help = gr_support::ENV_HELP)]
+pub struct Args {
+  /// Load the shaders from DIR and reload them whenever they are saved. Missing files are
+  /// written with the built-in shaders first.
+  #[clap(long = "shaders", value_name = "DIR")]
+  pub shader_dir: Option<PathBuf>,
+  /// Find out how many Mandelbrot iterations the GPU manages again, instead of using the count
+  /// cached for this board.
+  #[clap(long)]
+  pub probe: bool,
+  /// Use N Mandelbrot iterations, without probing.
+  #[clap(long, value_name = "N", conflicts_with = "probe")]
+  pub iterations: Option<u32>,
+  /// Stop after N frames instead of on a mouse click or a key.
+  #[clap(long, value_name = "N")]
+  pub frames: Option<u64>,
+  /// Where the fractals are centred, in pixels from the bottom left. The middle of the screen by
+  /// default.
+  #[clap(long, value_name = "X,Y", value_parser = point)]
+  pub centre: Option<(f32, f32)>,
+  /// The width of a pixel in the complex plane.
+  #[clap(long, default_value_t = 0.003)]
+  pub scale: f32,
+  /// Where the mouse starts, in pixels.
+  #[clap(long, value_name = "X,Y", value_parser = point, default_value = "800,400")]
+  pub start: (f32, f32),
+  /// The mouse to follow: an evdev node such as /dev/input/event0, a link to one in
+  /// /dev/input/by-id, or a legacy one such as /dev/input/mouse0. Every mouse by default.
+  #[clap(long, value_name = "DEVICE")]
+  pub mouse: Option<PathBuf>,
+  /// Bind keys as FILE says, a line such as `zoom-in = kpplus equal` for each action to change.
+  /// The actions are quit, pan-left, pan-right, pan-up, pan-down, zoom-in, zoom-out, reset,
+  /// screenshot and julia.
+  #[clap(long, value_name = "FILE")]
+  pub keys: Option<PathBuf>,
+  /// Save screenshots to PATH instead of hello_triangle2-{frame}.png.
+  #[clap(long, value_name = "PATH")]
+  pub capture: Option<String>,
+  /// Also write the frame timings printed on exit to FILE as JSON, - for stdout.
+  #[clap(long, value_name = "FILE")]
+  pub stats: Option<PathBuf>,
+  /// Print the info logs of shaders that built, which may hold driver warnings.
+  #[clap(short, long, action = ArgAction::Count)]
+  pub verbose: u8,
+}
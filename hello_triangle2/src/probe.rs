@@ -0,0 +1,106 @@
+//! Finds how many Mandelbrot iterations the GPU manages, and remembers it per board.
+//!
+//! VC4 drivers give up past a few dozen iterations, with `GL_OUT_OF_MEMORY` or a shader that
+//! does not compile or link, and where exactly depends on the board and driver. Probing takes a
+//! handful of shader builds, so the result is cached.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The largest count in `1..=ceiling` for which `works` returns true, assuming that anything
+/// below a working count works too. `None` when not even 1 does.
+///
+/// Halves down from `ceiling` until a count works, then bisects up to the last one that did not.
+/// Errors from `works` are not failed attempts, they stop the probe.
+pub fn search<E, F>(ceiling: u32, mut works: F) -> Result<Option<u32>, E>
+where
+  F: FnMut(u32) -> Result<bool, E>,
+{
+  let mut failed = ceiling.saturating_add(1);
+  let mut count = ceiling;
+  let working = loop {
+    if count == 0 {
+      return Ok(None);
+    }
+    if works(count)? {
+      break count;
+    }
+    failed = count;
+    count /= 2;
+  };
+
+  // `working` works and `failed` does not, look for the boundary in between
+  let mut low = working;
+  let mut high = failed;
+  while high - low > 1 {
+    let middle = low + (high - low) / 2;
+    if works(middle)? {
+      low = middle;
+    } else {
+      high = middle;
+    }
+  }
+
+  Ok(Some(low))
+}
+
+/// Probed iteration counts, one `count<TAB>board` line per board.
+pub struct Cache {
+  path: PathBuf,
+}
+
+impl Cache {
+  pub fn new<P: Into<PathBuf>>(path: P) -> Cache {
+    Cache { path: path.into() }
+  }
+
+  /// `$XDG_CACHE_HOME/hello_triangle2/mandelbrot-iterations`, or under `~/.cache`.
+  pub fn user() -> Option<Cache> {
+    let dir = match env::var_os("XDG_CACHE_HOME") {
+      Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+      _ => PathBuf::from(env::var_os("HOME")?).join(".cache"),
+    };
+    Some(Cache::new(
+      dir.join("hello_triangle2").join("mandelbrot-iterations"),
+    ))
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  pub fn get(&self, board: &str) -> Option<u32> {
+    let contents = fs::read_to_string(&self.path).ok()?;
+    contents.lines().find_map(|line| {
+      let (count, name) = split_line(line)?;
+      if name == board {
+        count.parse().ok()
+      } else {
+        None
+      }
+    })
+  }
+
+  /// Records `count` for `board`, replacing what was there.
+  pub fn set(&self, board: &str, count: u32) -> io::Result<()> {
+    let contents = fs::read_to_string(&self.path).unwrap_or_default();
+    let mut lines: Vec<String> = contents
+      .lines()
+      .filter(|line| !matches!(split_line(line), Some((_, name)) if name == board))
+      .map(str::to_string)
+      .collect();
+    lines.push(format!("{}\t{}", count, board));
+
+    if let Some(dir) = self.path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    fs::write(&self.path, lines.join("\n") + "\n")
+  }
+}
+
+fn split_line(line: &str) -> Option<(&str, &str)> {
+  let mut parts = line.splitn(2, '\t');
+  Some((parts.next()?, parts.next()?))
+}
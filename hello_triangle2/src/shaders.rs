@@ -0,0 +1,66 @@
+//! GLSL ES 1.00 sources, also checked offline by `tests/shaders.rs`.
+//!
+//! The sources live in `shaders/`, where `--shaders` can point to edit them live. Both GPUs run
+//! the same Mandelbrot shader, [`Mandelbrot`] holds the `#define`s that differ.
+
+#![allow(dead_code)]
+
+use gr_support::gpu::VideoCore;
+use gr_support::preprocess::Preprocessor;
+
+pub const VSHADER_SOURCE: &str = include_str!("../shaders/vshader.glsl");
+pub const JULIA_FSHADER_SOURCE: &str = include_str!("../shaders/julia.glsl");
+pub const MANDELBROT_FSHADER_SOURCE: &str = include_str!("../shaders/mandelbrot.glsl");
+pub const HSL2RGB_SOURCE: &str = include_str!("../shaders/hsl2rgb.glsl");
+
+/// The `#define`s of `mandelbrot.glsl`.
+#[derive(Clone, Copy, Debug)]
+pub struct Mandelbrot {
+  /// What the GPU should manage, `probe` finds out what it really does.
+  pub max_iterations: u32,
+  /// GLSL expression for the color of a pixel that escaped after `i` iterations.
+  pub color: &'static str,
+}
+
+impl Mandelbrot {
+  /*
+   * VC4 (Raspberry Pi up to 3) runs out of memory (GL_OUT_OF_MEMORY) somewhere past a few
+   * dozen iterations, 18 on the firmware driver. The probe starts from 64.
+   */
+  pub const VC4: Mandelbrot = Mandelbrot {
+    max_iterations: 64,
+    color: "vec4(float(i) / float(MAX_ITERATIONS), 0, 0, 1)",
+  };
+
+  /*
+   * VC6 (Raspberry Pi 4+) can do thousands of iterations, the demo will just take longer
+   * to do the initial render.
+   */
+  pub const VC6: Mandelbrot = Mandelbrot {
+    max_iterations: 512,
+    color: "float(i > 0) * hsl2rgb(vec3(float(i) / 360.0, 1.0, 0.5), 1.0)",
+  };
+
+  pub fn for_videocore(videocore: VideoCore) -> &'static Mandelbrot {
+    match videocore {
+      VideoCore::Vc4 => &Mandelbrot::VC4,
+      VideoCore::Vc6 => &Mandelbrot::VC6,
+    }
+  }
+
+  pub fn with_max_iterations(self, max_iterations: u32) -> Mandelbrot {
+    Mandelbrot {
+      max_iterations,
+      ..self
+    }
+  }
+
+  /// Defines `MAX_ITERATIONS` and `COLOR(i)`. `hsl2rgb.glsl` is looked for next to the shader
+  /// first, then falls back to the built-in copy.
+  pub fn preprocessor(&self) -> Preprocessor {
+    Preprocessor::new()
+      .snippet("hsl2rgb.glsl", HSL2RGB_SOURCE)
+      .define("MAX_ITERATIONS", self.max_iterations)
+      .define("COLOR(i)", self.color)
+  }
+}
@@ -0,0 +1,47 @@
+//! The command line.
+
+use clap::Parser;
+use hello_triangle2::Args;
+use std::path::Path;
+
+fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+  Args::try_parse_from(Some("hello_triangle2").iter().chain(args))
+}
+
+#[test]
+fn defaults_to_the_original_demo() {
+  let args = parse(&[]).unwrap();
+  assert_eq!(args.centre, None);
+  assert_eq!(args.scale, 0.003);
+  assert_eq!(args.start, (800.0, 400.0));
+  assert_eq!(args.mouse, None);
+  assert_eq!(args.verbose, 0);
+}
+
+#[test]
+fn parses_options() {
+  let args = parse(&[
+    "--centre",
+    "400,300",
+    "--scale",
+    "0.0015",
+    "--mouse",
+    "/dev/input/mouse1",
+    "--frames",
+    "60",
+    "-vv",
+  ])
+  .unwrap();
+  assert_eq!(args.centre, Some((400.0, 300.0)));
+  assert_eq!(args.scale, 0.0015);
+  assert_eq!(args.mouse.as_deref(), Some(Path::new("/dev/input/mouse1")));
+  assert_eq!(args.frames, Some(60));
+  assert_eq!(args.verbose, 2);
+}
+
+#[test]
+fn rejects_bad_options() {
+  assert!(parse(&["--centre", "400"]).is_err());
+  assert!(parse(&["--probe", "--iterations", "32"]).is_err());
+  assert!(parse(&["--frobnicate"]).is_err());
+}
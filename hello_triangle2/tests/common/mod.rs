@@ -0,0 +1,145 @@
+//! The shaders in `shaders/`, in Rust, and the example's frame, for the tests that draw on the
+//! CPU, and a directory for those that write files. Each test uses only some of them.
+
+#![allow(dead_code)]
+
+use clap::Parser;
+use gr_support::gpu::{Driver, Gpu, VideoCore};
+use gr_support::soft::{Fragment, Soft, VertexOutput};
+use gr_support::GlContext;
+use hello_triangle2::shaders::{
+  Mandelbrot, JULIA_FSHADER_SOURCE, MANDELBROT_FSHADER_SOURCE, VSHADER_SOURCE,
+};
+use hello_triangle2::{
+  draw_mandelbrot_to_texture, draw_triangles, init_ogl, init_shaders, Args, CubeState,
+};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+/// `hsl2rgb.glsl`, with GLSL's `mod`.
+fn hsl2rgb([h, s, l]: [f32; 3], a: f32) -> [f32; 4] {
+  let channel = |shift: f32| {
+    let x = h * 6.0 + shift;
+    let x = x - 6.0 * (x / 6.0).floor();
+    ((x - 3.0).abs() - 1.0).clamp(0.0, 1.0)
+  };
+  let chroma = 1.0 - (2.0 * l - 1.0).abs();
+  let [r, g, b] = [channel(0.0), channel(4.0), channel(2.0)];
+  [
+    l + s * (r - 0.5) * chroma,
+    l + s * (g - 0.5) * chroma,
+    l + s * (b - 0.5) * chroma,
+    a,
+  ]
+}
+
+fn vec2(fragment: &Fragment, name: &str) -> [f32; 2] {
+  let value = fragment.uniform(name);
+  [value[0], value[1]]
+}
+
+fn julia(fragment: &Fragment) -> [f32; 4] {
+  let [scale, centre, offset] = [
+    vec2(fragment, "scale"),
+    vec2(fragment, "centre"),
+    vec2(fragment, "offset"),
+  ];
+  let [x, y] = fragment.frag_coord;
+  let mut a = [(x - centre[0]) * scale[0], (y - centre[1]) * scale[1]];
+  let c = [
+    (offset[0] - centre[0]) * scale[0],
+    (offset[1] - centre[1]) * scale[1],
+  ];
+  let t2 = [
+    fragment.varyings[0] + (offset[0] - centre[0]) * (0.5 / centre[1]),
+    fragment.varyings[1] + (offset[1] - centre[1]) * (0.5 / centre[0]),
+  ];
+
+  let mut i = 0;
+  for i2 in 1..16 {
+    a = [a[0] * a[0] - a[1] * a[1] + c[0], 2.0 * a[0] * a[1] + c[1]];
+    if a[0] * a[0] + a[1] * a[1] > 16.0 {
+      i = i2;
+      break;
+    }
+  }
+
+  let texel = fragment.texture_2d("tex", t2);
+  [
+    texel[0],
+    i as f32 * 0.0625 + texel[1],
+    texel[2],
+    1.0 + texel[3],
+  ]
+}
+
+fn mandelbrot(fragment: &Fragment) -> [f32; 4] {
+  assert_eq!(fragment.define("COLOR(i)"), Some(Mandelbrot::VC6.color));
+  let max_iterations: u32 = fragment.define("MAX_ITERATIONS").unwrap().parse().unwrap();
+  let [scale, centre] = [vec2(fragment, "scale"), vec2(fragment, "centre")];
+  let [x, y] = fragment.frag_coord;
+  let c = [(x - centre[0]) * scale[0], (y - centre[1]) * scale[1]];
+  let mut a = c;
+
+  let mut i = 0;
+  for i2 in 1..max_iterations {
+    a = [a[0] * a[0] - a[1] * a[1] + c[0], 2.0 * a[0] * a[1] + c[1]];
+    if a[0] * a[0] + a[1] * a[1] > 16.0 {
+      i = i2;
+      break;
+    }
+  }
+
+  let escaped = if i > 0 { 1.0 } else { 0.0 };
+  let [r, g, b, a] = hsl2rgb([i as f32 / 360.0, 1.0, 0.5], 1.0);
+  [escaped * r, escaped * g, escaped * b, escaped * a]
+}
+
+pub fn soft(width: u32, height: u32) -> Soft {
+  Soft::new(width, height)
+    .vertex_shader(VSHADER_SOURCE, |vertex| {
+      let position = vertex.attribute("vertex");
+      VertexOutput {
+        position,
+        varyings: vec![position[0] * 0.5 + 0.5, position[1] * 0.5 + 0.5],
+      }
+    })
+    .fragment_shader(JULIA_FSHADER_SOURCE, julia)
+    .fragment_shader(MANDELBROT_FSHADER_SOURCE, mandelbrot)
+}
+
+/// Draws the Mandelbrot set to the texture, then the Julia set for the mouse at `(x, y)` over it,
+/// on a VC6 with 32 iterations.
+pub fn draw(context: &GlContext, scale: f32, x: i32, y: i32) {
+  let gpu = Gpu {
+    model: None,
+    videocore: Some(VideoCore::Vc6),
+    driver: Driver::Mesa,
+  };
+  let args = Args::parse_from(["hello_triangle2", "--iterations", "32"]);
+
+  let mut state = CubeState::new();
+  init_ogl(context, &mut state).unwrap();
+  let scene = init_shaders(context, &mut state, &gpu, &args).unwrap();
+  let (cx, cy) = (
+    state.screen_width as f32 / 2.0,
+    state.screen_height as f32 / 2.0,
+  );
+  draw_mandelbrot_to_texture(&scene, cx, cy, scale).unwrap();
+  draw_triangles(&scene, cx, cy, scale, x, y).unwrap();
+}
+
+/// An empty directory named after the crate, `name` and the process, for a test to write in.
+/// Tests running at the same time need different names.
+pub fn scratch_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!(
+    "{}-{}-{}",
+    env!("CARGO_PKG_NAME"),
+    name,
+    process::id()
+  ));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).expect("scratch dir");
+  dir
+}
@@ -0,0 +1,82 @@
+//! The keys, and how they move the view.
+
+use gr_support::input::{Event, Key, KeyState};
+use hello_triangle2::controls::{keymap, Action, View};
+
+fn press(key: Key) -> Event {
+  Event::Key {
+    key,
+    state: KeyState::Pressed,
+  }
+}
+
+#[test]
+fn binds_the_keyboard_and_the_keypad() {
+  let keymap = keymap();
+  assert_eq!(keymap.action(Key::ESC), Some(Action::Quit));
+  assert_eq!(keymap.action(Key::KP4), Some(Action::PanLeft));
+  // A keypad's 4, as a terminal sends it
+  assert_eq!(
+    keymap.action(Key::from_char('4').unwrap()),
+    Some(Action::PanLeft)
+  );
+  assert_eq!(keymap.action(Key::KP_PLUS), Some(Action::ZoomIn));
+  assert_eq!(keymap.action(Key::ENTER), Some(Action::Screenshot));
+  assert_eq!(keymap.action(Key::SPACE), Some(Action::Julia));
+  // Every action has a key
+  for &action in &Action::ALL {
+    assert!(!keymap.keys(action).is_empty(), "{:?}", action);
+  }
+}
+
+#[test]
+fn loads_actions_by_name() {
+  let keymap = keymap()
+    .load("quit =\nscreenshot = s kpenter\njulia = kp0")
+    .unwrap();
+  assert_eq!(keymap.actions(&[press(Key::ESC)]), []);
+  assert_eq!(
+    keymap.actions(&[press(Key::S), press(Key::ENTER), press(Key::KP0)]),
+    [Action::Screenshot, Action::Julia]
+  );
+  for &action in &Action::ALL {
+    assert_eq!(action.name().parse(), Ok(action));
+  }
+  assert!(keymap.load("pan = left").is_err());
+}
+
+#[test]
+fn pans_and_zooms_about_the_middle() {
+  let start = View {
+    centre: (400.0, 300.0),
+    scale: 0.004,
+  };
+  // The complex number at a pixel
+  let at = |view: &View, (x, y): (f32, f32)| {
+    (
+      (x - view.centre.0) * view.scale,
+      (y - view.centre.1) * view.scale,
+    )
+  };
+
+  let mut view = start;
+  assert!(view.apply(Action::PanRight, 800.0, 600.0));
+  assert_eq!(view.centre, (320.0, 300.0));
+  assert!(view.apply(Action::PanUp, 800.0, 600.0));
+  assert_eq!(view.centre, (320.0, 240.0));
+  assert!(!view.apply(Action::Julia, 800.0, 600.0));
+
+  let mut view = View {
+    centre: (100.0, 200.0),
+    ..start
+  };
+  let middle = at(&view, (400.0, 300.0));
+  view.apply(Action::ZoomIn, 800.0, 600.0);
+  assert!((view.scale - 0.0032).abs() < 1e-7);
+  let (re, im) = at(&view, (400.0, 300.0));
+  assert!((re - middle.0).abs() < 1e-4 && (im - middle.1).abs() < 1e-4);
+
+  view.apply(Action::ZoomOut, 800.0, 600.0);
+  assert!((view.scale - start.scale).abs() < 1e-7);
+  assert!((view.centre.0 - 100.0).abs() < 1e-3 && (view.centre.1 - 200.0).abs() < 1e-3);
+}
@@ -0,0 +1,22 @@
+//! The Mandelbrot and Julia sets on the software rasterizer, against the PNG in `tests/golden`.
+//!
+//! `GR_UPDATE_GOLDEN=1 cargo test` replaces the reference with what is drawn now.
+
+mod common;
+
+use gr_support::golden::Golden;
+use gr_support::image::Image;
+use gr_support::GlContext;
+
+#[test]
+fn draws_the_julia_set_for_a_mouse_position() {
+  let context = GlContext::with_target(common::soft(160, 120));
+  // The whole Mandelbrot set, and the Julia set for c = -0.25 + 0.375i
+  common::draw(&context, 0.025, 70, 75);
+
+  Golden::new(
+    concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"),
+    concat!(env!("CARGO_TARGET_TMPDIR"), "/golden"),
+  )
+  .assert("julia", &Image::read(&context).unwrap());
+}
@@ -0,0 +1,76 @@
+//! The iteration probe's search and cache, without a GPU.
+
+mod common;
+
+use common::scratch_dir;
+use hello_triangle2::probe::{search, Cache};
+use std::fs;
+
+#[test]
+fn finds_the_largest_working_count() {
+  let mut tried = Vec::new();
+  let found = search::<(), _>(512, |count| {
+    tried.push(count);
+    Ok(count <= 18)
+  });
+
+  assert_eq!(found, Ok(Some(18)));
+  assert_eq!(tried, vec![512, 256, 128, 64, 32, 16, 24, 20, 18, 19]);
+}
+
+#[test]
+fn stops_at_the_ceiling_when_it_works() {
+  let mut tried = Vec::new();
+  let found = search::<(), _>(512, |count| {
+    tried.push(count);
+    Ok(true)
+  });
+
+  assert_eq!(found, Ok(Some(512)));
+  assert_eq!(tried, vec![512]);
+}
+
+#[test]
+fn gives_up_when_nothing_works() {
+  assert_eq!(search::<(), _>(64, |_| Ok(false)), Ok(None));
+  assert_eq!(search::<(), _>(0, |_| Ok(true)), Ok(None));
+}
+
+#[test]
+fn errors_stop_the_probe() {
+  let mut tried = 0;
+  let found = search(512, |count| {
+    tried += 1;
+    if count < 100 {
+      Err("context lost")
+    } else {
+      Ok(false)
+    }
+  });
+
+  assert_eq!(found, Err("context lost"));
+  assert_eq!(tried, 4);
+}
+
+#[test]
+fn caches_one_count_per_board() {
+  let dir = scratch_dir("probe");
+  let cache = Cache::new(dir.join("cache").join("mandelbrot-iterations"));
+
+  let pi3 = "Raspberry Pi 3 Model B Plus Rev 1.3, VideoCore IV, Broadcom firmware driver";
+  let pi4 = "Raspberry Pi 4 Model B Rev 1.4, VideoCore VI, Mesa KMS driver";
+  assert_eq!(cache.get(pi3), None);
+
+  cache.set(pi3, 18).unwrap();
+  cache.set(pi4, 512).unwrap();
+  cache.set(pi3, 17).unwrap();
+
+  assert_eq!(cache.get(pi3), Some(17));
+  assert_eq!(cache.get(pi4), Some(512));
+  assert_eq!(
+    fs::read_to_string(cache.path()).unwrap(),
+    format!("512\t{}\n17\t{}\n", pi4, pi3)
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
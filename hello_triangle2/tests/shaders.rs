@@ -0,0 +1,32 @@
+//! Parses and validates the shaders without a GPU, see `gr_support::validate`.
+
+use gr_support::preprocess::Preprocessor;
+use gr_support::validate::validate_program;
+use hello_triangle2::shaders::{
+  Mandelbrot, JULIA_FSHADER_SOURCE, MANDELBROT_FSHADER_SOURCE, VSHADER_SOURCE,
+};
+
+fn assert_valid(preprocessor: &Preprocessor, fragment: &str) {
+  let vertex = preprocessor
+    .process("vshader.glsl", VSHADER_SOURCE)
+    .unwrap();
+  let fragment = preprocessor.process("fshader.glsl", fragment).unwrap();
+  if let Err(errors) = validate_program(vertex.text(), fragment.text()) {
+    panic!("{:#?}", errors);
+  }
+}
+
+#[test]
+fn julia_program_is_valid_glsl_es() {
+  assert_valid(&Preprocessor::new(), JULIA_FSHADER_SOURCE);
+}
+
+#[test]
+fn vc4_mandelbrot_program_is_valid_glsl_es() {
+  assert_valid(&Mandelbrot::VC4.preprocessor(), MANDELBROT_FSHADER_SOURCE);
+}
+
+#[test]
+fn vc6_mandelbrot_program_is_valid_glsl_es() {
+  assert_valid(&Mandelbrot::VC6.preprocessor(), MANDELBROT_FSHADER_SOURCE);
+}
@@ -0,0 +1,36 @@
+//! The example's two passes on the software rasterizer: the Mandelbrot set to a texture, then the
+//! Julia set over it.
+
+mod common;
+
+use gr_support::{gl, GlContext};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 48;
+
+#[test]
+fn draws_the_julia_set_over_the_mandelbrot_set() {
+  let context = GlContext::with_target(common::soft(WIDTH, HEIGHT));
+  common::draw(&context, 0.0625, WIDTH as i32 / 2, HEIGHT as i32 / 2);
+
+  let mut pixels = vec![0; (WIDTH * HEIGHT * 4) as usize];
+  gl::read_pixels(
+    0,
+    0,
+    WIDTH as i32,
+    HEIGHT as i32,
+    gl::GL_RGBA,
+    gl::GL_UNSIGNED_BYTE,
+    &mut pixels,
+  );
+  let pixel = |x: u32, y: u32| {
+    let i = ((y * WIDTH + x) * 4) as usize;
+    [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+  };
+
+  // Inside both sets
+  assert_eq!(pixel(WIDTH / 2, HEIGHT / 2), [0, 0, 0, 255]);
+  // Both escape after one iteration: red from the texture, green from the Julia set plus the
+  // texture's, quantised to 6 bits
+  assert_eq!(pixel(0, 0), [255, 20, 0, 255]);
+}
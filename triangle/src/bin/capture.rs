@@ -0,0 +1,258 @@
+//! Converts raw screen captures, like the `triangle.raw` the example used to write, and compares
+//! captures with each other.
+
+use clap::{CommandFactory, ErrorKind, Parser, Subcommand};
+use gr_support::args::size;
+use gr_support::image::{Image, RawFormat};
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+
+/// Converts raw captures to images, and compares captures.
+#[derive(Parser)]
+struct Args {
+  #[clap(subcommand)]
+  command: Command,
+  /// The pixels of raw captures: rgba (glReadPixels' RGBA bytes) or rgb565 (16 bit little
+  /// endian). Rows are bottom first, as GL reads them.
+  #[clap(
+    long,
+    global = true,
+    value_name = "FORMAT",
+    value_parser = RawFormat::from_str,
+    default_value = "rgba"
+  )]
+  format: RawFormat,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Saves a raw capture as OUTPUT, a .png, .ppm or .raw going by its extension.
+  Convert {
+    /// The size of the capture, which has no header.
+    #[clap(long, value_name = "WxH", value_parser = size)]
+    size: (u32, u32),
+    /// The raw capture.
+    input: PathBuf,
+    /// Where to save it.
+    output: PathBuf,
+  },
+  /// Compares two captures, and exits with 1 if they differ.
+  Diff {
+    /// The size of raw captures, which have no header. Without it, PNGs are read.
+    #[clap(long, value_name = "WxH", value_parser = size)]
+    size: Option<(u32, u32)>,
+    /// Compare colors by CIE76 delta E rather than by channel.
+    #[clap(long)]
+    perceptual: bool,
+    /// How far a pixel may be off and still count as the same: the largest channel difference
+    /// (0 by default), or the delta E with --perceptual (2.3 by default, about the smallest
+    /// difference people notice).
+    #[clap(long, value_name = "N", value_parser = tolerance)]
+    tolerance: Option<f64>,
+    /// Save the pixels that differ in red over the first capture in grey.
+    #[clap(long, value_name = "DIFF")]
+    output: Option<PathBuf>,
+    /// The capture to compare with, such as a reference.
+    a: PathBuf,
+    /// The capture to compare.
+    b: PathBuf,
+  },
+}
+
+fn tolerance(value: &str) -> Result<f64, String> {
+  match value.parse::<f64>() {
+    Ok(tolerance) if tolerance >= 0.0 => Ok(tolerance),
+    _ => Err(format!("expected a non-negative number, not {:?}", value)),
+  }
+}
+
+/// CIE L*a*b* of an sRGB color, under D65.
+fn lab(rgb: &[u8]) -> [f64; 3] {
+  let linear = |channel: u8| {
+    let c = channel as f64 / 255.0;
+    if c <= 0.04045 {
+      c / 12.92
+    } else {
+      ((c + 0.055) / 1.055).powf(2.4)
+    }
+  };
+  let [r, g, b] = [linear(rgb[0]), linear(rgb[1]), linear(rgb[2])];
+  let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+  let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+  let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+  let f = |t: f64| {
+    if t > 216.0 / 24389.0 {
+      t.cbrt()
+    } else {
+      (24389.0 / 27.0 * t + 16.0) / 116.0
+    }
+  };
+  let (fx, fy, fz) = (f(x), f(y), f(z));
+  [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn delta_e(a: &[u8], b: &[u8]) -> f64 {
+  let (a, b) = (lab(a), lab(b));
+  a.iter()
+    .zip(&b)
+    .map(|(a, b)| (a - b) * (a - b))
+    .sum::<f64>()
+    .sqrt()
+}
+
+/// Prints how `b` differs from `a`, returning whether any pixel is beyond `tolerance`.
+fn diff(
+  a: &Image,
+  b: &Image,
+  perceptual: bool,
+  tolerance: Option<f64>,
+  output: Option<PathBuf>,
+) -> gr_support::Result<bool> {
+  let pixels = a.pixels().len() / 4;
+  let mut squares = [0.0; 4];
+  let mut max_channel = 0;
+  let mut delta_es = Vec::with_capacity(if perceptual { pixels } else { 0 });
+  for (pixel_a, pixel_b) in a.pixels().chunks(4).zip(b.pixels().chunks(4)) {
+    for (channel, (value_a, value_b)) in pixel_a.iter().zip(pixel_b).enumerate() {
+      let difference = (*value_a as i16 - *value_b as i16).unsigned_abs();
+      squares[channel] += (difference * difference) as f64;
+      max_channel = max_channel.max(difference);
+    }
+    if perceptual {
+      delta_es.push(delta_e(pixel_a, pixel_b));
+    }
+  }
+
+  let differing = if perceptual {
+    let tolerance = tolerance.unwrap_or(2.3);
+    if let Some(path) = &output {
+      a.highlight(delta_es.iter().map(|e| *e > tolerance))
+        .save(path)?;
+    }
+    delta_es.iter().filter(|e| **e > tolerance).count()
+  } else {
+    let tolerance = tolerance.unwrap_or(0.0) as u8;
+    match a.diff(b, tolerance) {
+      Some(diff) => {
+        if let Some(path) = &output {
+          diff.image.save(path)?;
+        }
+        diff.differing
+      }
+      None => 0,
+    }
+  };
+
+  println!("pixels:       {}", pixels);
+  println!(
+    "differing:    {} ({:.2}%)",
+    differing,
+    100.0 * differing as f64 / pixels as f64
+  );
+  println!("max channel:  {}", max_channel);
+  let rms: Vec<f64> = squares
+    .iter()
+    .map(|square| (square / pixels as f64).sqrt())
+    .collect();
+  println!(
+    "rms:          r {:.3}  g {:.3}  b {:.3}  a {:.3}",
+    rms[0], rms[1], rms[2], rms[3]
+  );
+  let mse = squares[..3].iter().sum::<f64>() / (3 * pixels) as f64;
+  if mse > 0.0 {
+    println!(
+      "psnr:         {:.2} dB",
+      10.0 * (255.0 * 255.0 / mse).log10()
+    );
+  } else {
+    println!("psnr:         identical");
+  }
+
+  if perceptual {
+    delta_es.sort_by(|a, b| a.total_cmp(b));
+    let mean = delta_es.iter().sum::<f64>() / pixels as f64;
+    let p95 = delta_es[((pixels - 1) as f64 * 0.95).round() as usize];
+    println!(
+      "delta E:      mean {:.3}  p95 {:.3}  max {:.3}",
+      mean,
+      p95,
+      delta_es[pixels - 1]
+    );
+  }
+
+  Ok(differing > 0)
+}
+
+fn run(args: Args) -> gr_support::Result<bool> {
+  let format = args.format;
+  match args.command {
+    Command::Convert {
+      size: (width, height),
+      input,
+      output,
+    } => {
+      Image::load(&input, Some((width, height, format)))?.save(&output)?;
+      Ok(false)
+    }
+    Command::Diff {
+      size,
+      perceptual,
+      tolerance,
+      output,
+      a: a_path,
+      b: b_path,
+    } => {
+      let raw = size.map(|(width, height)| (width, height, format));
+      let a = Image::load(&a_path, raw)?;
+      let b = Image::load(&b_path, raw)?;
+      if (a.width(), a.height()) != (b.width(), b.height()) {
+        println!(
+          "{} is {}x{}, {} is {}x{}",
+          a_path.display(),
+          a.width(),
+          a.height(),
+          b_path.display(),
+          b.width(),
+          b.height()
+        );
+        return Ok(true);
+      }
+      diff(&a, &b, perceptual, tolerance, output)
+    }
+  }
+}
+
+fn main() {
+  let args = Args::parse();
+  // Channels differ by whole steps, so a fractional tolerance would silently round down
+  if let Command::Diff {
+    perceptual: false,
+    tolerance: Some(tolerance),
+    ..
+  } = args.command
+  {
+    if tolerance.fract() != 0.0 || tolerance > 255.0 {
+      Args::command()
+        .error(
+          ErrorKind::InvalidValue,
+          format!(
+            "without --perceptual, --tolerance is a channel difference from 0 to 255, not {}",
+            tolerance
+          ),
+        )
+        .exit();
+    }
+  }
+
+  // Like diff(1): 0 when the same, 1 when different, 2 on trouble
+  match run(args) {
+    Ok(false) => {}
+    Ok(true) => process::exit(1),
+    Err(err) => {
+      eprintln!("{}", err);
+      process::exit(2);
+    }
+  }
+}
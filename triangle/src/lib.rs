@@ -0,0 +1,200 @@
+//! The triangle example's drawing code, shared by the binary and the tests.
+
+use gr_support::bindings::{Attribute, Mat4, Uniform, Vec3, Vec4};
+use gr_support::image::Image;
+use gr_support::{gl, gl_check, Buffer, GlContext, ShaderBindings, ShaderProgram};
+use std::f64::consts::PI;
+use std::mem::size_of;
+use std::path::Path;
+
+pub mod shaders;
+
+use shaders::{FRAGMENT_SHADER_SOURCE, VERTEX_SHADER_SOURCE};
+
+// ----------------------------------------------------------------------------
+
+pub fn mygl_get_viewport(name: gl::GLenum) -> [gl::GLint; 4] {
+  let mut value: [gl::GLint; 4] = [0, 0, 0, 0];
+
+  gl::get_integerv(name, &mut value);
+
+  value
+}
+
+// ----------------------------------------------------------------------------
+
+#[rustfmt::skip]
+const VERTEX_COLOR: [gl::GLfloat; 12] = [
+  1_f32 as gl::GLfloat, 0_f32 as gl::GLfloat, 0_f32 as gl::GLfloat, 1_f32 as gl::GLfloat,
+  0_f32 as gl::GLfloat, 1_f32 as gl::GLfloat, 0_f32 as gl::GLfloat, 1_f32 as gl::GLfloat,
+  0_f32 as gl::GLfloat, 0_f32 as gl::GLfloat, 1_f32 as gl::GLfloat, 1_f32 as gl::GLfloat,
+];
+
+#[derive(ShaderBindings)]
+#[shader(rename_all = "camelCase")]
+pub struct Locations {
+  pub vertex_position: Attribute<Vec3>,
+  pub vertex_color: Attribute<Vec4>,
+  pub projection_matrix: Uniform<Mat4>,
+  pub model_view_matrix: Uniform<Mat4>,
+}
+
+pub struct Env<'ctx> {
+  pub program: ShaderProgram<'ctx>,
+  pub locations: Locations,
+  pub vertex_position_buffer: Buffer<'ctx>,
+  pub vertex_color_buffer: Buffer<'ctx>,
+  pub vertices: [gl::GLfloat; 9],
+}
+
+#[rustfmt::skip]
+#[inline(always)]
+pub fn identity() -> [f32; 16] {
+  [
+    1 as gl::GLfloat, 0 as gl::GLfloat, 0 as gl::GLfloat, 0 as gl::GLfloat,
+    0 as gl::GLfloat, 1 as gl::GLfloat, 0 as gl::GLfloat, 0 as gl::GLfloat,
+    0 as gl::GLfloat, 0 as gl::GLfloat, 1 as gl::GLfloat, 0 as gl::GLfloat,
+    0 as gl::GLfloat, 0 as gl::GLfloat, 0 as gl::GLfloat, 1 as gl::GLfloat,
+  ]
+}
+
+#[rustfmt::skip]
+#[inline]
+pub fn orthographic(
+  top: gl::GLfloat,
+  right: gl::GLfloat,
+  bottom: gl::GLfloat,
+  left: gl::GLfloat,
+  near: gl::GLfloat,
+  far: gl::GLfloat,
+) -> [gl::GLfloat; 16] {
+  let w = right - left;
+  let h = top - bottom;
+  let p = far - near;
+
+  let x = (right + left) / w;
+  let y = (top + bottom) / h;
+  let z = (far + near) / p;
+
+  [
+    (2_f32 / w) as gl::GLfloat, (    0_f32) as gl::GLfloat, (     0_f32) as gl::GLfloat, (0_f32) as gl::GLfloat,
+    (    0_f32) as gl::GLfloat, (2_f32 / h) as gl::GLfloat, (     0_f32) as gl::GLfloat, (0_f32) as gl::GLfloat,
+    (    0_f32) as gl::GLfloat, (    0_f32) as gl::GLfloat, (-2_f32 / p) as gl::GLfloat, (0_f32) as gl::GLfloat,
+    (       -x) as gl::GLfloat, (       -y) as gl::GLfloat, (        -z) as gl::GLfloat, (1_f32) as gl::GLfloat,
+  ]
+}
+
+fn matrices(width: u32, height: u32) -> ([gl::GLfloat; 16], [gl::GLfloat; 16]) {
+  let ratio = (width as f32) / (height as f32);
+  let scale = 3_f32;
+
+  let left = -scale * ratio / 2_f32;
+  let right = scale * ratio / 2_f32;
+  let bottom = -scale / 2_f32;
+  let top = scale / 2_f32;
+
+  let near = -1.0_f32;
+  let far = 1.0_f32;
+
+  let projection = orthographic(top, right, bottom, left, near, far);
+  let model_view = identity();
+
+  return (projection, model_view);
+}
+
+fn compute_triangle(vertices: &mut [gl::GLfloat; 9]) {
+  for i in 0..3 {
+    vertices[i * 3 + 0] = (PI / 2.0 + (i as f64 + 1.0) * 2.0 * PI / 3.0).cos() as f32;
+    vertices[i * 3 + 1] = (PI / 2.0 + (i as f64 + 1.0) * 2.0 * PI / 3.0).sin() as f32;
+    vertices[i * 3 + 2] = 0f32;
+  }
+}
+
+pub fn setup(context: &GlContext) -> gr_support::Result<Env<'_>> {
+  // Clear whole screen (front buffer)
+  gl::clear_color(0.0, 0.0, 0.0, 1.0);
+  gl::clear(gl::GL_COLOR_BUFFER_BIT | gl::GL_DEPTH_BUFFER_BIT);
+
+  gl_check()?;
+
+  // Create a shader program
+  let program = ShaderProgram::builder()
+    .vertex(VERTEX_SHADER_SOURCE)
+    .fragment(FRAGMENT_SHADER_SOURCE)
+    .verbose(true)
+    .build(context)?;
+
+  program.use_program()?;
+
+  // Create Vertex Buffer Object
+  let vertex_position_buffer = Buffer::new(context)?;
+  let vertex_color_buffer = Buffer::new(context)?;
+
+  let mut vertices: [gl::GLfloat; 9] = [0f32; 9];
+  compute_triangle(&mut vertices);
+
+  vertex_position_buffer.upload(gl::GL_ARRAY_BUFFER, &vertices, gl::GL_STATIC_DRAW)?;
+  vertex_color_buffer.upload(gl::GL_ARRAY_BUFFER, &VERTEX_COLOR, gl::GL_STATIC_DRAW)?;
+
+  // Get vertex attribute and uniform locations
+  let locations = program.bind()?;
+
+  Ok(Env {
+    program,
+    locations,
+    vertex_position_buffer,
+    vertex_color_buffer,
+    vertices,
+  })
+}
+
+/// Saves the frame in the back buffer to `path`, as a PNG or a PPM going by its extension.
+pub fn screen_capture<P: AsRef<Path>>(context: &GlContext, path: P) -> gr_support::Result<()> {
+  Image::read(context)?.save(path)
+}
+
+pub fn triangle(context: &GlContext, env: &Env) -> gr_support::Result<()> {
+  let (projection_matrix, model_view_matrix) = matrices((&context).width(), (&context).height());
+
+  env.locations.projection_matrix.set(&projection_matrix);
+  gl_check()?;
+  env.locations.model_view_matrix.set(&model_view_matrix);
+  gl_check()?;
+
+  // Set vertex data - Positions
+  gl::enable_vertex_attrib_array(env.locations.vertex_position.location);
+  gl_check()?;
+
+  env.vertex_position_buffer.bind(gl::GL_ARRAY_BUFFER)?;
+
+  gl::vertex_attrib_pointer_offset(
+    env.locations.vertex_position.location, /* index */
+    3,                                      /* size */
+    gl::GL_FLOAT,                           /* type */
+    false,                                  /* normalized */
+    3 * size_of::<gl::GLfloat>() as i32,    /* stride */
+    0,                                      /* offset */
+  );
+  gl_check()?;
+
+  // Colors
+  env.vertex_color_buffer.bind(gl::GL_ARRAY_BUFFER)?;
+
+  gl::vertex_attrib_pointer_offset(
+    env.locations.vertex_color.location,
+    4,                                   /* num_components */
+    gl::GL_FLOAT,                        /* type_ */
+    false,                               /* normalize */
+    0 * size_of::<gl::GLfloat>() as i32, /* stride */
+    0,                                   /* offset */
+  );
+  gl_check()?;
+  gl::enable_vertex_attrib_array(env.locations.vertex_color.location);
+  gl_check()?;
+
+  // Render a triangle consisting of 3 vertices:
+  gl::draw_arrays(gl::GL_TRIANGLES, 0, 3);
+  gl_check()?;
+
+  Ok(())
+}
@@ -0,0 +1,24 @@
+//! GLSL ES 1.00 sources, also checked offline by `tests/shaders.rs`.
+
+pub const VERTEX_SHADER_SOURCE: &str = "
+attribute mediump vec3 vertexPosition;
+attribute vec4 vertexColor;
+
+uniform mediump mat4 projectionMatrix;
+uniform mediump mat4 modelViewMatrix;
+
+varying mediump vec4 color;
+
+void main() {
+  gl_Position = projectionMatrix * modelViewMatrix * vec4(vertexPosition, 1.0);
+  color = vertexColor;
+}
+";
+
+pub const FRAGMENT_SHADER_SOURCE: &str = "
+varying mediump vec4 color;
+
+void main() {
+  gl_FragColor = color;
+}
+";
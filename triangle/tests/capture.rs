@@ -0,0 +1,117 @@
+//! The `capture` tool, on small raw dumps.
+
+mod common;
+
+use common::scratch_dir;
+use gr_support::image::Image;
+use std::env;
+use std::fs;
+use std::process::{Command, Output};
+
+fn capture(args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_capture"))
+    .args(args)
+    .output()
+    .unwrap()
+}
+
+#[test]
+fn converts_raw_dumps() {
+  let dir = scratch_dir("convert");
+  let (raw, png) = (dir.join("frame.raw"), dir.join("frame.png"));
+  // Bottom row red, top row blue
+  fs::write(&raw, [255, 0, 0, 255, 0, 0, 255, 255]).unwrap();
+
+  let output = capture(&[
+    "convert",
+    "--size",
+    "1x2",
+    raw.to_str().unwrap(),
+    png.to_str().unwrap(),
+  ]);
+  assert!(output.status.success(), "{:?}", output);
+  let image = Image::load(&png, None).unwrap();
+  assert_eq!(image.pixel(0, 0), [0, 0, 255, 255]);
+  assert_eq!(image.pixel(0, 1), [255, 0, 0, 255]);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn diffs_raw_dumps() {
+  let dir = scratch_dir("diff");
+  let (a, b, diff) = (dir.join("a.raw"), dir.join("b.raw"), dir.join("diff.png"));
+  fs::write(&a, [10, 10, 10, 255, 100, 100, 100, 255]).unwrap();
+  fs::write(&b, [10, 10, 12, 255, 100, 140, 100, 255]).unwrap();
+  let args = |extra: &[&str]| {
+    let mut args = vec!["diff", "--size", "2x1", "--output", diff.to_str().unwrap()];
+    args.extend_from_slice(extra);
+    args.push(a.to_str().unwrap());
+    args.push(b.to_str().unwrap());
+    capture(&args)
+  };
+
+  let output = args(&[]);
+  assert_eq!(output.status.code(), Some(1));
+  let stdout = String::from_utf8(output.stdout).unwrap();
+  assert!(stdout.contains("differing:    2 (100.00%)"), "{}", stdout);
+  assert!(stdout.contains("max channel:  40"), "{}", stdout);
+
+  let output = args(&["--tolerance", "2"]);
+  assert_eq!(output.status.code(), Some(1));
+  let stdout = String::from_utf8(output.stdout).unwrap();
+  assert!(stdout.contains("differing:    1 (50.00%)"), "{}", stdout);
+  let image = Image::load(&diff, None).unwrap();
+  assert_eq!(image.pixel(1, 0), [255, 0, 0, 255]);
+
+  // Two levels of blue are below what people notice, forty of green are not
+  let output = args(&["--perceptual"]);
+  assert_eq!(output.status.code(), Some(1));
+  let stdout = String::from_utf8(output.stdout).unwrap();
+  assert!(stdout.contains("differing:    1 (50.00%)"), "{}", stdout);
+  assert!(stdout.contains("delta E:"), "{}", stdout);
+
+  let output = capture(&[
+    "diff",
+    "--size",
+    "2x1",
+    a.to_str().unwrap(),
+    a.to_str().unwrap(),
+  ]);
+  assert_eq!(output.status.code(), Some(0));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rejects_bad_arguments() {
+  assert_eq!(
+    capture(&["convert", "a.raw", "a.png"]).status.code(),
+    Some(2)
+  );
+  assert_eq!(capture(&["frobnicate"]).status.code(), Some(2));
+  assert_eq!(
+    capture(&["diff", "--tolerance", "-1", "a.png", "b.png"])
+      .status
+      .code(),
+    Some(2)
+  );
+  for tolerance in &["2.5", "256"] {
+    let output = capture(&["diff", "--tolerance", tolerance, "a.png", "b.png"]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    assert!(String::from_utf8(output.stderr)
+      .unwrap()
+      .contains("--tolerance is a channel difference"));
+  }
+}
+
+#[test]
+fn prints_help() {
+  for args in &[&["--help"][..], &["diff", "--help"]] {
+    let output = capture(args);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8(output.stdout)
+      .unwrap()
+      .contains("--format"));
+  }
+}
@@ -0,0 +1,41 @@
+//! The shaders in `src/shaders.rs`, in Rust, for the tests that draw on the CPU, and a directory
+//! for those that write files. Each test uses only some of them.
+
+#![allow(dead_code)]
+
+use gr_support::soft::{transform, Soft, VertexOutput};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use triangle::shaders::{FRAGMENT_SHADER_SOURCE, VERTEX_SHADER_SOURCE};
+
+pub fn soft(width: u32, height: u32) -> Soft {
+  Soft::new(width, height)
+    .vertex_shader(VERTEX_SHADER_SOURCE, |vertex| {
+      let [x, y, z, _] = vertex.attribute("vertexPosition");
+      let eye = transform(vertex.uniform("modelViewMatrix"), [x, y, z, 1.0]);
+      VertexOutput {
+        position: transform(vertex.uniform("projectionMatrix"), eye),
+        varyings: vertex.attribute("vertexColor").to_vec(),
+      }
+    })
+    .fragment_shader(FRAGMENT_SHADER_SOURCE, |fragment| {
+      let mut color = [0.0; 4];
+      color.copy_from_slice(fragment.varyings);
+      color
+    })
+}
+
+/// An empty directory named after the crate, `name` and the process, for a test to write in.
+/// Tests running at the same time need different names.
+pub fn scratch_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!(
+    "{}-{}-{}",
+    env!("CARGO_PKG_NAME"),
+    name,
+    process::id()
+  ));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).expect("scratch dir");
+  dir
+}
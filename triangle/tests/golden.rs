@@ -0,0 +1,22 @@
+//! The triangle on the software rasterizer, against the PNG in `tests/golden`.
+//!
+//! `GR_UPDATE_GOLDEN=1 cargo test` replaces the reference with what is drawn now.
+
+mod common;
+
+use gr_support::golden::Golden;
+use gr_support::image::Image;
+use gr_support::GlContext;
+
+#[test]
+fn draws_the_triangle() {
+  let context = GlContext::with_target(common::soft(160, 120));
+  let env = triangle::setup(&context).unwrap();
+  triangle::triangle(&context, &env).unwrap();
+
+  Golden::new(
+    concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"),
+    concat!(env!("CARGO_TARGET_TMPDIR"), "/golden"),
+  )
+  .assert("triangle", &Image::read(&context).unwrap());
+}
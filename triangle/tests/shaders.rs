@@ -0,0 +1,11 @@
+//! Parses and validates the shaders without a GPU, see `gr_support::validate`.
+
+use gr_support::validate::validate_program;
+use triangle::shaders::{FRAGMENT_SHADER_SOURCE, VERTEX_SHADER_SOURCE};
+
+#[test]
+fn triangle_program_is_valid_glsl_es() {
+  if let Err(errors) = validate_program(VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE) {
+    panic!("{:#?}", errors);
+  }
+}
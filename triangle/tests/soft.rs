@@ -0,0 +1,54 @@
+//! Draws the triangle on the CPU, see `gr_support::soft`.
+
+mod common;
+
+use gr_support::{gl, GlContext};
+
+const WIDTH: i32 = 64;
+const HEIGHT: i32 = 48;
+
+fn pixel(pixels: &[u8], x: i32, y: i32) -> [u8; 4] {
+  let start = ((y * WIDTH + x) * 4) as usize;
+  [
+    pixels[start],
+    pixels[start + 1],
+    pixels[start + 2],
+    pixels[start + 3],
+  ]
+}
+
+#[test]
+fn draws_the_triangle() {
+  let context = GlContext::with_target(common::soft(WIDTH as u32, HEIGHT as u32));
+  let env = triangle::setup(&context).unwrap();
+  triangle::triangle(&context, &env).unwrap();
+
+  let mut pixels = vec![0; (WIDTH * HEIGHT * 4) as usize];
+  gl::read_pixels(
+    0,
+    0,
+    WIDTH,
+    HEIGHT,
+    gl::GL_RGBA,
+    gl::GL_UNSIGNED_BYTE,
+    &mut pixels,
+  );
+
+  // The corners are red (bottom left), green (bottom right) and blue (top)
+  let dominant = |[r, g, b, _]: [u8; 4]| {
+    let max = r.max(g).max(b);
+    (r == max, g == max, b == max)
+  };
+  assert_eq!(dominant(pixel(&pixels, 20, 17)), (true, false, false));
+  assert_eq!(dominant(pixel(&pixels, 44, 17)), (false, true, false));
+  assert_eq!(dominant(pixel(&pixels, 32, 37)), (false, false, true));
+
+  // An even mix in the middle, black around
+  let [r, g, b, a] = pixel(&pixels, 32, 24);
+  for channel in [r, g, b] {
+    assert!((75..=95).contains(&channel), "{:?}", [r, g, b]);
+  }
+  assert_eq!(a, 255);
+  assert_eq!(pixel(&pixels, 0, 0), [0, 0, 0, 255]);
+  assert_eq!(pixel(&pixels, 63, 47), [0, 0, 0, 255]);
+}